@@ -0,0 +1,28 @@
+use actix_web::{get, web, Responder};
+
+use crate::{
+    http::message::alarm::list::{AlarmListRequest, AlarmListResponse},
+    sip::handler::SipHandler,
+};
+
+#[get("/alarms")]
+async fn get_alarms(
+    query: web::Query<AlarmListRequest>,
+    sip_handler: web::Data<std::sync::Arc<SipHandler>>,
+) -> impl Responder {
+    let items: Vec<_> = sip_handler
+        .store
+        .list_alarms(&query.gb_code, query.start_ts, query.end_ts, query.limit)
+        .into_iter()
+        .map(|alarm| alarm.into())
+        .collect();
+
+    let result = AlarmListResponse {
+        locate: format!("{}#L{}", file!(), line!()),
+        code: 200,
+        msg: String::from("OK"),
+        total: items.len() as u32,
+        items,
+    };
+    web::Json(result)
+}
@@ -0,0 +1,5 @@
+pub mod list;
+pub use list::get_alarms;
+
+pub mod subscribe;
+pub use subscribe::post_subscribe;
@@ -0,0 +1,37 @@
+use actix_web::{post, web, Responder};
+
+use crate::{
+    http::message::alarm::subscribe::{AlarmSubscribeRequest, AlarmSubscribeResponse},
+    sip::{handler::SipHandler, message::AlarmQuery},
+};
+
+#[post("/alarms/subscribe")]
+async fn post_subscribe(
+    data: web::Json<AlarmSubscribeRequest>,
+    sip_handler: web::Data<std::sync::Arc<SipHandler>>,
+) -> impl Responder {
+    let mut query = AlarmQuery::new(0, &data.gb_code);
+    query.start_alarm_priority = data.start_priority.clone();
+    query.end_alarm_priority = data.end_priority.clone();
+    query.alarm_method = data.alarm_method.clone();
+    query.start_alarm_time = data.start_time.clone();
+    query.end_alarm_time = data.end_time.clone();
+
+    let expires = data.expires.unwrap_or(sip_handler.alarm_subscribe_expires);
+    let (code, msg, expires) = match sip_handler
+        .alarm_subscribe(&data.gb_code, query, expires)
+        .await
+    {
+        Ok(expires) => (200, String::from("OK"), expires),
+        Err((code, msg)) => (code, msg, 0),
+    };
+
+    let result = AlarmSubscribeResponse {
+        locate: format!("{}#L{}", file!(), line!()),
+        code,
+        msg,
+        gb_code: data.gb_code.clone(),
+        expires,
+    };
+    web::Json(result)
+}
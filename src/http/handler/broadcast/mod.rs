@@ -0,0 +1,5 @@
+pub mod start;
+pub use start::post_start;
+
+pub mod stop;
+pub use stop::post_stop;
@@ -0,0 +1,42 @@
+use actix_web::{post, web, Responder};
+
+use crate::{
+    http::message::broadcast::start::{BroadcastStartRequest, BroadcastStartResponse},
+    media,
+    sip::handler::SipHandler,
+};
+
+#[post("/broadcast/start")]
+async fn post_start(
+    data: web::Json<BroadcastStartRequest>,
+    sip_handler: web::Data<std::sync::Arc<SipHandler>>,
+) -> impl Responder {
+    let (code, msg, media) = match sip_handler
+        .broadcast_start(&data.gb_code, &data.channel_id)
+        .await
+    {
+        Ok(media) => (200, String::from("OK"), media),
+        Err((code, msg)) => (code, msg, Default::default()),
+    };
+
+    let result = BroadcastStartResponse {
+        locate: format!("{}#L{}", file!(), line!()),
+        code,
+        msg,
+        gb_code: data.gb_code.clone(),
+        channel_id: data.channel_id.clone(),
+        media_ip: media.media_ip,
+        app: if code == 200 {
+            media::BROADCAST_APP.to_string()
+        } else {
+            String::new()
+        },
+        stream: media.stream,
+        local_port: media.local_port,
+        device_ip: media.device_ip,
+        device_port: media.device_port,
+        ssrc: media.ssrc,
+        payload_type: media.payload_type,
+    };
+    web::Json(result)
+}
@@ -0,0 +1,29 @@
+use actix_web::{post, web, Responder};
+
+use crate::{
+    http::message::broadcast::stop::{BroadcastStopRequest, BroadcastStopResponse},
+    sip::handler::SipHandler,
+};
+
+#[post("/broadcast/stop")]
+async fn post_stop(
+    data: web::Json<BroadcastStopRequest>,
+    sip_handler: web::Data<std::sync::Arc<SipHandler>>,
+) -> impl Responder {
+    let (code, msg) = match sip_handler
+        .broadcast_stop(&data.gb_code, &data.channel_id)
+        .await
+    {
+        Ok(()) => (200, String::from("OK")),
+        Err((code, msg)) => (code, msg),
+    };
+
+    let result = BroadcastStopResponse {
+        locate: format!("{}#L{}", file!(), line!()),
+        code,
+        msg,
+        gb_code: data.gb_code.clone(),
+        channel_id: data.channel_id.clone(),
+    };
+    web::Json(result)
+}
@@ -0,0 +1,53 @@
+use actix_web::{routes, web, Responder};
+
+use crate::{
+    http::message::device::catalog::DeviceCatalogResponse,
+    sip::{handler::SipHandler, utils::query::QueryResponse},
+};
+
+// /device 为原有路径, /devices 与设备列表接口一致
+#[routes]
+#[get("/device/{gb_code}/catalog")]
+#[get("/devices/{gb_code}/catalog")]
+async fn get_catalog(
+    path: web::Path<String>,
+    sip_handler: web::Data<std::sync::Arc<SipHandler>>,
+) -> impl Responder {
+    let gb_code = path.into_inner();
+    let handler = sip_handler.get_ref();
+    let id = &gb_code;
+    let (code, msg, catalog) = match sip_handler
+        .query(id, id, "Catalog", |device, transport, sn| async move {
+            handler
+                .send_catalog_query(
+                    device.socket_addr,
+                    device.tcp_stream,
+                    transport,
+                    &device.branch,
+                    id,
+                    sn,
+                )
+                .await
+        })
+        .await
+    {
+        Ok(QueryResponse::Catalog(catalog)) => (200, String::from("OK"), catalog),
+        Ok(_) => (500, String::from("unexpected response"), Default::default()),
+        Err((code, msg)) => (code, msg, Default::default()),
+    };
+
+    let result = DeviceCatalogResponse {
+        locate: format!("{}#L{}", file!(), line!()),
+        code,
+        msg,
+        gb_code,
+        sum_num: catalog.sum_num,
+        items: catalog
+            .device_list
+            .items
+            .into_iter()
+            .map(|device| device.into())
+            .collect(),
+    };
+    web::Json(result)
+}
@@ -0,0 +1,39 @@
+use actix_web::{get, web, HttpResponse, Responder};
+use bytes::Bytes;
+use tokio::sync::{broadcast, mpsc};
+
+use crate::{http::handler::live::flv::ChannelBody, sip::handler::SipHandler};
+
+// 以 Server-Sent Events 推送目录订阅引起的通道变化
+#[get("/catalog/events")]
+async fn get_catalog_events(sip_handler: web::Data<std::sync::Arc<SipHandler>>) -> impl Responder {
+    let mut changes = sip_handler.catalog_events.subscribe();
+    let (tx, rx) = mpsc::channel::<Bytes>(256);
+    tokio::spawn(async move {
+        loop {
+            match changes.recv().await {
+                Ok(change) => {
+                    let data = serde_json::to_string(&change).unwrap_or_default();
+                    // 客户端断开后发送失败, 结束推送
+                    if tx
+                        .send(Bytes::from(format!("event: catalog\ndata: {}\n\n", data)))
+                        .await
+                        .is_err()
+                    {
+                        break;
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(n)) => {
+                    tracing::warn!("catalog events lagged, skipped: {}", n);
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    });
+
+    HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header(("Cache-Control", "no-cache"))
+        .insert_header(("Access-Control-Allow-Origin", "*"))
+        .body(ChannelBody { rx })
+}
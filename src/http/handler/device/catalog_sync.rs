@@ -0,0 +1,37 @@
+use actix_web::{routes, web, Responder};
+
+use crate::{
+    http::message::device::catalog_sync::DeviceCatalogSyncResponse, sip::handler::SipHandler,
+};
+
+#[routes]
+#[get("/device/{gb_code}/catalog/sync")]
+#[get("/devices/{gb_code}/catalog/sync")]
+async fn get_catalog_sync(
+    path: web::Path<String>,
+    sip_handler: web::Data<std::sync::Arc<SipHandler>>,
+) -> impl Responder {
+    let gb_code = path.into_inner();
+    let (mut code, mut msg) = (200, "OK");
+
+    let state = match sip_handler.store.find_catalog_sync_state(&gb_code) {
+        None => {
+            (code, msg) = (404, "device not found");
+            Default::default()
+        }
+        Some(state) => state,
+    };
+
+    let result = DeviceCatalogSyncResponse {
+        locate: format!("{}#L{}", file!(), line!()),
+        code,
+        msg: msg.to_string(),
+        gb_code,
+        status: state.status.to_string(),
+        sn: state.sn,
+        received: state.received,
+        sum_num: state.sum_num,
+        ts: state.ts,
+    };
+    web::Json(result)
+}
@@ -0,0 +1,30 @@
+use actix_web::{get, web, Responder};
+
+use crate::{http::message::device::channels::DeviceChannelsResponse, sip::handler::SipHandler};
+
+#[get("/devices/{gb_code}/channels")]
+async fn get_channels(
+    path: web::Path<String>,
+    sip_handler: web::Data<std::sync::Arc<SipHandler>>,
+) -> impl Responder {
+    let gb_code = path.into_inner();
+    let (mut code, mut msg) = (200, "OK");
+    let mut items = vec![];
+
+    if sip_handler.store.find_device_record(&gb_code).is_none() {
+        (code, msg) = (404, "device not found");
+    } else if let Some(devices) = sip_handler.store.find_catalog(&gb_code) {
+        // 未完成目录同步时返回空列表
+        items = devices.into_iter().map(|device| device.into()).collect();
+    }
+
+    let result = DeviceChannelsResponse {
+        locate: format!("{}#L{}", file!(), line!()),
+        code,
+        msg: msg.to_string(),
+        gb_code,
+        total: items.len() as u32,
+        items,
+    };
+    web::Json(result)
+}
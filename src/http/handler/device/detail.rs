@@ -0,0 +1,25 @@
+use actix_web::{get, web, Responder};
+
+use crate::{http::message::device::detail::DeviceDetailResponse, sip::handler::SipHandler};
+
+#[get("/devices/{gb_code}")]
+async fn get_device(
+    path: web::Path<String>,
+    sip_handler: web::Data<std::sync::Arc<SipHandler>>,
+) -> impl Responder {
+    let gb_code = path.into_inner();
+    let device = sip_handler.store.find_device_record(&gb_code);
+    let (code, msg) = match device {
+        Some(_) => (200, "OK"),
+        None => (404, "device not found"),
+    };
+
+    let result = DeviceDetailResponse {
+        locate: format!("{}#L{}", file!(), line!()),
+        code,
+        msg: msg.to_string(),
+        gb_code,
+        device: device.map(|device| device.into()),
+    };
+    web::Json(result)
+}
@@ -0,0 +1,50 @@
+use actix_web::{routes, web, Responder};
+
+use crate::{
+    http::message::device::info::DeviceInfoResponse,
+    sip::{handler::SipHandler, utils::query::QueryResponse},
+};
+
+#[routes]
+#[get("/device/{gb_code}/info")]
+#[get("/devices/{gb_code}/info")]
+async fn get_info(
+    path: web::Path<String>,
+    sip_handler: web::Data<std::sync::Arc<SipHandler>>,
+) -> impl Responder {
+    let gb_code = path.into_inner();
+    let handler = sip_handler.get_ref();
+    let id = &gb_code;
+    let (code, msg, info) = match sip_handler
+        .query(id, id, "DeviceInfo", |device, transport, sn| async move {
+            handler
+                .send_device_info_query(
+                    device.socket_addr,
+                    device.tcp_stream,
+                    transport,
+                    &device.branch,
+                    id,
+                    sn,
+                )
+                .await
+        })
+        .await
+    {
+        Ok(QueryResponse::DeviceInfo(data)) => (200, String::from("OK"), data),
+        Ok(_) => (500, String::from("unexpected response"), Default::default()),
+        Err((code, msg)) => (code, msg, Default::default()),
+    };
+
+    let result = DeviceInfoResponse {
+        locate: format!("{}#L{}", file!(), line!()),
+        code,
+        msg,
+        gb_code,
+        device_name: info.device_name,
+        manufacturer: info.manufacturer,
+        model: info.model,
+        firmware: info.firmware,
+        result: info.result,
+    };
+    web::Json(result)
+}
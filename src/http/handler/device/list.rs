@@ -0,0 +1,22 @@
+use actix_web::{get, web, Responder};
+
+use crate::{http::message::device::list::DeviceListResponse, sip::handler::SipHandler};
+
+#[get("/devices")]
+async fn get_devices(sip_handler: web::Data<std::sync::Arc<SipHandler>>) -> impl Responder {
+    let items: Vec<_> = sip_handler
+        .store
+        .list_devices()
+        .into_iter()
+        .map(|device| device.into())
+        .collect();
+
+    let result = DeviceListResponse {
+        locate: format!("{}#L{}", file!(), line!()),
+        code: 200,
+        msg: String::from("OK"),
+        total: items.len() as u32,
+        items,
+    };
+    web::Json(result)
+}
@@ -0,0 +1,26 @@
+pub mod catalog;
+pub use catalog::get_catalog;
+
+pub mod catalog_sync;
+pub use catalog_sync::get_catalog_sync;
+
+pub mod catalog_events;
+pub use catalog_events::get_catalog_events;
+
+pub mod info;
+pub use info::get_info;
+
+pub mod status;
+pub use status::get_status;
+
+pub mod list;
+pub use list::get_devices;
+
+pub mod detail;
+pub use detail::get_device;
+
+pub mod channels;
+pub use channels::get_channels;
+
+pub mod track;
+pub use track::{get_track, post_track_subscribe};
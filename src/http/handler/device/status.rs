@@ -0,0 +1,51 @@
+use actix_web::{routes, web, Responder};
+
+use crate::{
+    http::message::device::status::DeviceStatusResponse,
+    sip::{handler::SipHandler, utils::query::QueryResponse},
+};
+
+#[routes]
+#[get("/device/{gb_code}/status")]
+#[get("/devices/{gb_code}/status")]
+async fn get_status(
+    path: web::Path<String>,
+    sip_handler: web::Data<std::sync::Arc<SipHandler>>,
+) -> impl Responder {
+    let gb_code = path.into_inner();
+    let handler = sip_handler.get_ref();
+    let id = &gb_code;
+    let (code, msg, status) = match sip_handler
+        .query(id, id, "DeviceStatus", |device, transport, sn| async move {
+            handler
+                .send_device_status_query(
+                    device.socket_addr,
+                    device.tcp_stream,
+                    transport,
+                    &device.branch,
+                    id,
+                    sn,
+                )
+                .await
+        })
+        .await
+    {
+        Ok(QueryResponse::DeviceStatus(data)) => (200, String::from("OK"), data),
+        Ok(_) => (500, String::from("unexpected response"), Default::default()),
+        Err((code, msg)) => (code, msg, Default::default()),
+    };
+
+    let result = DeviceStatusResponse {
+        locate: format!("{}#L{}", file!(), line!()),
+        code,
+        msg,
+        gb_code,
+        result: status.result,
+        online: status.online,
+        status: status.status,
+        device_time: status.device_time,
+        encode: status.encode,
+        record: status.record,
+    };
+    web::Json(result)
+}
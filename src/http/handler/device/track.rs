@@ -0,0 +1,66 @@
+use actix_web::{get, post, web, Responder};
+
+use crate::{
+    http::message::device::track::{
+        DeviceTrackRequest, DeviceTrackResponse, DeviceTrackSubscribeRequest,
+        DeviceTrackSubscribeResponse,
+    },
+    sip::handler::SipHandler,
+};
+
+#[get("/devices/{gb_code}/track")]
+async fn get_track(
+    path: web::Path<String>,
+    query: web::Query<DeviceTrackRequest>,
+    sip_handler: web::Data<std::sync::Arc<SipHandler>>,
+) -> impl Responder {
+    let gb_code = path.into_inner();
+    // 设备离线后仍可查询历史轨迹
+    let features = sip_handler
+        .store
+        .list_track(&gb_code, query.from, query.to)
+        .into_iter()
+        .map(|point| point.into())
+        .collect();
+
+    let result = DeviceTrackResponse {
+        collection_type: String::from("FeatureCollection"),
+        locate: format!("{}#L{}", file!(), line!()),
+        code: 200,
+        msg: String::from("OK"),
+        gb_code,
+        features,
+    };
+    web::Json(result)
+}
+
+#[post("/devices/{gb_code}/track/subscribe")]
+async fn post_track_subscribe(
+    path: web::Path<String>,
+    data: web::Json<DeviceTrackSubscribeRequest>,
+    sip_handler: web::Data<std::sync::Arc<SipHandler>>,
+) -> impl Responder {
+    let gb_code = path.into_inner();
+    let interval = data
+        .interval
+        .unwrap_or(sip_handler.mobile_position_interval);
+    let expires = data
+        .expires
+        .unwrap_or(sip_handler.mobile_position_subscribe_expires);
+    let (code, msg, expires) = match sip_handler
+        .mobile_position_subscribe(&gb_code, interval, expires)
+        .await
+    {
+        Ok(expires) => (200, String::from("OK"), expires),
+        Err((code, msg)) => (code, msg, 0),
+    };
+
+    let result = DeviceTrackSubscribeResponse {
+        locate: format!("{}#L{}", file!(), line!()),
+        code,
+        msg,
+        gb_code,
+        expires,
+    };
+    web::Json(result)
+}
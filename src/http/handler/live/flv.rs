@@ -0,0 +1,81 @@
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use actix_web::{
+    body::{BodySize, MessageBody},
+    get, web, HttpResponse, Responder,
+};
+use bytes::Bytes;
+use tokio::sync::{broadcast, mpsc};
+
+use crate::{
+    media::{self, mux::flv::FlvMuxer},
+    sip::handler::SipHandler,
+};
+
+// 由后台任务写入的流式响应体
+pub struct ChannelBody {
+    pub rx: mpsc::Receiver<Bytes>,
+}
+
+impl MessageBody for ChannelBody {
+    type Error = std::convert::Infallible;
+
+    fn size(&self) -> BodySize {
+        BodySize::Stream
+    }
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Bytes, Self::Error>>> {
+        self.rx.poll_recv(cx).map(|data| data.map(Ok))
+    }
+}
+
+#[get("/live/{stream_id}.flv")]
+async fn get_flv(
+    path: web::Path<u32>,
+    sip_handler: web::Data<std::sync::Arc<SipHandler>>,
+) -> impl Responder {
+    let stream_id = path.into_inner();
+    let gb_code = sip_handler.store.find_gb_code(stream_id);
+    let units = if gb_code.is_empty() {
+        None
+    } else {
+        sip_handler
+            .media
+            .subscribe(&media::stream_name(&gb_code, stream_id))
+    };
+    let Some(mut units) = units else {
+        return HttpResponse::NotFound().finish();
+    };
+
+    let (tx, rx) = mpsc::channel::<Bytes>(256);
+    tokio::spawn(async move {
+        let mut muxer = FlvMuxer::new();
+        if tx.send(Bytes::from(FlvMuxer::header())).await.is_err() {
+            return;
+        }
+        loop {
+            match units.recv().await {
+                Ok(unit) => {
+                    let data = muxer.push(&unit);
+                    // 客户端断开后发送失败, 结束转封装
+                    if !data.is_empty() && tx.send(Bytes::from(data)).await.is_err() {
+                        break;
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(n)) => {
+                    tracing::warn!("http-flv lagged, stream_id: {}, skipped: {}", stream_id, n);
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    });
+
+    HttpResponse::Ok()
+        .content_type("video/x-flv")
+        .insert_header(("Access-Control-Allow-Origin", "*"))
+        .body(ChannelBody { rx })
+}
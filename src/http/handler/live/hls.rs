@@ -0,0 +1,78 @@
+use actix_web::{get, web, HttpResponse, Responder};
+
+use crate::{media, sip::handler::SipHandler};
+
+// 首个切片需要两个关键帧, 等待时间按较长的 GOP 估算
+static HLS_WAIT_SECONDS: u64 = 15;
+
+fn find_stream_name(sip_handler: &SipHandler, stream_id: u32) -> Option<String> {
+    let gb_code = sip_handler.store.find_gb_code(stream_id);
+    if gb_code.is_empty() {
+        return None;
+    }
+    Some(media::stream_name(&gb_code, stream_id))
+}
+
+fn media_response(content_type: &str, body: impl Into<bytes::Bytes>) -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type(content_type)
+        .insert_header(("Access-Control-Allow-Origin", "*"))
+        .insert_header(("Cache-Control", "no-cache"))
+        .body(body.into())
+}
+
+#[get("/live/{stream_id}/index.m3u8")]
+async fn get_playlist(
+    path: web::Path<u32>,
+    sip_handler: web::Data<std::sync::Arc<SipHandler>>,
+) -> impl Responder {
+    let Some(stream_name) = find_stream_name(&sip_handler, path.into_inner()) else {
+        return HttpResponse::NotFound().finish();
+    };
+
+    let hls = &sip_handler.media.hls;
+    if !hls.is_running(&stream_name) {
+        match sip_handler.media.subscribe(&stream_name) {
+            Some(units) => hls.start(&stream_name, units),
+            None => return HttpResponse::NotFound().finish(),
+        }
+    }
+
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(HLS_WAIT_SECONDS);
+    loop {
+        if let Some(m3u8) = hls.playlist(&stream_name) {
+            return media_response("application/vnd.apple.mpegurl", m3u8);
+        }
+        if std::time::Instant::now() >= deadline || !hls.is_running(&stream_name) {
+            return HttpResponse::ServiceUnavailable().finish();
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+    }
+}
+
+#[get("/live/{stream_id}/init.mp4")]
+async fn get_init(
+    path: web::Path<u32>,
+    sip_handler: web::Data<std::sync::Arc<SipHandler>>,
+) -> impl Responder {
+    match find_stream_name(&sip_handler, path.into_inner())
+        .and_then(|stream_name| sip_handler.media.hls.init_segment(&stream_name))
+    {
+        Some(data) => media_response("video/mp4", data),
+        None => HttpResponse::NotFound().finish(),
+    }
+}
+
+#[get("/live/{stream_id}/{sequence}.m4s")]
+async fn get_segment(
+    path: web::Path<(u32, u64)>,
+    sip_handler: web::Data<std::sync::Arc<SipHandler>>,
+) -> impl Responder {
+    let (stream_id, sequence) = path.into_inner();
+    match find_stream_name(&sip_handler, stream_id)
+        .and_then(|stream_name| sip_handler.media.hls.segment(&stream_name, sequence))
+    {
+        Some(data) => media_response("video/iso.segment", data),
+        None => HttpResponse::NotFound().finish(),
+    }
+}
@@ -10,14 +10,19 @@ async fn post_keep_alive(
     data: web::Json<LiveKeepAliveRequest>,
     sip_handler: web::Data<std::sync::Arc<SipHandler>>,
 ) -> impl Responder {
-    sip_handler
+    let (code, msg) = if sip_handler
         .store
-        .stream_keep_alive(&data.gb_code, data.stream_id);
+        .stream_keep_alive(&data.gb_code, data.stream_id)
+    {
+        (200, String::from("OK"))
+    } else {
+        (404, String::from("stream not found"))
+    };
 
     let result = LiveKeepAliveResponse {
         locate: format!("{}#L{}", file!(), line!()),
-        code: 0,
-        msg: String::from("OK"),
+        code,
+        msg,
         gb_code: data.gb_code.clone(),
         stream_id: data.stream_id,
     };
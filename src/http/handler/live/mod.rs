@@ -6,3 +6,9 @@ pub use stop::post_stop;
 
 pub mod keep_alive;
 pub use keep_alive::post_keep_alive;
+
+pub mod flv;
+pub use flv::get_flv;
+
+pub mod hls;
+pub use hls::{get_init, get_playlist, get_segment};
@@ -1,70 +1,51 @@
-use actix_web::{post, web, Responder};
+use actix_web::{post, web, HttpRequest, Responder};
 
 use crate::{
     http::message::live::play::{LivePlayRequest, LivePlayResponse},
-    sip::{self, handler::SipHandler},
-    store::InviteResult,
+    media::{self, PlayUrls},
+    sip::handler::SipHandler,
 };
 
 #[post("/live/play")]
 async fn post_play(
+    req: HttpRequest,
     data: web::Json<LivePlayRequest>,
     sip_handler: web::Data<std::sync::Arc<SipHandler>>,
 ) -> impl Responder {
-    let (mut code, mut msg) = (200, "OK");
-
-    let mut id: u32 = 0;
-    let call_id = sip_handler.caller_id_str();
-    let from_tag = sip_handler.tag_new(32);
-    match sip_handler
-        .store
-        .invite(&data.gb_code, &data.channel_id, &call_id, &from_tag, true)
+    let (code, msg, id, answer) = match sip_handler
+        .live_open(&data.gb_code, &data.channel_id, &data.setup_type)
+        .await
     {
-        None => (code, msg) = (404, "device not found"),
-        Some(InviteResult {
-            success,
-            channel_id,
-            stream_id,
-            branch,
-            socket_addr,
-            tcp_stream,
-        }) => {
-            let server_ip = "192.168.31.164";
-            let server_port = 10000;
-            id = stream_id;
-            sip_handler
-                .store
-                .update_stream_server_info(stream_id, server_ip, server_port);
+        Ok((stream_id, answer)) => (200, String::from("OK"), stream_id, answer),
+        Err((code, msg)) => (code, msg, 0, Default::default()),
+    };
 
-            if success {
-                // dispatch
-            }
-            sip_handler
-                .send_invite(sip::request::invite::SendInviteParams {
-                    device_addr: socket_addr,
-                    tcp_stream,
-                    branch,
-                    channel_id,
-                    caller_id: call_id,
-                    from_tag,
-                    media_server_ip: server_ip.to_string(),
-                    media_server_port: server_port,
-                    session_type: sip::message::sdp::SdpSessionType::Play,
-                    gb_code: data.gb_code.clone(),
-                    setup_type: data.setup_type.clone(),
-                    start_ts: 0,
-                    stop_ts: 0,
-                })
-                .await;
-        }
+    // 内置收流节点由本服务输出 HTTP-FLV/HLS, 地址取客户端访问的 Host
+    let urls = if code == 200 {
+        sip_handler
+            .media
+            .play_urls(&media::stream_name(&data.gb_code, id))
+            .unwrap_or_else(|| {
+                let info = req.connection_info();
+                let base = format!("{}://{}", info.scheme(), info.host());
+                PlayUrls {
+                    flv: format!("{}/live/{}.flv", base, id),
+                    hls: format!("{}/live/{}/index.m3u8", base, id),
+                }
+            })
+    } else {
+        PlayUrls::default()
     };
 
     let result = LivePlayResponse {
         locate: format!("{}#L{}", file!(), line!()),
         code,
-        msg: msg.to_string(),
+        msg,
         gb_code: data.gb_code.clone(),
         stream_id: id,
+        flv_url: urls.flv,
+        hls_url: urls.hls,
+        answer: answer.into(),
     };
     web::Json(result)
 }
@@ -0,0 +1,2 @@
+pub mod nodes;
+pub use nodes::get_nodes;
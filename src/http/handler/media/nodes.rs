@@ -0,0 +1,24 @@
+use actix_web::{get, web, Responder};
+
+use crate::{http::message::media::nodes::MediaNodesResponse, sip::handler::SipHandler};
+
+#[get("/media/nodes")]
+async fn get_nodes(sip_handler: web::Data<std::sync::Arc<SipHandler>>) -> impl Responder {
+    let items: Vec<_> = sip_handler
+        .media
+        .nodes()
+        .into_iter()
+        .map(|node| node.into())
+        .collect();
+
+    let result = MediaNodesResponse {
+        locate: format!("{}#L{}", file!(), line!()),
+        code: 200,
+        msg: String::from("OK"),
+        service_id: sip_handler.store.service_id(),
+        strategy: sip_handler.media.strategy.to_string(),
+        total: items.len() as u32,
+        items,
+    };
+    web::Json(result)
+}
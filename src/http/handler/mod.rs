@@ -1,2 +1,10 @@
+pub mod alarm;
+pub mod broadcast;
+pub mod device;
 pub mod live;
+pub mod media;
+pub mod ptz;
+pub mod record;
 pub mod replay;
+pub mod stream;
+pub mod talk;
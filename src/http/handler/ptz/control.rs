@@ -0,0 +1,81 @@
+use std::str::FromStr;
+
+use actix_web::{post, web, Responder};
+
+use crate::{
+    http::message::ptz::control::{PtzControlRequest, PtzControlResponse},
+    sip::{self, handler::SipHandler},
+    store::DeviceInfo,
+};
+
+#[post("/ptz/control")]
+async fn post_control(
+    data: web::Json<PtzControlRequest>,
+    sip_handler: web::Data<std::sync::Arc<SipHandler>>,
+) -> impl Responder {
+    let (code, msg) = match sip::message::PtzCommand::from_str(&data.command) {
+        Err(e) => (400, e),
+        Ok(command) => {
+            let ptz_cmd = sip::message::PtzCmd::from_command(&command, data.speed);
+            send_device_control(&sip_handler, &data.gb_code, &data.channel_id, &ptz_cmd).await
+        }
+    };
+
+    let result = PtzControlResponse {
+        locate: format!("{}#L{}", file!(), line!()),
+        code,
+        msg,
+        gb_code: data.gb_code.clone(),
+        channel_id: data.channel_id.clone(),
+    };
+    web::Json(result)
+}
+
+// 下发 DeviceControl 并等待设备应答, 返回 (code, msg)
+pub async fn send_device_control(
+    sip_handler: &SipHandler,
+    gb_code: &String,
+    channel_id: &str,
+    ptz_cmd: &sip::message::PtzCmd,
+) -> (u32, String) {
+    let Some(DeviceInfo {
+        branch,
+        socket_addr,
+        tcp_stream,
+    }) = sip_handler.store.find_device_by_gb_code(gb_code)
+    else {
+        return (404, String::from("device not found"));
+    };
+
+    let transport = if tcp_stream.is_some() {
+        rsip::Transport::Tcp
+    } else {
+        rsip::Transport::Udp
+    };
+    let call_id = sip_handler.caller_id_str();
+    let receiver = sip_handler.transaction_begin(&call_id);
+    if !sip_handler
+        .send_ptz_control(
+            socket_addr,
+            tcp_stream,
+            transport,
+            &branch,
+            &call_id,
+            gb_code,
+            channel_id,
+            ptz_cmd,
+        )
+        .await
+    {
+        sip_handler.transaction_cancel(&call_id);
+        return (500, String::from("send failed"));
+    }
+
+    match sip_handler.transaction_wait(&call_id, receiver).await {
+        None => (408, String::from("device timeout")),
+        Some(response) => (
+            response.status_code().code() as u32,
+            response.status_code().to_string(),
+        ),
+    }
+}
@@ -0,0 +1,44 @@
+use actix_web::{post, web, Responder};
+
+use super::control::send_device_control;
+use crate::{
+    http::message::ptz::cruise::{PtzCruiseRequest, PtzCruiseResponse},
+    sip::{handler::SipHandler, message::PtzCmd},
+};
+
+#[post("/ptz/cruise")]
+async fn post_cruise(
+    data: web::Json<PtzCruiseRequest>,
+    sip_handler: web::Data<std::sync::Arc<SipHandler>>,
+) -> impl Responder {
+    let ptz_cmd = match data.action.as_str() {
+        "add_point" => Some(PtzCmd::cruise_add_point(data.cruise_id, data.preset_id)),
+        "remove_point" => Some(PtzCmd::cruise_remove_point(data.cruise_id, data.preset_id)),
+        "set_speed" => Some(PtzCmd::cruise_speed(data.cruise_id, data.value)),
+        "set_dwell" => Some(PtzCmd::cruise_dwell(data.cruise_id, data.value)),
+        "start" => Some(PtzCmd::cruise_start(data.cruise_id)),
+        "stop" => Some(PtzCmd::stop()),
+        _ => None,
+    };
+
+    let (code, msg) = match ptz_cmd {
+        None => (
+            400,
+            String::from(
+                "Support lists: add_point, remove_point, set_speed, set_dwell, start, stop",
+            ),
+        ),
+        Some(ptz_cmd) => {
+            send_device_control(&sip_handler, &data.gb_code, &data.channel_id, &ptz_cmd).await
+        }
+    };
+
+    let result = PtzCruiseResponse {
+        locate: format!("{}#L{}", file!(), line!()),
+        code,
+        msg,
+        gb_code: data.gb_code.clone(),
+        channel_id: data.channel_id.clone(),
+    };
+    web::Json(result)
+}
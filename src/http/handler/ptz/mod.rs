@@ -0,0 +1,14 @@
+pub mod control;
+pub use control::post_control;
+
+pub mod preset;
+pub use preset::post_preset;
+
+pub mod preset_query;
+pub use preset_query::post_preset_query;
+
+pub mod cruise;
+pub use cruise::post_cruise;
+
+pub mod scan;
+pub use scan::post_scan;
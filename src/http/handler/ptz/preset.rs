@@ -0,0 +1,37 @@
+use actix_web::{post, web, Responder};
+
+use super::control::send_device_control;
+use crate::{
+    http::message::ptz::preset::{PtzPresetRequest, PtzPresetResponse},
+    sip::{handler::SipHandler, message::PtzCmd},
+};
+
+#[post("/ptz/preset")]
+async fn post_preset(
+    data: web::Json<PtzPresetRequest>,
+    sip_handler: web::Data<std::sync::Arc<SipHandler>>,
+) -> impl Responder {
+    let ptz_cmd = match data.action.as_str() {
+        "set" => Some(PtzCmd::preset_set(data.preset_id)),
+        "call" => Some(PtzCmd::preset_call(data.preset_id)),
+        "delete" => Some(PtzCmd::preset_delete(data.preset_id)),
+        _ => None,
+    };
+
+    let (code, msg) = match ptz_cmd {
+        None => (400, String::from("Support lists: set, call, delete")),
+        Some(_) if data.preset_id == 0 => (400, String::from("preset_id must be 1~255")),
+        Some(ptz_cmd) => {
+            send_device_control(&sip_handler, &data.gb_code, &data.channel_id, &ptz_cmd).await
+        }
+    };
+
+    let result = PtzPresetResponse {
+        locate: format!("{}#L{}", file!(), line!()),
+        code,
+        msg,
+        gb_code: data.gb_code.clone(),
+        channel_id: data.channel_id.clone(),
+    };
+    web::Json(result)
+}
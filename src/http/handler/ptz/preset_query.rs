@@ -0,0 +1,64 @@
+use actix_web::{post, web, Responder};
+
+use crate::{
+    http::message::ptz::preset_query::{
+        PtzPresetItem, PtzPresetQueryRequest, PtzPresetQueryResponse,
+    },
+    sip::{handler::SipHandler, utils::query::QueryResponse},
+};
+
+#[post("/ptz/preset/query")]
+async fn post_preset_query(
+    data: web::Json<PtzPresetQueryRequest>,
+    sip_handler: web::Data<std::sync::Arc<SipHandler>>,
+) -> impl Responder {
+    let handler = sip_handler.get_ref();
+    let (gb_code, channel_id) = (&data.gb_code, &data.channel_id);
+    let (code, msg, presets) = match sip_handler
+        .query(
+            gb_code,
+            channel_id,
+            "PresetQuery",
+            |device, transport, sn| async move {
+                handler
+                    .send_preset_query(
+                        device.socket_addr,
+                        device.tcp_stream,
+                        transport,
+                        &device.branch,
+                        gb_code,
+                        channel_id,
+                        sn,
+                    )
+                    .await
+            },
+        )
+        .await
+    {
+        Ok(QueryResponse::PresetQuery(response)) => (
+            200,
+            String::from("OK"),
+            response
+                .preset_list
+                .items
+                .into_iter()
+                .map(|item| PtzPresetItem {
+                    preset_id: item.preset_id,
+                    preset_name: item.preset_name,
+                })
+                .collect(),
+        ),
+        Ok(_) => (500, String::from("unexpected response"), vec![]),
+        Err((code, msg)) => (code, msg, vec![]),
+    };
+
+    let result = PtzPresetQueryResponse {
+        locate: format!("{}#L{}", file!(), line!()),
+        code,
+        msg,
+        gb_code: data.gb_code.clone(),
+        channel_id: data.channel_id.clone(),
+        presets,
+    };
+    web::Json(result)
+}
@@ -0,0 +1,41 @@
+use actix_web::{post, web, Responder};
+
+use super::control::send_device_control;
+use crate::{
+    http::message::ptz::scan::{PtzScanRequest, PtzScanResponse},
+    sip::{handler::SipHandler, message::PtzCmd},
+};
+
+#[post("/ptz/scan")]
+async fn post_scan(
+    data: web::Json<PtzScanRequest>,
+    sip_handler: web::Data<std::sync::Arc<SipHandler>>,
+) -> impl Responder {
+    let ptz_cmd = match data.action.as_str() {
+        "set_left" => Some(PtzCmd::scan_left_border(data.scan_id)),
+        "set_right" => Some(PtzCmd::scan_right_border(data.scan_id)),
+        "set_speed" => Some(PtzCmd::scan_speed(data.scan_id, data.speed)),
+        "start" => Some(PtzCmd::scan_start(data.scan_id)),
+        "stop" => Some(PtzCmd::stop()),
+        _ => None,
+    };
+
+    let (code, msg) = match ptz_cmd {
+        None => (
+            400,
+            String::from("Support lists: set_left, set_right, set_speed, start, stop"),
+        ),
+        Some(ptz_cmd) => {
+            send_device_control(&sip_handler, &data.gb_code, &data.channel_id, &ptz_cmd).await
+        }
+    };
+
+    let result = PtzScanResponse {
+        locate: format!("{}#L{}", file!(), line!()),
+        code,
+        msg,
+        gb_code: data.gb_code.clone(),
+        channel_id: data.channel_id.clone(),
+    };
+    web::Json(result)
+}
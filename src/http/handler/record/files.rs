@@ -0,0 +1,44 @@
+use actix_web::{get, web, Responder};
+
+use crate::{
+    http::message::record::files::{RecordFilesRequest, RecordFilesResponse},
+    sip::handler::SipHandler,
+};
+
+#[get("/record/files")]
+async fn get_files(
+    query: web::Query<RecordFilesRequest>,
+    sip_handler: web::Data<std::sync::Arc<SipHandler>>,
+) -> impl Responder {
+    let items: Vec<_> = sip_handler
+        .recorder
+        .files(&query.gb_code, &query.channel_id)
+        .into_iter()
+        .map(|file| file.into())
+        .collect();
+
+    let (code, msg) = if sip_handler
+        .recorder
+        .channel_dir(&query.gb_code, &query.channel_id)
+        .is_none()
+    {
+        (400, String::from("invalid gb_code or channel_id"))
+    } else {
+        (200, String::from("OK"))
+    };
+
+    let result = RecordFilesResponse {
+        locate: format!("{}#L{}", file!(), line!()),
+        code,
+        msg,
+        gb_code: query.gb_code.clone(),
+        channel_id: query.channel_id.clone(),
+        recording: sip_handler
+            .recorder
+            .find(&query.gb_code, &query.channel_id)
+            .is_some(),
+        total: items.len() as u32,
+        items,
+    };
+    web::Json(result)
+}
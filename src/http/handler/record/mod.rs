@@ -0,0 +1,8 @@
+pub mod start;
+pub use start::post_start;
+
+pub mod stop;
+pub use stop::post_stop;
+
+pub mod files;
+pub use files::get_files;
@@ -0,0 +1,90 @@
+use std::str::FromStr;
+
+use actix_web::{post, web, Responder};
+
+use crate::{
+    http::message::record::start::{RecordStartRequest, RecordStartResponse},
+    media::{self, recorder::RecordFormat},
+    sip::handler::SipHandler,
+};
+
+#[post("/record/start")]
+async fn post_start(
+    data: web::Json<RecordStartRequest>,
+    sip_handler: web::Data<std::sync::Arc<SipHandler>>,
+) -> impl Responder {
+    let (mut code, mut msg) = (200, String::from("OK"));
+    let mut id = 0;
+
+    let format = if data.format.is_empty() {
+        Ok(sip_handler.recorder.format)
+    } else {
+        RecordFormat::from_str(&data.format)
+    };
+
+    match format {
+        Err(ref e) => (code, msg) = (400, e.clone()),
+        Ok(_)
+            if sip_handler
+                .recorder
+                .channel_dir(&data.gb_code, &data.channel_id)
+                .is_none() =>
+        {
+            (code, msg) = (400, String::from("invalid gb_code or channel_id"))
+        }
+        Ok(format) => {
+            if let Some((stream_id, _, _)) =
+                sip_handler.recorder.find(&data.gb_code, &data.channel_id)
+            {
+                (code, msg, id) = (409, String::from("already recording"), stream_id);
+            } else {
+                // 录像作为一个观看者共享通道的实时流
+                match sip_handler
+                    .live_open(&data.gb_code, &data.channel_id, &data.setup_type)
+                    .await
+                {
+                    Err((c, m)) => (code, msg) = (c, m),
+                    Ok((stream_id, _)) => {
+                        match sip_handler
+                            .media
+                            .subscribe(&media::stream_name(&data.gb_code, stream_id))
+                        {
+                            None => {
+                                sip_handler.stream_close(&data.gb_code, stream_id).await;
+                                (code, msg) =
+                                    (501, String::from("record not supported by media node"));
+                            }
+                            Some(units) => {
+                                if sip_handler.recorder.start(
+                                    sip_handler.get_ref().clone(),
+                                    &data.gb_code,
+                                    &data.channel_id,
+                                    stream_id,
+                                    format,
+                                    units,
+                                ) {
+                                    id = stream_id;
+                                } else {
+                                    sip_handler.stream_close(&data.gb_code, stream_id).await;
+                                    (code, msg) =
+                                        (400, String::from("invalid gb_code or channel_id"));
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    let result = RecordStartResponse {
+        locate: format!("{}#L{}", file!(), line!()),
+        code,
+        msg,
+        gb_code: data.gb_code.clone(),
+        channel_id: data.channel_id.clone(),
+        stream_id: id,
+        format: format.map(|f| f.to_string()).unwrap_or_default(),
+    };
+    web::Json(result)
+}
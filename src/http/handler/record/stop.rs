@@ -0,0 +1,33 @@
+use actix_web::{post, web, Responder};
+
+use crate::{
+    http::message::record::stop::{RecordStopRequest, RecordStopResponse},
+    sip::handler::SipHandler,
+};
+
+#[post("/record/stop")]
+async fn post_stop(
+    data: web::Json<RecordStopRequest>,
+    sip_handler: web::Data<std::sync::Arc<SipHandler>>,
+) -> impl Responder {
+    let (mut code, mut msg) = (200, "OK");
+    let mut id = 0;
+
+    match sip_handler.recorder.stop(&data.gb_code, &data.channel_id) {
+        None => (code, msg) = (404, "record not found"),
+        Some(stream_id) => {
+            id = stream_id;
+            sip_handler.stream_close(&data.gb_code, stream_id).await;
+        }
+    }
+
+    let result = RecordStopResponse {
+        locate: format!("{}#L{}", file!(), line!()),
+        code,
+        msg: msg.to_string(),
+        gb_code: data.gb_code.clone(),
+        channel_id: data.channel_id.clone(),
+        stream_id: id,
+    };
+    web::Json(result)
+}
@@ -6,3 +6,6 @@ pub use stop::post_stop;
 
 pub mod keep_alive;
 pub use keep_alive::post_keep_alive;
+
+pub mod records;
+pub use records::post_records;
@@ -0,0 +1,99 @@
+use actix_web::{post, web, Responder};
+use chrono::{Local, NaiveDateTime, TimeZone};
+
+use crate::{
+    http::message::replay::records::{
+        ReplayRecordItem, ReplayRecordsRequest, ReplayRecordsResponse,
+    },
+    sip::{handler::SipHandler, utils::query::QueryResponse},
+};
+
+// GB28181 时间格式为设备本地时间, 例如 2025-02-11T13:43:16
+static RECORD_TIME_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+
+fn ts_to_record_time(ts: u64) -> String {
+    match Local.timestamp_opt(ts as i64, 0).single() {
+        Some(t) => t.format(RECORD_TIME_FORMAT).to_string(),
+        None => String::new(),
+    }
+}
+
+fn record_time_to_ts(s: &str) -> u64 {
+    NaiveDateTime::parse_from_str(s, RECORD_TIME_FORMAT)
+        .ok()
+        .and_then(|t| Local.from_local_datetime(&t).earliest())
+        .map(|t| t.timestamp().max(0) as u64)
+        .unwrap_or(0)
+}
+
+#[post("/replay/records")]
+async fn post_records(
+    data: web::Json<ReplayRecordsRequest>,
+    sip_handler: web::Data<std::sync::Arc<SipHandler>>,
+) -> impl Responder {
+    let handler = sip_handler.get_ref();
+    let (gb_code, channel_id) = (&data.gb_code, &data.channel_id);
+    let (start_time, end_time) = (
+        ts_to_record_time(data.start_ts),
+        ts_to_record_time(data.stop_ts),
+    );
+    let (start_time, end_time) = (start_time.as_str(), end_time.as_str());
+    let (code, msg, records) = match sip_handler
+        .query(
+            gb_code,
+            channel_id,
+            "RecordInfo",
+            |device, transport, sn| async move {
+                handler
+                    .send_record_info_query(
+                        device.socket_addr,
+                        device.tcp_stream,
+                        transport,
+                        &device.branch,
+                        gb_code,
+                        channel_id,
+                        sn,
+                        start_time,
+                        end_time,
+                    )
+                    .await
+            },
+        )
+        .await
+    {
+        Ok(QueryResponse::RecordInfo(record_info)) => (
+            200,
+            String::from("OK"),
+            record_info
+                .record_list
+                .items
+                .into_iter()
+                .map(|item| ReplayRecordItem {
+                    start_ts: record_time_to_ts(&item.start_time),
+                    stop_ts: record_time_to_ts(&item.end_time),
+                    device_id: item.device_id,
+                    name: item.name,
+                    file_path: item.file_path,
+                    address: item.address,
+                    start_time: item.start_time,
+                    end_time: item.end_time,
+                    secrecy: item.secrecy,
+                    record_type: item.record_type,
+                    recorder_id: item.recorder_id,
+                })
+                .collect(),
+        ),
+        Ok(_) => (500, String::from("unexpected response"), vec![]),
+        Err((code, msg)) => (code, msg, vec![]),
+    };
+
+    let result = ReplayRecordsResponse {
+        locate: format!("{}#L{}", file!(), line!()),
+        code,
+        msg,
+        gb_code: data.gb_code.clone(),
+        channel_id: data.channel_id.clone(),
+        records,
+    };
+    web::Json(result)
+}
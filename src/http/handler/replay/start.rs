@@ -2,8 +2,7 @@ use actix_web::{post, web, Responder};
 
 use crate::{
     http::message::replay::start::{ReplayStartRequest, ReplayStartResponse},
-    sip::{self, handler::SipHandler},
-    store::InviteResult,
+    sip::handler::SipHandler,
 };
 
 #[post("/replay/start")]
@@ -11,60 +10,27 @@ async fn post_start(
     data: web::Json<ReplayStartRequest>,
     sip_handler: web::Data<std::sync::Arc<SipHandler>>,
 ) -> impl Responder {
-    let (mut code, mut msg) = (200, "OK");
-
-    let mut id = 0;
-    let call_id = sip_handler.caller_id_str();
-    let from_tag = sip_handler.tag_new(32);
-    match sip_handler
-        .store
-        .invite(&data.gb_code, &data.channel_id, &call_id, &from_tag, true)
+    let (code, msg, id, answer) = match sip_handler
+        .replay_open(
+            &data.gb_code,
+            &data.channel_id,
+            &data.setup_type,
+            data.start_ts,
+            data.stop_ts,
+        )
+        .await
     {
-        None => (code, msg) = (404, "device not found"),
-        Some(InviteResult {
-            success,
-            stream_id,
-            branch,
-            channel_id,
-            socket_addr,
-            tcp_stream,
-        }) => {
-            let server_ip = "192.168.31.164";
-            let server_port = 10000;
-            id = stream_id;
-            sip_handler
-                .store
-                .update_stream_server_info(stream_id, server_ip, server_port);
-
-            if success {
-                // dispatch
-            }
-            sip_handler
-                .send_invite(sip::request::invite::SendInviteParams {
-                    device_addr: socket_addr,
-                    tcp_stream,
-                    branch,
-                    channel_id,
-                    caller_id: call_id,
-                    from_tag,
-                    media_server_ip: server_ip.to_string(),
-                    media_server_port: server_port,
-                    session_type: sip::message::sdp::SdpSessionType::Playback,
-                    gb_code: data.gb_code.clone(),
-                    setup_type: data.setup_type.clone(),
-                    start_ts: data.start_ts,
-                    stop_ts: data.stop_ts,
-                })
-                .await;
-        }
+        Ok((stream_id, answer)) => (200, String::from("OK"), stream_id, answer),
+        Err((code, msg)) => (code, msg, 0, Default::default()),
     };
 
     let result = ReplayStartResponse {
         locate: format!("{}#L{}", file!(), line!()),
         code,
-        msg: msg.to_string(),
+        msg,
         gb_code: data.gb_code.clone(),
         stream_id: id,
+        answer: answer.into(),
     };
     web::Json(result)
 }
@@ -2,8 +2,7 @@ use actix_web::{post, web, Responder};
 
 use crate::{
     http::message::replay::stop::{ReplayStopRequest, ReplayStopResponse},
-    sip::handler::{SipHandler, SipTransaction},
-    store::ByeResult,
+    sip::handler::SipHandler,
 };
 
 #[post("/replay/stop")]
@@ -11,37 +10,19 @@ async fn post_stop(
     data: web::Json<ReplayStopRequest>,
     sip_handler: web::Data<std::sync::Arc<SipHandler>>,
 ) -> impl Responder {
-    if let Some(ByeResult {
-        success,
-        caller_id,
-        from_tag,
-        to_tag,
-        branch,
-        socket_addr,
-        tcp_stream,
-    }) = sip_handler.store.bye(&data.gb_code, data.stream_id)
+    let (code, msg) = if sip_handler
+        .stream_close(&data.gb_code, data.stream_id)
+        .await
     {
-        if success {
-            sip_handler
-                .send_bye(
-                    socket_addr,
-                    tcp_stream,
-                    SipTransaction {
-                        caller_id,
-                        from_tag,
-                        to_tag,
-                        branch,
-                    },
-                    &data.gb_code,
-                )
-                .await;
-        }
-    }
+        (200, String::from("OK"))
+    } else {
+        (404, String::from("stream not found"))
+    };
 
     let result = ReplayStopResponse {
         locate: format!("{}#L{}", file!(), line!()),
-        code: 0,
-        msg: String::from("OK"),
+        code,
+        msg,
         gb_code: data.gb_code.clone(),
         stream_id: data.stream_id,
     };
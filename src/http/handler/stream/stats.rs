@@ -0,0 +1,43 @@
+use actix_web::{get, web, Responder};
+
+use crate::{http::message::stream::stats::StreamStatsResponse, media, sip::handler::SipHandler};
+
+#[get("/streams/{stream_id}/stats")]
+async fn get_stats(
+    path: web::Path<u32>,
+    sip_handler: web::Data<std::sync::Arc<SipHandler>>,
+) -> impl Responder {
+    let stream_id = path.into_inner();
+    let gb_code = sip_handler.store.find_gb_code(stream_id);
+
+    let mut result = StreamStatsResponse {
+        locate: format!("{}#L{}", file!(), line!()),
+        code: 200,
+        msg: String::from("OK"),
+        stream_id,
+        gb_code: gb_code.clone(),
+        ..Default::default()
+    };
+
+    if gb_code.is_empty() {
+        (result.code, result.msg) = (404, String::from("stream not found"));
+    } else {
+        // 仅内置收流节点提供统计
+        match sip_handler
+            .media
+            .stream_stats(&media::stream_name(&gb_code, stream_id))
+        {
+            None => (result.code, result.msg) = (404, String::from("stats not available")),
+            Some(stats) => {
+                result.transport = stats.transport;
+                result.local_port = stats.local_port;
+                result.remote_addr = stats.remote_addr;
+                result.start_ts = stats.start_ts;
+                result.last_packet_ts = stats.last_packet_ts;
+                result.items = stats.ssrcs.into_iter().map(|s| s.into()).collect();
+            }
+        }
+    }
+
+    web::Json(result)
+}
@@ -0,0 +1,37 @@
+use actix_web::{post, web, Responder};
+
+use crate::{
+    http::message::talk::keep_alive::{TalkKeepAliveRequest, TalkKeepAliveResponse},
+    sip::handler::SipHandler,
+};
+
+#[post("/talk/keep_alive")]
+async fn post_keep_alive(
+    data: web::Json<TalkKeepAliveRequest>,
+    sip_handler: web::Data<std::sync::Arc<SipHandler>>,
+) -> impl Responder {
+    // 对讲流与实时流一样会超时回收, 通话期间需要保活
+    let (code, msg) = if sip_handler
+        .store
+        .find_talk(&data.gb_code, data.stream_id)
+        .is_none()
+    {
+        (404, String::from("talk not found"))
+    } else if sip_handler
+        .store
+        .stream_keep_alive(&data.gb_code, data.stream_id)
+    {
+        (200, String::from("OK"))
+    } else {
+        (404, String::from("stream not found"))
+    };
+
+    let result = TalkKeepAliveResponse {
+        locate: format!("{}#L{}", file!(), line!()),
+        code,
+        msg,
+        gb_code: data.gb_code.clone(),
+        stream_id: data.stream_id,
+    };
+    web::Json(result)
+}
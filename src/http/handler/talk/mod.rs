@@ -0,0 +1,8 @@
+pub mod keep_alive;
+pub use keep_alive::post_keep_alive;
+
+pub mod start;
+pub use start::post_start;
+
+pub mod stop;
+pub use stop::post_stop;
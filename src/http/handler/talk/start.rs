@@ -0,0 +1,41 @@
+use actix_web::{post, web, Responder};
+
+use crate::{
+    http::message::talk::start::{TalkStartRequest, TalkStartResponse},
+    media,
+    sip::handler::SipHandler,
+};
+
+#[post("/talk/start")]
+async fn post_start(
+    data: web::Json<TalkStartRequest>,
+    sip_handler: web::Data<std::sync::Arc<SipHandler>>,
+) -> impl Responder {
+    let (code, msg, id, answer, media) = match sip_handler
+        .talk_open(&data.gb_code, &data.channel_id, &data.setup_type)
+        .await
+    {
+        Ok((stream_id, answer, media)) => (200, String::from("OK"), stream_id, answer, media),
+        Err((code, msg)) => (code, msg, 0, Default::default(), Default::default()),
+    };
+
+    let result = TalkStartResponse {
+        locate: format!("{}#L{}", file!(), line!()),
+        code,
+        msg,
+        gb_code: data.gb_code.clone(),
+        stream_id: id,
+        media_ip: media.media_ip,
+        recv_port: media.recv_port,
+        app: if code == 200 {
+            media::TALK_APP.to_string()
+        } else {
+            String::new()
+        },
+        stream: media.stream,
+        send_port: media.send_port,
+        payload_type: media.payload_type,
+        answer: answer.into(),
+    };
+    web::Json(result)
+}
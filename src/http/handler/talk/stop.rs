@@ -0,0 +1,37 @@
+use actix_web::{post, web, Responder};
+
+use crate::{
+    http::message::talk::stop::{TalkStopRequest, TalkStopResponse},
+    sip::handler::SipHandler,
+};
+
+#[post("/talk/stop")]
+async fn post_stop(
+    data: web::Json<TalkStopRequest>,
+    sip_handler: web::Data<std::sync::Arc<SipHandler>>,
+) -> impl Responder {
+    // 只关闭对讲流, 实时流与回放流走各自的接口
+    let (code, msg) = if sip_handler
+        .store
+        .find_talk(&data.gb_code, data.stream_id)
+        .is_none()
+    {
+        (404, String::from("talk not found"))
+    } else if sip_handler
+        .stream_close(&data.gb_code, data.stream_id)
+        .await
+    {
+        (200, String::from("OK"))
+    } else {
+        (404, String::from("stream not found"))
+    };
+
+    let result = TalkStopResponse {
+        locate: format!("{}#L{}", file!(), line!()),
+        code,
+        msg,
+        gb_code: data.gb_code.clone(),
+        stream_id: data.stream_id,
+    };
+    web::Json(result)
+}
@@ -0,0 +1,62 @@
+use serde::{Deserialize, Serialize};
+
+use crate::store::AlarmRecord;
+
+fn default_limit() -> usize {
+    100
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AlarmListRequest {
+    // 为空时不限设备
+    #[serde(default)]
+    pub gb_code: String,
+    #[serde(default)]
+    pub start_ts: u32,
+    // 为 0 时不限结束时间
+    #[serde(default)]
+    pub end_ts: u32,
+    #[serde(default = "default_limit")]
+    pub limit: usize,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct AlarmItem {
+    pub gb_code: String,
+    pub device_id: String,
+    pub priority: String,
+    pub method: String,
+    pub alarm_type: String,
+    pub alarm_time: String,
+    pub description: String,
+    pub longitude: Option<f64>,
+    pub latitude: Option<f64>,
+    // 收到报警的时间戳
+    pub ts: u32,
+}
+
+impl From<AlarmRecord> for AlarmItem {
+    fn from(alarm: AlarmRecord) -> Self {
+        AlarmItem {
+            gb_code: alarm.gb_code,
+            device_id: alarm.device_id,
+            priority: alarm.priority,
+            method: alarm.method,
+            alarm_type: alarm.alarm_type,
+            alarm_time: alarm.alarm_time,
+            description: alarm.description,
+            longitude: alarm.longitude,
+            latitude: alarm.latitude,
+            ts: alarm.ts,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AlarmListResponse {
+    pub locate: String,
+    pub code: u32,
+    pub msg: String,
+    pub total: u32,
+    pub items: Vec<AlarmItem>,
+}
@@ -0,0 +1,34 @@
+use serde::{Deserialize, Serialize};
+
+fn default_level() -> String {
+    String::from("0")
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AlarmSubscribeRequest {
+    pub gb_code: String,
+    // 不填时使用配置的 alarm_subscribe_expires, 为 0 时取消订阅
+    pub expires: Option<u32>,
+    // 优先级与方式为 0 时不限
+    #[serde(default = "default_level")]
+    pub start_priority: String,
+    #[serde(default = "default_level")]
+    pub end_priority: String,
+    #[serde(default = "default_level")]
+    pub alarm_method: String,
+    // 格式 2024-05-01T08:30:00, 为空时不限
+    #[serde(default)]
+    pub start_time: String,
+    #[serde(default)]
+    pub end_time: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AlarmSubscribeResponse {
+    pub locate: String,
+    pub code: u32,
+    pub msg: String,
+    pub gb_code: String,
+    // 设备认可的有效期
+    pub expires: u32,
+}
@@ -0,0 +1,27 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BroadcastStartRequest {
+    pub gb_code: String,
+    // 设备的语音输出通道
+    pub channel_id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BroadcastStartResponse {
+    pub locate: String,
+    pub code: u32,
+    pub msg: String,
+    pub gb_code: String,
+    pub channel_id: String,
+    // 客户端把音频推送到该节点的 app/stream 下
+    pub media_ip: String,
+    pub app: String,
+    pub stream: String,
+    // 节点发送端口与设备收音频的地址
+    pub local_port: u16,
+    pub device_ip: String,
+    pub device_port: u16,
+    pub ssrc: String,
+    pub payload_type: u8,
+}
@@ -0,0 +1,16 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BroadcastStopRequest {
+    pub gb_code: String,
+    pub channel_id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BroadcastStopResponse {
+    pub locate: String,
+    pub code: u32,
+    pub msg: String,
+    pub gb_code: String,
+    pub channel_id: String,
+}
@@ -0,0 +1,58 @@
+use serde::{Deserialize, Serialize};
+
+use crate::sip::message::Device;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct DeviceCatalogItem {
+    pub device_id: String,
+    pub name: String,
+    pub manufacturer: String,
+    pub model: String,
+    pub owner: String,
+    pub civil_code: String,
+    pub address: String,
+    pub parental: u32,
+    pub parent_id: String,
+    pub register_way: u32,
+    pub secrecy: u32,
+    pub ip_address: Option<String>,
+    pub port: Option<u16>,
+    pub status: Option<String>,
+    pub longitude: Option<f64>,
+    pub latitude: Option<f64>,
+    pub ptz_type: Option<u32>,
+}
+
+impl From<Device> for DeviceCatalogItem {
+    fn from(device: Device) -> Self {
+        DeviceCatalogItem {
+            device_id: device.device_id,
+            name: device.name,
+            manufacturer: device.manufacturer,
+            model: device.model,
+            owner: device.owner,
+            civil_code: device.civil_code,
+            address: device.address,
+            parental: device.parental,
+            parent_id: device.parent_id,
+            register_way: device.register_way,
+            secrecy: device.secrecy,
+            ip_address: device.ip_address,
+            port: device.port,
+            status: device.status,
+            longitude: device.longitude,
+            latitude: device.latitude,
+            ptz_type: device.ptz_type,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeviceCatalogResponse {
+    pub locate: String,
+    pub code: u32,
+    pub msg: String,
+    pub gb_code: String,
+    pub sum_num: u32,
+    pub items: Vec<DeviceCatalogItem>,
+}
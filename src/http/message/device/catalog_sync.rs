@@ -0,0 +1,14 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeviceCatalogSyncResponse {
+    pub locate: String,
+    pub code: u32,
+    pub msg: String,
+    pub gb_code: String,
+    pub status: String,
+    pub sn: u32,
+    pub received: u32,
+    pub sum_num: u32,
+    pub ts: u32,
+}
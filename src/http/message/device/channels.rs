@@ -0,0 +1,13 @@
+use serde::{Deserialize, Serialize};
+
+use super::catalog::DeviceCatalogItem;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeviceChannelsResponse {
+    pub locate: String,
+    pub code: u32,
+    pub msg: String,
+    pub gb_code: String,
+    pub total: u32,
+    pub items: Vec<DeviceCatalogItem>,
+}
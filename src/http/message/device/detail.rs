@@ -0,0 +1,12 @@
+use serde::{Deserialize, Serialize};
+
+use super::list::DeviceItem;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeviceDetailResponse {
+    pub locate: String,
+    pub code: u32,
+    pub msg: String,
+    pub gb_code: String,
+    pub device: Option<DeviceItem>,
+}
@@ -0,0 +1,58 @@
+use serde::{Deserialize, Serialize};
+
+use crate::store::DeviceRecord;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct DeviceItem {
+    pub gb_code: String,
+    pub socket_addr: String,
+    pub transport: String,
+    pub register_ts: u32,
+    pub keep_alive_ts: u32,
+    pub online: bool,
+    pub manufacturer: String,
+    pub model: String,
+    pub firmware: String,
+    // 以下字段来自最近一次 DeviceStatus 应答
+    pub device_online: String,
+    pub device_status: String,
+    pub encode: String,
+    pub record: String,
+    pub device_time: String,
+    pub alarm_num: String,
+    pub status_ts: u32,
+    pub channel_num: u32,
+}
+
+impl From<DeviceRecord> for DeviceItem {
+    fn from(device: DeviceRecord) -> Self {
+        DeviceItem {
+            gb_code: device.gb_code,
+            socket_addr: device.socket_addr,
+            transport: device.transport,
+            register_ts: device.register_ts,
+            keep_alive_ts: device.keep_alive_ts,
+            online: device.online,
+            manufacturer: device.manufacturer,
+            model: device.model,
+            firmware: device.firmware,
+            device_online: device.status.online,
+            device_status: device.status.status,
+            encode: device.status.encode,
+            record: device.status.record,
+            device_time: device.status.device_time,
+            alarm_num: device.status.alarm_num,
+            status_ts: device.status.ts,
+            channel_num: device.channel_num,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeviceListResponse {
+    pub locate: String,
+    pub code: u32,
+    pub msg: String,
+    pub total: u32,
+    pub items: Vec<DeviceItem>,
+}
@@ -0,0 +1,8 @@
+pub mod catalog;
+pub mod catalog_sync;
+pub mod channels;
+pub mod detail;
+pub mod info;
+pub mod list;
+pub mod status;
+pub mod track;
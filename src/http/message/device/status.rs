@@ -0,0 +1,15 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeviceStatusResponse {
+    pub locate: String,
+    pub code: u32,
+    pub msg: String,
+    pub gb_code: String,
+    pub result: String,
+    pub online: String,
+    pub status: String,
+    pub device_time: String,
+    pub encode: String,
+    pub record: String,
+}
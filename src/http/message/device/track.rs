@@ -0,0 +1,91 @@
+use serde::{Deserialize, Serialize};
+
+use crate::store::TrackPoint;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeviceTrackRequest {
+    #[serde(default)]
+    pub from: u32,
+    // 为 0 时不限结束时间
+    #[serde(default)]
+    pub to: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TrackGeometry {
+    // 固定为 Point
+    #[serde(rename = "type")]
+    pub geometry_type: String,
+    // [经度, 纬度]
+    pub coordinates: Vec<f64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TrackProperties {
+    pub device_id: String,
+    pub time: String,
+    pub speed: Option<f64>,
+    pub direction: Option<f64>,
+    pub altitude: Option<f64>,
+    pub ts: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TrackFeature {
+    // 固定为 Feature
+    #[serde(rename = "type")]
+    pub feature_type: String,
+    pub geometry: TrackGeometry,
+    pub properties: TrackProperties,
+}
+
+impl From<TrackPoint> for TrackFeature {
+    fn from(point: TrackPoint) -> Self {
+        TrackFeature {
+            feature_type: String::from("Feature"),
+            geometry: TrackGeometry {
+                geometry_type: String::from("Point"),
+                coordinates: vec![point.longitude, point.latitude],
+            },
+            properties: TrackProperties {
+                device_id: point.device_id,
+                time: point.time,
+                speed: point.speed,
+                direction: point.direction,
+                altitude: point.altitude,
+                ts: point.ts,
+            },
+        }
+    }
+}
+
+// GeoJSON FeatureCollection, 每个轨迹点一个 Feature, 按时间正序
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeviceTrackResponse {
+    // 固定为 FeatureCollection
+    #[serde(rename = "type")]
+    pub collection_type: String,
+    pub locate: String,
+    pub code: u32,
+    pub msg: String,
+    pub gb_code: String,
+    pub features: Vec<TrackFeature>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeviceTrackSubscribeRequest {
+    // 不填时使用配置的 mobile_position_interval
+    pub interval: Option<u32>,
+    // 不填时使用配置的 mobile_position_subscribe_expires, 为 0 时取消订阅
+    pub expires: Option<u32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeviceTrackSubscribeResponse {
+    pub locate: String,
+    pub code: u32,
+    pub msg: String,
+    pub gb_code: String,
+    // 设备认可的有效期
+    pub expires: u32,
+}
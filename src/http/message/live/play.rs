@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 
+use crate::http::message::stream::answer::StreamAnswer;
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct LivePlayRequest {
     pub gb_code: String,
@@ -14,4 +16,9 @@ pub struct LivePlayResponse {
     pub msg: String,
     pub gb_code: String,
     pub stream_id: u32,
+    // 点播失败时为空
+    pub flv_url: String,
+    pub hls_url: String,
+    // 设备应答的 SDP
+    pub answer: StreamAnswer,
 }
@@ -0,0 +1,45 @@
+use serde::{Deserialize, Serialize};
+
+use crate::media::balance::MediaNodeState;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct MediaNodeItem {
+    pub id: String,
+    pub ip: String,
+    pub weight: u32,
+    pub streams: u32,
+    // 字节/秒
+    pub bandwidth: u64,
+    pub heartbeat_ts: u32,
+    pub healthy: bool,
+    pub rtp_ports_used: u32,
+    pub rtp_ports_total: u32,
+}
+
+impl From<MediaNodeState> for MediaNodeItem {
+    fn from(node: MediaNodeState) -> Self {
+        MediaNodeItem {
+            id: node.id,
+            ip: node.ip,
+            weight: node.weight,
+            streams: node.streams,
+            bandwidth: node.bandwidth,
+            heartbeat_ts: node.heartbeat_ts,
+            healthy: node.healthy,
+            rtp_ports_used: node.rtp_ports_used,
+            rtp_ports_total: node.rtp_ports_total,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MediaNodesResponse {
+    pub locate: String,
+    pub code: u32,
+    pub msg: String,
+    // 本实例标识
+    pub service_id: String,
+    pub strategy: String,
+    pub total: u32,
+    pub items: Vec<MediaNodeItem>,
+}
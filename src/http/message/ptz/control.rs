@@ -0,0 +1,18 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PtzControlRequest {
+    pub gb_code: String,
+    pub channel_id: String,
+    pub command: String,
+    pub speed: u8,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PtzControlResponse {
+    pub locate: String,
+    pub code: u32,
+    pub msg: String,
+    pub gb_code: String,
+    pub channel_id: String,
+}
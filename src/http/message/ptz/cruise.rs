@@ -0,0 +1,23 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PtzCruiseRequest {
+    pub gb_code: String,
+    pub channel_id: String,
+    pub action: String,
+    pub cruise_id: u8,
+    #[serde(default)]
+    pub preset_id: u8,
+    // 巡航速度或停留时间(秒)
+    #[serde(default)]
+    pub value: u16,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PtzCruiseResponse {
+    pub locate: String,
+    pub code: u32,
+    pub msg: String,
+    pub gb_code: String,
+    pub channel_id: String,
+}
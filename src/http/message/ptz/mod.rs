@@ -0,0 +1,5 @@
+pub mod control;
+pub mod cruise;
+pub mod preset;
+pub mod preset_query;
+pub mod scan;
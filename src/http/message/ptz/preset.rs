@@ -0,0 +1,18 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PtzPresetRequest {
+    pub gb_code: String,
+    pub channel_id: String,
+    pub action: String,
+    pub preset_id: u8,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PtzPresetResponse {
+    pub locate: String,
+    pub code: u32,
+    pub msg: String,
+    pub gb_code: String,
+    pub channel_id: String,
+}
@@ -0,0 +1,23 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PtzPresetQueryRequest {
+    pub gb_code: String,
+    pub channel_id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PtzPresetItem {
+    pub preset_id: String,
+    pub preset_name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PtzPresetQueryResponse {
+    pub locate: String,
+    pub code: u32,
+    pub msg: String,
+    pub gb_code: String,
+    pub channel_id: String,
+    pub presets: Vec<PtzPresetItem>,
+}
@@ -0,0 +1,20 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PtzScanRequest {
+    pub gb_code: String,
+    pub channel_id: String,
+    pub action: String,
+    pub scan_id: u8,
+    #[serde(default)]
+    pub speed: u16,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PtzScanResponse {
+    pub locate: String,
+    pub code: u32,
+    pub msg: String,
+    pub gb_code: String,
+    pub channel_id: String,
+}
@@ -0,0 +1,43 @@
+use serde::{Deserialize, Serialize};
+
+use crate::media::recorder::RecordFile;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RecordFilesRequest {
+    pub gb_code: String,
+    pub channel_id: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct RecordFileItem {
+    pub name: String,
+    pub path: String,
+    pub size: u64,
+    pub start_ts: u32,
+    pub modified_ts: u32,
+}
+
+impl From<RecordFile> for RecordFileItem {
+    fn from(file: RecordFile) -> Self {
+        RecordFileItem {
+            name: file.name,
+            path: file.path,
+            size: file.size,
+            start_ts: file.start_ts,
+            modified_ts: file.modified_ts,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RecordFilesResponse {
+    pub locate: String,
+    pub code: u32,
+    pub msg: String,
+    pub gb_code: String,
+    pub channel_id: String,
+    // 是否正在录像
+    pub recording: bool,
+    pub total: u32,
+    pub items: Vec<RecordFileItem>,
+}
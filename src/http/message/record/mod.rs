@@ -0,0 +1,3 @@
+pub mod files;
+pub mod start;
+pub mod stop;
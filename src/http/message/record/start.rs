@@ -0,0 +1,24 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RecordStartRequest {
+    pub gb_code: String,
+    pub channel_id: String,
+    // 空则使用 udp
+    #[serde(default)]
+    pub setup_type: String,
+    // mp4 或 flv, 空则使用配置
+    #[serde(default)]
+    pub format: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RecordStartResponse {
+    pub locate: String,
+    pub code: u32,
+    pub msg: String,
+    pub gb_code: String,
+    pub channel_id: String,
+    pub stream_id: u32,
+    pub format: String,
+}
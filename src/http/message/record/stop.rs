@@ -0,0 +1,17 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RecordStopRequest {
+    pub gb_code: String,
+    pub channel_id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RecordStopResponse {
+    pub locate: String,
+    pub code: u32,
+    pub msg: String,
+    pub gb_code: String,
+    pub channel_id: String,
+    pub stream_id: u32,
+}
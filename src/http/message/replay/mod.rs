@@ -1,3 +1,4 @@
 pub mod keep_alive;
+pub mod records;
 pub mod start;
 pub mod stop;
@@ -0,0 +1,34 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReplayRecordsRequest {
+    pub gb_code: String,
+    pub channel_id: String,
+    pub start_ts: u64,
+    pub stop_ts: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReplayRecordItem {
+    pub device_id: String,
+    pub name: String,
+    pub file_path: String,
+    pub address: String,
+    pub start_time: String,
+    pub end_time: String,
+    pub start_ts: u64,
+    pub stop_ts: u64,
+    pub secrecy: u32,
+    pub record_type: String,
+    pub recorder_id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReplayRecordsResponse {
+    pub locate: String,
+    pub code: u32,
+    pub msg: String,
+    pub gb_code: String,
+    pub channel_id: String,
+    pub records: Vec<ReplayRecordItem>,
+}
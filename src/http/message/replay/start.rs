@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 
+use crate::http::message::stream::answer::StreamAnswer;
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ReplayStartRequest {
     pub gb_code: String,
@@ -16,4 +18,6 @@ pub struct ReplayStartResponse {
     pub msg: String,
     pub gb_code: String,
     pub stream_id: u32,
+    // 设备应答的 SDP
+    pub answer: StreamAnswer,
 }
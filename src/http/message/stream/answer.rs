@@ -0,0 +1,25 @@
+use serde::{Deserialize, Serialize};
+
+use crate::sip::message::SdpAnswer;
+
+// 设备应答 INVITE 时协商出的媒体参数
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct StreamAnswer {
+    pub ssrc: String,
+    pub transport: String,
+    pub setup: String,
+    pub media_ip: String,
+    pub media_port: u16,
+}
+
+impl From<SdpAnswer> for StreamAnswer {
+    fn from(answer: SdpAnswer) -> Self {
+        StreamAnswer {
+            ssrc: answer.ssrc,
+            transport: answer.transport,
+            setup: answer.setup,
+            media_ip: answer.media_ip,
+            media_port: answer.media_port,
+        }
+    }
+}
@@ -0,0 +1,43 @@
+use serde::{Deserialize, Serialize};
+
+use crate::media::SsrcStats;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SsrcStatsItem {
+    pub ssrc: u32,
+    pub payload_type: u8,
+    pub packets: u64,
+    pub bytes: u64,
+    pub lost: u64,
+    pub frames: u64,
+    pub keyframes: u64,
+}
+
+impl From<SsrcStats> for SsrcStatsItem {
+    fn from(stats: SsrcStats) -> Self {
+        SsrcStatsItem {
+            ssrc: stats.ssrc,
+            payload_type: stats.payload_type,
+            packets: stats.packets,
+            bytes: stats.bytes,
+            lost: stats.lost,
+            frames: stats.frames,
+            keyframes: stats.keyframes,
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct StreamStatsResponse {
+    pub locate: String,
+    pub code: u32,
+    pub msg: String,
+    pub stream_id: u32,
+    pub gb_code: String,
+    pub transport: String,
+    pub local_port: u16,
+    pub remote_addr: String,
+    pub start_ts: u32,
+    pub last_packet_ts: u32,
+    pub items: Vec<SsrcStatsItem>,
+}
@@ -0,0 +1,3 @@
+pub mod keep_alive;
+pub mod start;
+pub mod stop;
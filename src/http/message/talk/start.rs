@@ -0,0 +1,31 @@
+use serde::{Deserialize, Serialize};
+
+use crate::http::message::stream::answer::StreamAnswer;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TalkStartRequest {
+    pub gb_code: String,
+    // 为空时走 UDP
+    #[serde(default)]
+    pub setup_type: String,
+    pub channel_id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TalkStartResponse {
+    pub locate: String,
+    pub code: u32,
+    pub msg: String,
+    pub gb_code: String,
+    pub stream_id: u32,
+    pub media_ip: String,
+    // 设备 -> 节点: 设备音频发往 media_ip:recv_port
+    pub recv_port: u16,
+    // 客户端 -> 设备: 音频推送到节点的 app/stream, 节点从 send_port 发往设备
+    pub app: String,
+    pub stream: String,
+    pub send_port: u16,
+    pub payload_type: u8,
+    // 设备应答的 SDP, 即设备收音频的地址
+    pub answer: StreamAnswer,
+}
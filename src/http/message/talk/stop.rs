@@ -0,0 +1,16 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TalkStopRequest {
+    pub gb_code: String,
+    pub stream_id: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TalkStopResponse {
+    pub locate: String,
+    pub code: u32,
+    pub msg: String,
+    pub gb_code: String,
+    pub stream_id: u32,
+}
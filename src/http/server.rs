@@ -16,9 +16,41 @@ pub async fn run_forever(
             .service(http::handler::live::post_play)
             .service(http::handler::live::post_stop)
             .service(http::handler::live::post_keep_alive)
+            .service(http::handler::live::get_flv)
+            .service(http::handler::live::get_playlist)
+            .service(http::handler::live::get_init)
+            .service(http::handler::live::get_segment)
             .service(http::handler::replay::post_start)
             .service(http::handler::replay::post_stop)
             .service(http::handler::replay::post_keep_alive)
+            .service(http::handler::replay::post_records)
+            .service(http::handler::record::post_start)
+            .service(http::handler::record::post_stop)
+            .service(http::handler::record::get_files)
+            .service(http::handler::device::get_catalog)
+            .service(http::handler::device::get_catalog_sync)
+            .service(http::handler::device::get_catalog_events)
+            .service(http::handler::device::get_info)
+            .service(http::handler::device::get_status)
+            .service(http::handler::device::get_devices)
+            .service(http::handler::device::get_device)
+            .service(http::handler::device::get_channels)
+            .service(http::handler::device::get_track)
+            .service(http::handler::device::post_track_subscribe)
+            .service(http::handler::ptz::post_control)
+            .service(http::handler::ptz::post_preset_query)
+            .service(http::handler::ptz::post_preset)
+            .service(http::handler::ptz::post_cruise)
+            .service(http::handler::ptz::post_scan)
+            .service(http::handler::media::get_nodes)
+            .service(http::handler::stream::get_stats)
+            .service(http::handler::alarm::get_alarms)
+            .service(http::handler::alarm::post_subscribe)
+            .service(http::handler::broadcast::post_start)
+            .service(http::handler::broadcast::post_stop)
+            .service(http::handler::talk::post_start)
+            .service(http::handler::talk::post_stop)
+            .service(http::handler::talk::post_keep_alive)
     })
     .bind((config.host.clone(), config.http_port))
     {
@@ -1,4 +1,5 @@
 pub mod http;
+pub mod media;
 pub mod sip;
 pub mod store;
 pub mod utils;
@@ -31,23 +32,55 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let (sip_udp_socket, sip_tcp_listener) = sip::server::bind(&config).await?;
 
     // connect store
-    let store_engine = store::create_store(&config);
+    let mut store_engine = store::create_store(&config);
     if !store_engine.is_connected() {
         tracing::error!("create_store error");
         return Ok(());
     }
 
+    // device and stream timeout
+    let (timeout_devices_sender, timeout_devices_receiver) = std::sync::mpsc::channel();
+    let (timeout_streams_sender, timeout_streams_receiver) = std::sync::mpsc::channel();
+    store_engine.start_timeout_check(timeout_devices_sender, timeout_streams_sender);
+
     // run sip server
     let sip_handler =
         sip::handler::SipHandler::new(&config, store_engine, sip_udp_socket, sip_tcp_listener);
     let sip_handler_arc = std::sync::Arc::new(sip_handler);
     let sip_service = sip::server::run_forever(config.clone(), sip_handler_arc.clone());
 
+    // register to upstream platforms
+    let cascade_service = sip::cascade::run_forever(sip_handler_arc.clone());
+
+    // release timeout devices and streams
+    let timeout_service = sip::handler::timeout::run_forever(
+        sip_handler_arc.clone(),
+        timeout_devices_receiver,
+        timeout_streams_receiver,
+    );
+
+    // refresh subscriptions before expiry
+    let subscribe_service = sip::handler::subscribe::run_forever(sip_handler_arc.clone());
+
+    // media node heartbeat
+    let media_service = media::run_forever(sip_handler_arc.clone());
+
+    // record retention
+    let record_service = media::recorder::run_forever(sip_handler_arc.clone());
+
     // run http server
     let http_service = http::server::run_forever(&config, sip_handler_arc);
 
     // wait
-    let _ = tokio::join!(sip_service, http_service);
+    let _ = tokio::join!(
+        sip_service,
+        cascade_service,
+        timeout_service,
+        subscribe_service,
+        media_service,
+        record_service,
+        http_service
+    );
 
     Ok(())
 }
@@ -0,0 +1,132 @@
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BalanceStrategy {
+    // 平滑加权轮询
+    RoundRobin,
+    // 按权重折算后的最少流数
+    LeastConnections,
+}
+
+impl fmt::Display for BalanceStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BalanceStrategy::RoundRobin => write!(f, "round_robin"),
+            BalanceStrategy::LeastConnections => write!(f, "least_connections"),
+        }
+    }
+}
+
+impl FromStr for BalanceStrategy {
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "round_robin" => Ok(Self::RoundRobin),
+            "least_connections" => Ok(Self::LeastConnections),
+            _ => Err(String::from(
+                "Support lists: round_robin, least_connections",
+            )),
+        }
+    }
+
+    type Err = String;
+}
+
+// 媒体节点负载, 由心跳刷新
+#[derive(Debug, Default, Clone)]
+pub struct MediaNodeState {
+    pub id: String,
+    pub ip: String,
+    pub weight: u32,
+    pub streams: u32,
+    // 收流总码率, 字节/秒
+    pub bandwidth: u64,
+    // 最近一次心跳成功的时间戳
+    pub heartbeat_ts: u32,
+    pub healthy: bool,
+    pub rtp_ports_used: u32,
+    pub rtp_ports_total: u32,
+    // 平滑加权轮询的当前权重
+    pub current_weight: i64,
+}
+
+// 按策略给出候选节点顺序, 首选在前, 不健康或权重为 0 的节点被排除
+pub fn candidates(strategy: BalanceStrategy, states: &mut [MediaNodeState]) -> Vec<usize> {
+    let mut healthy: Vec<usize> = (0..states.len())
+        .filter(|&i| states[i].healthy && states[i].weight > 0)
+        .collect();
+
+    match strategy {
+        BalanceStrategy::RoundRobin => {
+            if let Some(best) = smooth_weighted_pick(states, &healthy) {
+                healthy.retain(|&i| i != best);
+                healthy.insert(0, best);
+            }
+        }
+        BalanceStrategy::LeastConnections => {
+            // streams_a / weight_a 与 streams_b / weight_b 交叉相乘比较, 再比较码率
+            healthy.sort_by(|&a, &b| {
+                let load_a = states[a].streams as u64 * states[b].weight as u64;
+                let load_b = states[b].streams as u64 * states[a].weight as u64;
+                load_a
+                    .cmp(&load_b)
+                    .then(states[a].bandwidth.cmp(&states[b].bandwidth))
+            });
+        }
+    }
+
+    healthy
+}
+
+fn smooth_weighted_pick(states: &mut [MediaNodeState], healthy: &[usize]) -> Option<usize> {
+    let total: i64 = healthy.iter().map(|&i| states[i].weight as i64).sum();
+    let mut best: Option<usize> = None;
+    for &i in healthy {
+        states[i].current_weight += states[i].weight as i64;
+        if best.is_none_or(|b| states[i].current_weight > states[b].current_weight) {
+            best = Some(i);
+        }
+    }
+
+    if let Some(b) = best {
+        states[b].current_weight -= total;
+    }
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{candidates, BalanceStrategy, MediaNodeState};
+
+    fn node(id: &str, weight: u32, streams: u32) -> MediaNodeState {
+        MediaNodeState {
+            id: id.to_string(),
+            weight,
+            streams,
+            healthy: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_weighted_round_robin() {
+        let mut states = vec![node("a", 5, 0), node("b", 1, 0), node("c", 1, 0)];
+        let picks: String = (0..7)
+            .map(|_| {
+                let first = candidates(BalanceStrategy::RoundRobin, &mut states)[0];
+                states[first].id.clone()
+            })
+            .collect();
+        assert_eq!(picks, "aabacaa");
+    }
+
+    #[test]
+    fn test_least_connections_skips_unhealthy() {
+        let mut states = vec![node("a", 1, 3), node("b", 2, 4), node("c", 1, 0)];
+        states[2].healthy = false;
+        assert_eq!(
+            candidates(BalanceStrategy::LeastConnections, &mut states),
+            vec![1, 0]
+        );
+    }
+}
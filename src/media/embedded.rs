@@ -0,0 +1,380 @@
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use dashmap::DashMap;
+use tokio::{sync::broadcast, task::JoinHandle};
+
+use super::ps::{AccessUnit, PsDemuxer};
+use super::rtp::{read_rfc4571_frame, RtpHeader};
+use super::{MediaNodeLoad, MediaServer, RtpTransport, SsrcStats, StreamStats};
+use crate::utils::config::MediaNode;
+
+// 订阅者消费过慢时丢弃最早的帧
+static UNIT_CHANNEL_CAPACITY: usize = 1024;
+
+struct EmbeddedStream {
+    stats: Arc<Mutex<StreamStats>>,
+    units: broadcast::Sender<Arc<AccessUnit>>,
+    task: Option<JoinHandle<()>>,
+}
+
+// 单路收流: 统计并在有订阅者时解复用
+struct StreamReceiver {
+    stats: Arc<Mutex<StreamStats>>,
+    units: broadcast::Sender<Arc<AccessUnit>>,
+    demuxer: PsDemuxer,
+}
+
+impl StreamReceiver {
+    fn on_packet(&mut self, data: &[u8]) {
+        let Some((ssrc, payload)) = on_rtp_packet(&self.stats, data) else {
+            return;
+        };
+
+        // 帧数与关键帧数按解复用出的视频帧统计
+        let units = self.demuxer.push(payload);
+        if let Some(stats) = self
+            .stats
+            .lock()
+            .unwrap()
+            .ssrcs
+            .iter_mut()
+            .find(|stats| stats.ssrc == ssrc)
+        {
+            for unit in units.iter().filter(|unit| unit.stream_type.is_video()) {
+                stats.frames += 1;
+                if unit.keyframe {
+                    stats.keyframes += 1;
+                }
+            }
+        }
+
+        if self.units.receiver_count() == 0 {
+            return;
+        }
+        for unit in units {
+            let _ = self.units.send(Arc::new(unit));
+        }
+    }
+}
+
+// 内置收流节点: 绑定分配的端口, 解析 RTP/PS 并统计, 不做转发
+pub struct EmbeddedMediaServer {
+    pub id: String,
+    pub ip: String,
+    streams: DashMap<String, EmbeddedStream>,
+}
+
+impl EmbeddedMediaServer {
+    pub fn new(node: &MediaNode) -> Self {
+        EmbeddedMediaServer {
+            id: node.id.clone(),
+            ip: node.ip.clone(),
+            streams: DashMap::new(),
+        }
+    }
+}
+
+fn ts_now() -> u32 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .expect("Time went backwards")
+        .as_secs() as u32
+}
+
+// 统计一个 RTP 包, 返回 SSRC 与 RTP 负载
+fn on_rtp_packet<'a>(stats: &Mutex<StreamStats>, data: &'a [u8]) -> Option<(u32, &'a [u8])> {
+    let (header, payload) = RtpHeader::parse(data)?;
+
+    let mut stats = stats.lock().unwrap();
+    stats.last_packet_ts = ts_now();
+    let index = match stats.ssrcs.iter().position(|s| s.ssrc == header.ssrc) {
+        Some(index) => index,
+        None => {
+            stats.ssrcs.push(SsrcStats {
+                ssrc: header.ssrc,
+                payload_type: header.payload_type,
+                ..Default::default()
+            });
+            stats.ssrcs.len() - 1
+        }
+    };
+
+    let ssrc = &mut stats.ssrcs[index];
+    if ssrc.packets > 0 {
+        let gap = header.sequence.wrapping_sub(ssrc.last_sequence);
+        if gap > 1 && gap < 0x8000 {
+            ssrc.lost += (gap - 1) as u64;
+        }
+    }
+
+    ssrc.packets += 1;
+    ssrc.bytes += data.len() as u64;
+    ssrc.last_sequence = header.sequence;
+    ssrc.last_timestamp = header.timestamp;
+    Some((header.ssrc, payload))
+}
+
+async fn receive_udp(socket: tokio::net::UdpSocket, mut receiver: StreamReceiver) {
+    let mut buffer = vec![0; 65535];
+    loop {
+        match socket.recv_from(&mut buffer).await {
+            Ok((amount, addr)) => {
+                {
+                    let mut stats = receiver.stats.lock().unwrap();
+                    if stats.remote_addr.is_empty() {
+                        stats.remote_addr = addr.to_string();
+                    }
+                }
+                receiver.on_packet(&buffer[..amount]);
+            }
+            Err(e) => {
+                tracing::error!("UdpSocket::recv_from error, e: {:?}", e);
+                return;
+            }
+        }
+    }
+}
+
+async fn receive_tcp(
+    mut stream: tokio::net::TcpStream,
+    addr: std::net::SocketAddr,
+    mut receiver: StreamReceiver,
+) {
+    receiver.stats.lock().unwrap().remote_addr = addr.to_string();
+    let mut buffer = vec![];
+    while read_rfc4571_frame(&mut stream, &mut buffer).await.is_ok() {
+        receiver.on_packet(&buffer);
+    }
+    tracing::info!("rtp tcp connection closed: {}", addr);
+}
+
+#[async_trait]
+impl MediaServer for EmbeddedMediaServer {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn ip(&self) -> &str {
+        &self.ip
+    }
+
+    async fn open_rtp_server(
+        &self,
+        stream_name: &str,
+        port: u16,
+        transport: RtpTransport,
+    ) -> Option<u16> {
+        let stats = Arc::new(Mutex::new(StreamStats {
+            transport: format!("{:?}", transport),
+            local_port: port,
+            start_ts: ts_now(),
+            ..Default::default()
+        }));
+        let (units, _) = broadcast::channel(UNIT_CHANNEL_CAPACITY);
+        let receiver = StreamReceiver {
+            stats: stats.clone(),
+            units: units.clone(),
+            demuxer: PsDemuxer::new(),
+        };
+
+        let local_addr = format!("0.0.0.0:{}", port);
+        let task = match transport {
+            RtpTransport::Udp => match tokio::net::UdpSocket::bind(&local_addr).await {
+                Ok(socket) => Some(tokio::spawn(receive_udp(socket, receiver))),
+                Err(e) => {
+                    tracing::error!("UdpSocket::bind({}) error, e: {:?}", &local_addr, e);
+                    return None;
+                }
+            },
+            RtpTransport::TcpPassive => match tokio::net::TcpListener::bind(&local_addr).await {
+                Ok(listener) => Some(tokio::spawn(async move {
+                    match listener.accept().await {
+                        Ok((stream, addr)) => receive_tcp(stream, addr, receiver).await,
+                        Err(e) => tracing::error!("TcpListener::accept error: {:?}", e),
+                    }
+                })),
+                Err(e) => {
+                    tracing::error!("TcpListener::bind({}) error, e: {:?}", &local_addr, e);
+                    return None;
+                }
+            },
+            // 等待设备应答后再连接
+            RtpTransport::TcpActive => None,
+        };
+
+        self.streams.insert(
+            stream_name.to_string(),
+            EmbeddedStream { stats, units, task },
+        );
+        Some(port)
+    }
+
+    async fn connect_rtp_server(
+        &self,
+        stream_name: &str,
+        remote_ip: &str,
+        remote_port: u16,
+    ) -> bool {
+        let Some(receiver) = self.streams.get(stream_name).map(|stream| StreamReceiver {
+            stats: stream.stats.clone(),
+            units: stream.units.clone(),
+            demuxer: PsDemuxer::new(),
+        }) else {
+            return false;
+        };
+
+        let remote_addr = format!("{}:{}", remote_ip, remote_port);
+        let stream = match tokio::net::TcpStream::connect(&remote_addr).await {
+            Ok(stream) => stream,
+            Err(e) => {
+                tracing::error!("TcpStream::connect({}) error, e: {:?}", &remote_addr, e);
+                return false;
+            }
+        };
+        let Ok(addr) = stream.peer_addr() else {
+            return false;
+        };
+
+        match self.streams.get_mut(stream_name) {
+            Some(mut entry) => {
+                entry.task = Some(tokio::spawn(receive_tcp(stream, addr, receiver)));
+                true
+            }
+            None => false,
+        }
+    }
+
+    async fn close_rtp_server(&self, stream_name: &str) -> bool {
+        match self.streams.remove(stream_name) {
+            Some((_, stream)) => {
+                if let Some(task) = stream.task {
+                    task.abort();
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    async fn stream_exists(&self, stream_name: &str) -> bool {
+        self.streams.get(stream_name).is_some_and(|stream| {
+            let stats = stream.stats.lock().unwrap();
+            stats.ssrcs.iter().any(|ssrc| ssrc.packets > 0)
+        })
+    }
+
+    async fn heartbeat(&self) -> Option<MediaNodeLoad> {
+        Some(MediaNodeLoad {
+            streams: self.streams.len() as u32,
+            bandwidth: 0,
+        })
+    }
+
+    fn stream_stats(&self, stream_name: &str) -> Option<StreamStats> {
+        self.streams
+            .get(stream_name)
+            .map(|stream| stream.stats.lock().unwrap().clone())
+    }
+
+    fn subscribe(&self, stream_name: &str) -> Option<broadcast::Receiver<Arc<AccessUnit>>> {
+        self.streams
+            .get(stream_name)
+            .map(|stream| stream.units.subscribe())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::EmbeddedMediaServer;
+    use crate::media::{MediaServer, RtpTransport};
+    use crate::utils::config::MediaNode;
+
+    fn rtp_packet(sequence: u16, timestamp: u32, payload: &[u8]) -> Vec<u8> {
+        let mut packet = vec![0x80, 0x60];
+        packet.extend_from_slice(&sequence.to_be_bytes());
+        packet.extend_from_slice(&timestamp.to_be_bytes());
+        packet.extend_from_slice(&0x0102_0304u32.to_be_bytes());
+        packet.extend_from_slice(payload);
+        packet
+    }
+
+    // MPEG-2 包头 + 带 PTS 的视频 PES, 负载为单个 H.264 NAL
+    fn ps_packet(pts: u64, nal: &[u8]) -> Vec<u8> {
+        let mut packet = vec![
+            0x00, 0x00, 0x01, 0xBA, 0x44, 0x00, 0x04, 0x00, 0x04, 0x01, 0x00, 0x00, 0x03, 0xF8,
+        ];
+        packet.extend_from_slice(&[0x00, 0x00, 0x01, 0xE0]);
+        packet.extend_from_slice(&((3 + 5 + 4 + nal.len()) as u16).to_be_bytes());
+        packet.extend_from_slice(&[
+            0x80,
+            0x80,
+            0x05,
+            0x21 | (((pts >> 30) & 0x07) as u8) << 1,
+            (pts >> 22) as u8,
+            (((pts >> 15) & 0x7F) as u8) << 1 | 1,
+            (pts >> 7) as u8,
+            ((pts & 0x7F) as u8) << 1 | 1,
+        ]);
+        packet.extend_from_slice(&[0x00, 0x00, 0x00, 0x01]);
+        packet.extend_from_slice(nal);
+        packet
+    }
+
+    #[tokio::test]
+    async fn test_udp_receive_stats() {
+        let server = EmbeddedMediaServer::new(&MediaNode {
+            id: String::from("embedded"),
+            kind: String::from("embedded"),
+            ip: String::from("127.0.0.1"),
+            api_url: String::new(),
+            secret: String::new(),
+            weight: 1,
+            rtp_port_min: 0,
+            rtp_port_max: 0,
+        });
+
+        // 由系统分配空闲端口, 避免测试间冲突
+        let port = std::net::UdpSocket::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        assert_eq!(
+            server.open_rtp_server("s1", port, RtpTransport::Udp).await,
+            Some(port)
+        );
+
+        // IDR 拆成两个 RTP 包, P 帧在下一帧到达时才输出
+        let idr = ps_packet(3600, &[0x65, 0x88, 0x80, 0x40]);
+        let sender = tokio::net::UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        for packet in [
+            rtp_packet(1, 3600, &idr[..10]),
+            rtp_packet(2, 3600, &idr[10..]),
+            rtp_packet(4, 7200, &ps_packet(7200, &[0x41, 0x9A])),
+            rtp_packet(5, 10800, &ps_packet(10800, &[0x41, 0x9A])),
+        ] {
+            sender.send_to(&packet, ("127.0.0.1", port)).await.unwrap();
+        }
+
+        for _ in 0..50 {
+            if server.stream_exists("s1").await
+                && server.stream_stats("s1").unwrap().ssrcs[0].packets == 4
+            {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+
+        let stats = server.stream_stats("s1").unwrap();
+        let ssrc = &stats.ssrcs[0];
+        assert_eq!(ssrc.ssrc, 0x0102_0304);
+        assert_eq!(ssrc.packets, 4);
+        assert_eq!(ssrc.frames, 2);
+        assert_eq!(ssrc.keyframes, 1);
+        assert_eq!(ssrc.lost, 1);
+
+        assert!(server.close_rtp_server("s1").await);
+        assert!(server.stream_stats("s1").is_none());
+    }
+}
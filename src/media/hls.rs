@@ -0,0 +1,257 @@
+use std::collections::VecDeque;
+use std::fmt::Write;
+use std::sync::{Arc, Mutex};
+
+use bytes::Bytes;
+use dashmap::DashMap;
+use tokio::sync::broadcast;
+
+use super::mux::fmp4::Fmp4Muxer;
+use super::ps::AccessUnit;
+
+// 内存中保留的切片数
+static HLS_SEGMENT_COUNT: usize = 6;
+
+// 超过该时长无人请求播放列表则停止切片
+static HLS_IDLE_SECONDS: u64 = 60;
+
+#[derive(Debug, Clone)]
+pub struct HlsSegment {
+    pub sequence: u64,
+    // 秒
+    pub duration: f64,
+    pub data: Bytes,
+}
+
+#[derive(Debug, Default)]
+struct HlsPlaylist {
+    init: Option<Bytes>,
+    segments: VecDeque<HlsSegment>,
+    last_access: Option<std::time::Instant>,
+}
+
+impl HlsPlaylist {
+    fn render(&self) -> String {
+        let target = self
+            .segments
+            .iter()
+            .map(|s| s.duration.ceil() as u64)
+            .max()
+            .unwrap_or(1)
+            .max(1);
+        let first = self
+            .segments
+            .front()
+            .map(|s| s.sequence)
+            .unwrap_or_default();
+
+        let mut m3u8 = String::from("#EXTM3U\n#EXT-X-VERSION:7\n");
+        let _ = writeln!(m3u8, "#EXT-X-TARGETDURATION:{}", target);
+        let _ = writeln!(m3u8, "#EXT-X-MEDIA-SEQUENCE:{}", first);
+        m3u8.push_str("#EXT-X-MAP:URI=\"init.mp4\"\n");
+        for segment in &self.segments {
+            let _ = writeln!(m3u8, "#EXTINF:{:.3},", segment.duration);
+            let _ = writeln!(m3u8, "{}.m4s", segment.sequence);
+        }
+        m3u8
+    }
+}
+
+// 按流切 fMP4 切片, 首次请求时开始, 空闲后自动停止
+#[derive(Default)]
+pub struct HlsManager {
+    sessions: Arc<DashMap<String, Arc<Mutex<HlsPlaylist>>>>,
+}
+
+impl HlsManager {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn is_running(&self, stream_name: &str) -> bool {
+        self.sessions.contains_key(stream_name)
+    }
+
+    pub fn start(&self, stream_name: &str, units: broadcast::Receiver<Arc<AccessUnit>>) {
+        let playlist = Arc::new(Mutex::new(HlsPlaylist {
+            last_access: Some(std::time::Instant::now()),
+            ..Default::default()
+        }));
+        self.sessions
+            .insert(stream_name.to_string(), playlist.clone());
+
+        let sessions = self.sessions.clone();
+        let stream_name = stream_name.to_string();
+        tokio::spawn(async move {
+            segment_loop(units, playlist.clone()).await;
+            sessions.remove_if(&stream_name, |_, p| Arc::ptr_eq(p, &playlist));
+            tracing::info!("hls stopped: {}", stream_name);
+        });
+    }
+
+    // 播放列表, 尚无切片时返回 None
+    pub fn playlist(&self, stream_name: &str) -> Option<String> {
+        let session = self.sessions.get(stream_name)?;
+        let mut playlist = session.lock().unwrap();
+        playlist.last_access = Some(std::time::Instant::now());
+        if playlist.segments.is_empty() {
+            return None;
+        }
+        Some(playlist.render())
+    }
+
+    pub fn init_segment(&self, stream_name: &str) -> Option<Bytes> {
+        let session = self.sessions.get(stream_name)?;
+        let playlist = session.lock().unwrap();
+        playlist.init.clone()
+    }
+
+    pub fn segment(&self, stream_name: &str, sequence: u64) -> Option<Bytes> {
+        let session = self.sessions.get(stream_name)?;
+        let playlist = session.lock().unwrap();
+        playlist
+            .segments
+            .iter()
+            .find(|s| s.sequence == sequence)
+            .map(|s| s.data.clone())
+    }
+}
+
+async fn segment_loop(
+    mut units: broadcast::Receiver<Arc<AccessUnit>>,
+    playlist: Arc<Mutex<HlsPlaylist>>,
+) {
+    let mut muxer = Fmp4Muxer::new();
+    let mut segmenter = Segmenter::default();
+    let mut idle_check = tokio::time::interval(std::time::Duration::from_secs(5));
+
+    loop {
+        tokio::select! {
+            _ = idle_check.tick() => {
+                let idle = playlist
+                    .lock()
+                    .unwrap()
+                    .last_access
+                    .is_some_and(|t| t.elapsed().as_secs() >= HLS_IDLE_SECONDS);
+                if idle {
+                    break;
+                }
+            }
+            unit = units.recv() => match unit {
+                Ok(unit) => {
+                    let data = muxer.push(&unit);
+                    if let Some(segment) = segmenter.on_output(&muxer, &unit, data) {
+                        let mut playlist = playlist.lock().unwrap();
+                        if playlist.init.is_none() {
+                            playlist.init = muxer.init_segment().map(Bytes::from);
+                        }
+                        playlist.segments.push_back(segment);
+                        while playlist.segments.len() > HLS_SEGMENT_COUNT {
+                            playlist.segments.pop_front();
+                        }
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(n)) => {
+                    tracing::warn!("hls lagged, skipped: {}", n);
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+        }
+    }
+}
+
+// 把封装输出切成以关键帧开头的切片
+#[derive(Debug, Default)]
+struct Segmenter {
+    sequence: u64,
+    // 当前切片首个关键帧的 DTS
+    start_dts: Option<u64>,
+}
+
+impl Segmenter {
+    fn on_output(
+        &mut self,
+        muxer: &Fmp4Muxer,
+        unit: &AccessUnit,
+        mut data: Vec<u8>,
+    ) -> Option<HlsSegment> {
+        if !unit.stream_type.is_video() || !unit.keyframe {
+            return None;
+        }
+        let dts = unit.dts.or(unit.pts).unwrap_or_default();
+        let start_dts = self.start_dts.replace(dts);
+        if data.is_empty() {
+            return None;
+        }
+
+        // 首个切片前带有初始化段
+        if self.sequence == 0 {
+            let init_len = muxer.init_segment().map(|init| init.len()).unwrap_or(0);
+            data.drain(..init_len.min(data.len()));
+        }
+
+        let segment = HlsSegment {
+            sequence: self.sequence,
+            duration: dts.saturating_sub(start_dts.unwrap_or(dts)) as f64 / 90000.0,
+            data: Bytes::from(data),
+        };
+        self.sequence += 1;
+        Some(segment)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{HlsPlaylist, Segmenter};
+    use crate::media::mux::fmp4::Fmp4Muxer;
+    use crate::media::ps::{AccessUnit, StreamType};
+
+    fn video(dts: u64, keyframe: bool) -> AccessUnit {
+        let data = if keyframe {
+            vec![
+                0x00, 0x00, 0x00, 0x01, 0x67, 0x42, 0xC0, 0x1E, 0xDA, 0x05, 0x82, 0x59, 0x00, 0x00,
+                0x00, 0x01, 0x68, 0xCE, 0x3C, 0x80, 0x00, 0x00, 0x00, 0x01, 0x65, 0x88, 0x84,
+            ]
+        } else {
+            vec![0x00, 0x00, 0x00, 0x01, 0x41, 0x9A, 0x02]
+        };
+        AccessUnit {
+            stream_id: 0xE0,
+            stream_type: StreamType::H264,
+            pts: Some(dts),
+            dts: Some(dts),
+            keyframe,
+            data,
+        }
+    }
+
+    #[test]
+    fn test_segments_and_playlist() {
+        let mut muxer = Fmp4Muxer::new();
+        let mut segmenter = Segmenter::default();
+        let mut playlist = HlsPlaylist::default();
+
+        // GOP 2 秒, 每秒 1 帧
+        for i in 0..7u64 {
+            let unit = video(i * 90000, i % 2 == 0);
+            let data = muxer.push(&unit);
+            if let Some(segment) = segmenter.on_output(&muxer, &unit, data) {
+                if playlist.init.is_none() {
+                    playlist.init = muxer.init_segment().map(bytes::Bytes::from);
+                }
+                playlist.segments.push_back(segment);
+            }
+        }
+
+        assert_eq!(playlist.segments.len(), 3);
+        assert_eq!(&playlist.init.as_ref().unwrap()[4..8], b"ftyp");
+        // 切片不带初始化段, 以 moof 开头
+        assert_eq!(&playlist.segments[0].data[4..8], b"moof");
+        assert_eq!(playlist.segments[2].sequence, 2);
+
+        let m3u8 = playlist.render();
+        assert!(m3u8.contains("#EXT-X-TARGETDURATION:2\n"));
+        assert!(m3u8.contains("#EXT-X-MAP:URI=\"init.mp4\"\n"));
+        assert!(m3u8.contains("#EXTINF:2.000,\n0.m4s\n"));
+    }
+}
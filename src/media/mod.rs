@@ -0,0 +1,463 @@
+pub mod balance;
+pub mod embedded;
+pub mod hls;
+pub mod mux;
+pub mod port;
+pub mod ps;
+pub mod recorder;
+pub mod rtp;
+pub mod zlm;
+
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use dashmap::DashMap;
+
+use crate::sip::handler::SipHandler;
+use crate::utils::config::{Config, MediaNode};
+use balance::{BalanceStrategy, MediaNodeState};
+use port::PortPool;
+
+// 收流方式, 与 SDP 中的 setup 属性对应
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RtpTransport {
+    Udp,
+    // 媒体节点监听, 设备主动连接
+    TcpPassive,
+    // 媒体节点主动连接设备
+    TcpActive,
+}
+
+impl RtpTransport {
+    pub fn from_setup_type(setup_type: &str) -> Self {
+        match setup_type {
+            "passive" => RtpTransport::TcpPassive,
+            "active" => RtpTransport::TcpActive,
+            _ => RtpTransport::Udp,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MediaError {
+    // 没有可用的媒体节点
+    Unavailable,
+    // 所有可用节点的收流端口都已分配完
+    PortExhausted,
+}
+
+impl fmt::Display for MediaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MediaError::Unavailable => write!(f, "media server unavailable"),
+            MediaError::PortExhausted => write!(f, "rtp port exhausted"),
+        }
+    }
+}
+
+// 节点心跳上报的负载
+#[derive(Debug, Default, Clone)]
+pub struct MediaNodeLoad {
+    pub streams: u32,
+    // 字节/秒
+    pub bandwidth: u64,
+}
+
+// 客户端播放地址
+#[derive(Debug, Default, Clone)]
+pub struct PlayUrls {
+    pub flv: String,
+    pub hls: String,
+}
+
+// 单个 SSRC 的收流统计
+#[derive(Debug, Default, Clone)]
+pub struct SsrcStats {
+    pub ssrc: u32,
+    pub payload_type: u8,
+    pub packets: u64,
+    pub bytes: u64,
+    // 按 RTP 序号推算的丢包数
+    pub lost: u64,
+    pub frames: u64,
+    pub keyframes: u64,
+    pub last_sequence: u16,
+    pub last_timestamp: u32,
+}
+
+// 单路流的收流统计
+#[derive(Debug, Default, Clone)]
+pub struct StreamStats {
+    pub transport: String,
+    pub local_port: u16,
+    // 发流端地址, 收到数据前为空
+    pub remote_addr: String,
+    pub start_ts: u32,
+    pub last_packet_ts: u32,
+    pub ssrcs: Vec<SsrcStats>,
+}
+
+// 把节点上已有的流以 RTP 推送给设备, 用于语音广播与对讲
+#[derive(Debug, Default, Clone)]
+pub struct SendRtp {
+    pub app: String,
+    pub stream: String,
+    pub ssrc: String,
+    pub dst_ip: String,
+    pub dst_port: u16,
+    // 96 时按 PS 封装, 否则直接发送音频负载
+    pub payload_type: u8,
+    pub udp: bool,
+}
+
+#[async_trait]
+pub trait MediaServer: Send + Sync {
+    fn id(&self) -> &str;
+
+    // 写入 SDP 的收流地址
+    fn ip(&self) -> &str;
+
+    // 在指定端口上开启收流, 返回节点实际监听的端口
+    async fn open_rtp_server(
+        &self,
+        stream_name: &str,
+        port: u16,
+        transport: RtpTransport,
+    ) -> Option<u16>;
+
+    // setup:active 时由媒体节点主动连接设备
+    async fn connect_rtp_server(
+        &self,
+        stream_name: &str,
+        remote_ip: &str,
+        remote_port: u16,
+    ) -> bool;
+
+    async fn close_rtp_server(&self, stream_name: &str) -> bool;
+
+    async fn stream_exists(&self, stream_name: &str) -> bool;
+
+    // 探测节点并获取负载, 失败返回 None
+    async fn heartbeat(&self) -> Option<MediaNodeLoad>;
+
+    // 收流统计, 仅内置收流节点支持
+    fn stream_stats(&self, _stream_name: &str) -> Option<StreamStats> {
+        None
+    }
+
+    // 节点自身提供的播放地址, 内置收流节点由本服务转封装输出
+    fn play_urls(&self, _stream_name: &str) -> Option<PlayUrls> {
+        None
+    }
+
+    // 开始向对端发送 RTP, 返回本地发送端口, 仅 ZLM 节点支持
+    async fn start_send_rtp(&self, _params: &SendRtp) -> Option<u16> {
+        None
+    }
+
+    async fn stop_send_rtp(&self, _app: &str, _stream: &str, _ssrc: &str) -> bool {
+        false
+    }
+
+    // 解复用后的帧, 仅内置收流节点提供
+    fn subscribe(
+        &self,
+        _stream_name: &str,
+    ) -> Option<tokio::sync::broadcast::Receiver<Arc<ps::AccessUnit>>> {
+        None
+    }
+}
+
+pub fn create_media_server(node: &MediaNode) -> Option<Box<dyn MediaServer>> {
+    match node.kind.as_str() {
+        "zlm" => Some(Box::new(zlm::ZlmMediaServer::new(node))),
+        "embedded" => Some(Box::new(embedded::EmbeddedMediaServer::new(node))),
+        _ => {
+            tracing::error!("not impl media server: {}, id: {}", &node.kind, &node.id);
+            None
+        }
+    }
+}
+
+// 媒体节点上的流名称
+pub fn stream_name(gb_code: &str, stream_id: u32) -> String {
+    format!("{}_{}", gb_code, stream_id)
+}
+
+// 客户端推送语音广播音频的 app, 流名称为 gb_code_channel_id
+pub static BROADCAST_APP: &str = "broadcast";
+
+// 客户端推送对讲音频的 app, 流名称与设备音频的收流相同
+pub static TALK_APP: &str = "talk";
+
+// 媒体节点注册表, 按负载均衡策略选择节点并记录每路流所在的节点
+pub struct MediaManager {
+    pub servers: Vec<Box<dyn MediaServer>>,
+    pub states: std::sync::Mutex<Vec<MediaNodeState>>,
+    pub strategy: BalanceStrategy,
+    pub heartbeat_seconds: u32,
+    pub node_timeout_seconds: u32,
+    pub ports: Vec<std::sync::Mutex<PortPool>>,
+    // 流名称 -> (节点序号, 占用的端口池端口, 节点实际使用的收流端口)
+    pub streams: DashMap<String, (usize, Option<u16>, u16)>,
+    // setup:active 的流在设备应答后才能连接, Call-ID -> 流名称
+    pub pending_connects: DashMap<String, String>,
+    // 正在向设备发送音频的流, 流名称 -> (节点序号, 发送参数)
+    pub senders: DashMap<String, (usize, SendRtp)>,
+    pub hls: hls::HlsManager,
+}
+
+impl MediaManager {
+    pub fn new(config: &Config) -> Self {
+        let strategy = BalanceStrategy::from_str(&config.media_balance).unwrap_or_else(|e| {
+            tracing::error!("media_balance error: {}, e: {}", &config.media_balance, e);
+            BalanceStrategy::RoundRobin
+        });
+
+        // 未收到心跳的节点从启动时刻开始计算超时
+        let start_ts = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .expect("Time went backwards")
+            .as_secs() as u32;
+        let mut servers = vec![];
+        let mut states = vec![];
+        let mut ports = vec![];
+        for node in &config.media_nodes {
+            if let Some(server) = create_media_server(node) {
+                servers.push(server);
+                ports.push(std::sync::Mutex::new(PortPool::new(
+                    node.rtp_port_min,
+                    node.rtp_port_max,
+                )));
+                states.push(MediaNodeState {
+                    id: node.id.clone(),
+                    ip: node.ip.clone(),
+                    weight: node.weight,
+                    heartbeat_ts: start_ts,
+                    healthy: true,
+                    ..Default::default()
+                });
+            }
+        }
+
+        MediaManager {
+            servers,
+            states: std::sync::Mutex::new(states),
+            strategy,
+            heartbeat_seconds: config.media_heartbeat_seconds,
+            node_timeout_seconds: config.media_node_timeout_seconds,
+            ports,
+            streams: DashMap::new(),
+            pending_connects: DashMap::new(),
+            senders: DashMap::new(),
+            hls: hls::HlsManager::new(),
+        }
+    }
+
+    // 成功时返回 (收流地址, 收流端口)
+    pub async fn open_rtp_server(
+        &self,
+        stream_name: &str,
+        transport: RtpTransport,
+    ) -> Result<(String, u16), MediaError> {
+        let candidates = balance::candidates(self.strategy, &mut self.states.lock().unwrap());
+        let mut exhausted = !candidates.is_empty();
+        for index in candidates {
+            let Some(port) = self.ports[index].lock().unwrap().allocate() else {
+                tracing::warn!("rtp port exhausted, node: {}", self.servers[index].id());
+                continue;
+            };
+            exhausted = false;
+
+            let server = &self.servers[index];
+            match server.open_rtp_server(stream_name, port, transport).await {
+                Some(recv_port) => {
+                    // 节点未使用分配的端口时立即归还, 关闭时不再释放
+                    let allocated = if recv_port == port {
+                        Some(port)
+                    } else {
+                        self.ports[index].lock().unwrap().release(port);
+                        None
+                    };
+                    self.streams
+                        .insert(stream_name.to_string(), (index, allocated, recv_port));
+                    self.states.lock().unwrap()[index].streams += 1;
+                    return Ok((server.ip().to_string(), recv_port));
+                }
+                None => {
+                    self.ports[index].lock().unwrap().release(port);
+                }
+            }
+        }
+
+        let error = if exhausted {
+            MediaError::PortExhausted
+        } else {
+            MediaError::Unavailable
+        };
+        tracing::error!("open rtp server error: {}, stream: {}", error, stream_name);
+        Err(error)
+    }
+
+    pub async fn close_rtp_server(&self, stream_name: &str) -> bool {
+        self.pending_connects.retain(|_, name| name != stream_name);
+        // 对讲的收发共用流名称, 收流关闭时一并停止发送
+        self.stop_send_rtp(stream_name).await;
+        match self.streams.remove(stream_name) {
+            Some((_, (index, allocated, _))) => {
+                if let Some(port) = allocated {
+                    self.ports[index].lock().unwrap().release(port);
+                }
+                {
+                    let mut states = self.states.lock().unwrap();
+                    states[index].streams = states[index].streams.saturating_sub(1);
+                }
+                self.servers[index].close_rtp_server(stream_name).await
+            }
+            None => false,
+        }
+    }
+
+    // 按负载均衡策略选出一个可用节点, 不占用收流端口
+    pub fn select_node(&self) -> Result<usize, MediaError> {
+        balance::candidates(self.strategy, &mut self.states.lock().unwrap())
+            .first()
+            .copied()
+            .ok_or(MediaError::Unavailable)
+    }
+
+    pub fn node_ip(&self, index: usize) -> Option<&str> {
+        self.servers.get(index).map(|server| server.ip())
+    }
+
+    pub async fn start_send_rtp(&self, index: usize, params: &SendRtp) -> Option<u16> {
+        let port = self.servers.get(index)?.start_send_rtp(params).await?;
+        self.senders
+            .insert(params.stream.clone(), (index, params.clone()));
+        Some(port)
+    }
+
+    pub async fn stop_send_rtp(&self, stream: &str) -> bool {
+        match self.senders.remove(stream) {
+            Some((_, (index, params))) => {
+                self.servers[index]
+                    .stop_send_rtp(&params.app, &params.stream, &params.ssrc)
+                    .await
+            }
+            None => false,
+        }
+    }
+
+    pub fn expect_connect(&self, caller_id: &str, stream_name: &str) {
+        self.pending_connects
+            .insert(caller_id.to_string(), stream_name.to_string());
+    }
+
+    // 设备应答 INVITE 后, 按应答 SDP 中的地址发起 TCP 连接
+    pub async fn on_answer(&self, caller_id: &str, sdp: &sdp_rs::SessionDescription) -> bool {
+        let Some((_, stream_name)) = self.pending_connects.remove(caller_id) else {
+            return false;
+        };
+        let Some(media) = sdp.media_descriptions.first() else {
+            return false;
+        };
+        let Some(connection) = media.connections.first().or(sdp.connection.as_ref()) else {
+            return false;
+        };
+        let Some(index) = self.streams.get(&stream_name).map(|stream| stream.0) else {
+            return false;
+        };
+
+        self.servers[index]
+            .connect_rtp_server(
+                &stream_name,
+                &connection.connection_address.base.to_string(),
+                media.media.port,
+            )
+            .await
+    }
+
+    pub fn stream_stats(&self, stream_name: &str) -> Option<StreamStats> {
+        let index = self.streams.get(stream_name)?.0;
+        self.servers[index].stream_stats(stream_name)
+    }
+
+    pub fn subscribe(
+        &self,
+        stream_name: &str,
+    ) -> Option<tokio::sync::broadcast::Receiver<Arc<ps::AccessUnit>>> {
+        let index = self.streams.get(stream_name)?.0;
+        self.servers[index].subscribe(stream_name)
+    }
+
+    pub fn play_urls(&self, stream_name: &str) -> Option<PlayUrls> {
+        let index = self.streams.get(stream_name)?.0;
+        self.servers[index].play_urls(stream_name)
+    }
+
+    pub async fn stream_exists(&self, stream_name: &str) -> bool {
+        let index = match self.streams.get(stream_name) {
+            Some(stream) => stream.0,
+            None => return false,
+        };
+        self.servers[index].stream_exists(stream_name).await
+    }
+
+    // 刷新所有节点负载, 超时未响应的节点不再参与选择
+    pub async fn heartbeat(&self) {
+        for (index, server) in self.servers.iter().enumerate() {
+            let load = server.heartbeat().await;
+            let ts = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .expect("Time went backwards")
+                .as_secs() as u32;
+
+            let mut states = self.states.lock().unwrap();
+            let state = &mut states[index];
+            match load {
+                Some(load) => {
+                    if !state.healthy {
+                        tracing::info!("media node recovered: {}", state.id);
+                    }
+                    state.streams = load.streams;
+                    state.bandwidth = load.bandwidth;
+                    state.heartbeat_ts = ts;
+                    state.healthy = true;
+                }
+                None => {
+                    if state.healthy
+                        && ts.saturating_sub(state.heartbeat_ts) > self.node_timeout_seconds
+                    {
+                        tracing::warn!("media node unhealthy: {}", state.id);
+                        state.healthy = false;
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn nodes(&self) -> Vec<MediaNodeState> {
+        let mut nodes = self.states.lock().unwrap().clone();
+        for (index, node) in nodes.iter_mut().enumerate() {
+            let ports = self.ports[index].lock().unwrap();
+            node.rtp_ports_used = ports.in_use();
+            node.rtp_ports_total = ports.capacity();
+        }
+        nodes
+    }
+}
+
+pub async fn run_forever(sip_handler: Arc<SipHandler>) {
+    if sip_handler.media.servers.is_empty() {
+        return;
+    }
+
+    let interval =
+        std::time::Duration::from_secs(sip_handler.media.heartbeat_seconds.max(1) as u64);
+    loop {
+        sip_handler.media.heartbeat().await;
+        tokio::time::sleep(interval).await;
+    }
+}
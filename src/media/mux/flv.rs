@@ -0,0 +1,214 @@
+use super::{audio_frames, to_length_prefixed, AudioConfig, VideoConfig};
+use crate::media::ps::{AccessUnit, StreamType};
+
+static TAG_AUDIO: u8 = 8;
+static TAG_VIDEO: u8 = 9;
+
+static CODEC_H264: u8 = 7;
+// 国内通用的 H.265 扩展编号
+static CODEC_H265: u8 = 12;
+
+fn tag(kind: u8, timestamp: u32, data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(15 + data.len());
+    out.push(kind);
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes()[1..]);
+    out.extend_from_slice(&timestamp.to_be_bytes()[1..]);
+    out.push((timestamp >> 24) as u8);
+    out.extend_from_slice(&[0, 0, 0]);
+    out.extend_from_slice(data);
+    out.extend_from_slice(&(11 + data.len() as u32).to_be_bytes());
+    out
+}
+
+// packet_type 0 为序列头, 1 为帧数据
+fn video_tag(
+    config: &VideoConfig,
+    keyframe: bool,
+    packet_type: u8,
+    timestamp: u32,
+    cts: i32,
+    data: &[u8],
+) -> Vec<u8> {
+    let codec = if config.is_h265() {
+        CODEC_H265
+    } else {
+        CODEC_H264
+    };
+    let frame_type = if keyframe { 1 } else { 2 };
+
+    let mut body = vec![(frame_type << 4) | codec, packet_type];
+    body.extend_from_slice(&cts.to_be_bytes()[1..]);
+    body.extend_from_slice(data);
+    tag(TAG_VIDEO, timestamp, &body)
+}
+
+// FLV 封装, 时间戳以首个关键帧为零点, 单位毫秒
+#[derive(Debug, Default)]
+pub struct FlvMuxer {
+    video: Option<VideoConfig>,
+    audio_header_sent: bool,
+    base_dts: u64,
+}
+
+impl FlvMuxer {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    // 文件头, 声明音视频都存在
+    pub fn header() -> Vec<u8> {
+        vec![b'F', b'L', b'V', 0x01, 0x05, 0, 0, 0, 9, 0, 0, 0, 0]
+    }
+
+    pub fn push(&mut self, unit: &AccessUnit) -> Vec<u8> {
+        if unit.stream_type.is_video() {
+            self.push_video(unit)
+        } else {
+            self.push_audio(unit)
+        }
+    }
+
+    fn timestamp(&self, ts: u64) -> u32 {
+        (ts.saturating_sub(self.base_dts) / 90) as u32
+    }
+
+    fn push_video(&mut self, unit: &AccessUnit) -> Vec<u8> {
+        let dts = unit.dts.or(unit.pts).unwrap_or_default();
+        let pts = unit.pts.unwrap_or(dts);
+        let mut out = vec![];
+
+        if unit.keyframe {
+            // 参数集变化时重新下发序列头
+            if let Some(config) = VideoConfig::from_unit(unit) {
+                if self.video.as_ref() != Some(&config) {
+                    if self.video.is_none() {
+                        self.base_dts = dts;
+                    }
+                    let timestamp = self.timestamp(dts);
+                    out.extend(video_tag(
+                        &config,
+                        true,
+                        0,
+                        timestamp,
+                        0,
+                        &config.decoder_config(),
+                    ));
+                    self.video = Some(config);
+                }
+            }
+        }
+        let Some(config) = self.video.as_ref() else {
+            return out;
+        };
+
+        let cts = (pts as i64 - dts as i64) / 90;
+        let data = to_length_prefixed(unit.stream_type, &unit.data);
+        out.extend(video_tag(
+            config,
+            unit.keyframe,
+            1,
+            self.timestamp(dts),
+            cts as i32,
+            &data,
+        ));
+        out
+    }
+
+    fn push_audio(&mut self, unit: &AccessUnit) -> Vec<u8> {
+        // 视频开始前的音频丢弃, 保证时间戳从 0 开始
+        if self.video.is_none() {
+            return vec![];
+        }
+        let Some(config) = AudioConfig::from_unit(unit) else {
+            return vec![];
+        };
+
+        let timestamp = self.timestamp(unit.pts.unwrap_or_default());
+        let mut out = vec![];
+        match config.codec {
+            StreamType::Aac => {
+                if !self.audio_header_sent {
+                    self.audio_header_sent = true;
+                    let body = [&[0xAF, 0x00], config.specific_config.as_slice()].concat();
+                    out.extend(tag(TAG_AUDIO, timestamp, &body));
+                }
+                for frame in audio_frames(unit) {
+                    let body = [&[0xAF, 0x01], frame].concat();
+                    out.extend(tag(TAG_AUDIO, timestamp, &body));
+                }
+            }
+            codec => {
+                // 7: G.711A, 8: G.711U, 16 位单声道
+                let flags = if codec == StreamType::G711U {
+                    0x82
+                } else {
+                    0x72
+                };
+                let body = [&[flags], unit.data.as_slice()].concat();
+                out.extend(tag(TAG_AUDIO, timestamp, &body));
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FlvMuxer;
+    use crate::media::ps::{AccessUnit, StreamType};
+
+    fn unit(stream_type: StreamType, pts: u64, keyframe: bool, data: &[u8]) -> AccessUnit {
+        AccessUnit {
+            stream_id: if stream_type.is_video() { 0xE0 } else { 0xC0 },
+            stream_type,
+            pts: Some(pts),
+            dts: Some(pts),
+            keyframe,
+            data: data.to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_tags() {
+        let keyframe = [
+            0x00, 0x00, 0x00, 0x01, 0x67, 0x42, 0xC0, 0x1E, 0xDA, 0x05, 0x82, 0x59, 0x00, 0x00,
+            0x00, 0x01, 0x68, 0xCE, 0x3C, 0x80, 0x00, 0x00, 0x00, 0x01, 0x65, 0x88, 0x84,
+        ];
+        let mut muxer = FlvMuxer::new();
+        let mut out = FlvMuxer::header();
+        out.extend(muxer.push(&unit(StreamType::G711U, 1000, true, &[0xFF; 160])));
+        out.extend(muxer.push(&unit(StreamType::H264, 9000, true, &keyframe)));
+        out.extend(muxer.push(&unit(StreamType::G711U, 9000, true, &[0xFF; 160])));
+        out.extend(muxer.push(&unit(
+            StreamType::H264,
+            12600,
+            false,
+            &[0x00, 0x00, 0x01, 0x41, 0x9A],
+        )));
+
+        // (类型, 时间戳, 首字节)
+        let mut tags = vec![];
+        let mut pos = 13;
+        while pos < out.len() {
+            let size = u32::from_be_bytes([0, out[pos + 1], out[pos + 2], out[pos + 3]]) as usize;
+            let ts = u32::from_be_bytes([out[pos + 7], out[pos + 4], out[pos + 5], out[pos + 6]]);
+            let prev =
+                u32::from_be_bytes(out[pos + 11 + size..pos + 15 + size].try_into().unwrap());
+            assert_eq!(prev as usize, 11 + size);
+            tags.push((out[pos], ts, out[pos + 11], out[pos + 12]));
+            pos += 15 + size;
+        }
+        assert_eq!(pos, out.len());
+
+        // 视频前的音频丢弃, 序列头后依次为关键帧/音频/P 帧
+        assert_eq!(
+            tags,
+            vec![
+                (9, 0, 0x17, 0x00),
+                (9, 0, 0x17, 0x01),
+                (8, 0, 0x82, 0xFF),
+                (9, 40, 0x27, 0x01)
+            ]
+        );
+    }
+}
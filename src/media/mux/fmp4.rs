@@ -0,0 +1,543 @@
+use super::{audio_frame_samples, audio_frames, to_length_prefixed, AudioConfig, VideoConfig};
+use crate::media::ps::{AccessUnit, StreamType};
+
+static VIDEO_TRACK_ID: u32 = 1;
+static AUDIO_TRACK_ID: u32 = 2;
+static VIDEO_TIMESCALE: u32 = 90000;
+
+// 未知时长时按 25fps 估算
+static DEFAULT_VIDEO_DURATION: u32 = 3600;
+
+static UNITY_MATRIX: [u32; 9] = [0x10000, 0, 0, 0, 0x10000, 0, 0, 0, 0x40000000];
+
+fn mp4_box(kind: &[u8; 4], payload: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(8 + payload.len());
+    out.extend_from_slice(&(8 + payload.len() as u32).to_be_bytes());
+    out.extend_from_slice(kind);
+    out.extend_from_slice(payload);
+    out
+}
+
+fn full_box(kind: &[u8; 4], version: u8, flags: u32, payload: &[u8]) -> Vec<u8> {
+    let mut body = ((version as u32) << 24 | (flags & 0x00FF_FFFF))
+        .to_be_bytes()
+        .to_vec();
+    body.extend_from_slice(payload);
+    mp4_box(kind, &body)
+}
+
+fn matrix() -> Vec<u8> {
+    UNITY_MATRIX.iter().flat_map(|v| v.to_be_bytes()).collect()
+}
+
+#[derive(Debug, Clone)]
+struct Sample {
+    duration: u32,
+    flags: u32,
+    composition_offset: i32,
+    data: Vec<u8>,
+}
+
+// 分片 MP4 封装: 每个 GOP 输出一个 moof+mdat, 首个分片前输出 ftyp+moov
+#[derive(Debug, Default)]
+pub struct Fmp4Muxer {
+    video: Option<VideoConfig>,
+    audio: Option<AudioConfig>,
+    // moov 推迟到首个分片前写出, 以便包含关键帧之后才到达的音频
+    init_written: bool,
+    sequence: u32,
+    // 首个关键帧的 DTS, 各轨时间以此为零点
+    base_dts: u64,
+    // 视频样本的时长要等到下一帧才知道
+    last_video: Option<(u64, Sample)>,
+    video_samples: Vec<Sample>,
+    video_decode_time: u64,
+    audio_samples: Vec<Sample>,
+    audio_decode_time: u64,
+    audio_next_time: Option<u64>,
+}
+
+impl Fmp4Muxer {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    // 初始化段 ftyp+moov, 供 HLS 等需要单独下发的场景
+    pub fn init_segment(&self) -> Option<Vec<u8>> {
+        if !self.init_written {
+            return None;
+        }
+        let video = self.video.as_ref()?;
+        let mut out = mp4_box(
+            b"ftyp",
+            &[
+                b"isom".as_slice(),
+                &[0, 0, 2, 0],
+                b"isom",
+                b"iso5",
+                b"iso6",
+                b"mp41",
+            ]
+            .concat(),
+        );
+        out.extend(self.moov(video));
+        Some(out)
+    }
+
+    // 返回需要追加写入的数据
+    pub fn push(&mut self, unit: &AccessUnit) -> Vec<u8> {
+        if !unit.stream_type.is_video() {
+            self.push_audio(unit);
+            return vec![];
+        }
+
+        if self.video.is_none() {
+            // 等待带参数集的关键帧
+            if !unit.keyframe {
+                return vec![];
+            }
+            let Some(config) = VideoConfig::from_unit(unit) else {
+                return vec![];
+            };
+            self.video = Some(config);
+            self.base_dts = unit.dts.or(unit.pts).unwrap_or_default();
+        }
+        self.push_video(unit)
+    }
+
+    // 输出缓存中的样本
+    pub fn finish(&mut self) -> Vec<u8> {
+        if let Some((time, mut sample)) = self.last_video.take() {
+            sample.duration = self
+                .video_samples
+                .last()
+                .map(|s| s.duration)
+                .unwrap_or(DEFAULT_VIDEO_DURATION);
+            if self.video_samples.is_empty() {
+                self.video_decode_time = time;
+            }
+            self.video_samples.push(sample);
+        }
+        self.fragment()
+    }
+
+    // 关键帧之前的样本组成一个分片
+    fn push_video(&mut self, unit: &AccessUnit) -> Vec<u8> {
+        let dts = unit.dts.or(unit.pts).unwrap_or_default();
+        let pts = unit.pts.unwrap_or(dts);
+        let time = dts.saturating_sub(self.base_dts);
+
+        if let Some((last_time, mut sample)) = self.last_video.take() {
+            sample.duration = match time.saturating_sub(last_time) {
+                0 => DEFAULT_VIDEO_DURATION,
+                duration => duration as u32,
+            };
+            if self.video_samples.is_empty() {
+                self.video_decode_time = last_time;
+            }
+            self.video_samples.push(sample);
+        }
+
+        let out = if unit.keyframe {
+            self.fragment()
+        } else {
+            vec![]
+        };
+
+        self.last_video = Some((
+            time,
+            Sample {
+                duration: 0,
+                flags: if unit.keyframe {
+                    0x0200_0000
+                } else {
+                    0x0101_0000
+                },
+                composition_offset: (pts as i64 - dts as i64) as i32,
+                data: to_length_prefixed(unit.stream_type, &unit.data),
+            },
+        ));
+        out
+    }
+
+    fn push_audio(&mut self, unit: &AccessUnit) {
+        if self.audio.is_none() && !self.init_written {
+            self.audio = AudioConfig::from_unit(unit);
+        }
+        let Some(audio) = self.audio.as_ref() else {
+            return;
+        };
+        let pts = unit.pts.unwrap_or_default();
+        if self.video.is_none() || unit.stream_type != audio.codec || pts < self.base_dts {
+            return;
+        }
+
+        // 音频按采样数连续累加, 只用首帧时间戳定位
+        let codec = audio.codec;
+        let next_time = *self.audio_next_time.get_or_insert(
+            (pts - self.base_dts) * audio.sample_rate as u64 / VIDEO_TIMESCALE as u64,
+        );
+        if self.audio_samples.is_empty() {
+            self.audio_decode_time = next_time;
+        }
+
+        let mut next_time = next_time;
+        for frame in audio_frames(unit) {
+            let duration = audio_frame_samples(codec, frame);
+            next_time += duration as u64;
+            self.audio_samples.push(Sample {
+                duration,
+                flags: 0x0200_0000,
+                composition_offset: 0,
+                data: frame.to_vec(),
+            });
+        }
+        self.audio_next_time = Some(next_time);
+    }
+
+    fn fragment(&mut self) -> Vec<u8> {
+        if self.video_samples.is_empty() {
+            return vec![];
+        }
+
+        let mut out = vec![];
+        if !self.init_written {
+            self.init_written = true;
+            out.extend(self.init_segment().unwrap_or_default());
+        }
+
+        self.sequence += 1;
+        let mut tracks = vec![(
+            VIDEO_TRACK_ID,
+            self.video_decode_time,
+            std::mem::take(&mut self.video_samples),
+            true,
+        )];
+        if !self.audio_samples.is_empty() {
+            tracks.push((
+                AUDIO_TRACK_ID,
+                self.audio_decode_time,
+                std::mem::take(&mut self.audio_samples),
+                false,
+            ));
+        }
+
+        // 先按 0 偏移计算 moof 大小, 再回填 data_offset
+        let moof_len = self.moof(&tracks, 0).len() as u32;
+        out.extend(self.moof(&tracks, moof_len + 8));
+
+        let mdat: Vec<u8> = tracks
+            .iter()
+            .flat_map(|(_, _, samples, _)| samples.iter().flat_map(|s| s.data.iter().copied()))
+            .collect();
+        out.extend(mp4_box(b"mdat", &mdat));
+        out
+    }
+
+    fn moof(&self, tracks: &[(u32, u64, Vec<Sample>, bool)], data_offset: u32) -> Vec<u8> {
+        let mut payload = full_box(b"mfhd", 0, 0, &self.sequence.to_be_bytes());
+        let mut offset = data_offset;
+        for (track_id, decode_time, samples, is_video) in tracks {
+            let mut traf = full_box(b"tfhd", 0, 0x020000, &track_id.to_be_bytes());
+            traf.extend(full_box(b"tfdt", 1, 0, &decode_time.to_be_bytes()));
+
+            let flags = if *is_video { 0x000F01 } else { 0x000301 };
+            let mut trun = (samples.len() as u32).to_be_bytes().to_vec();
+            trun.extend_from_slice(&offset.to_be_bytes());
+            for sample in samples {
+                trun.extend_from_slice(&sample.duration.to_be_bytes());
+                trun.extend_from_slice(&(sample.data.len() as u32).to_be_bytes());
+                if *is_video {
+                    trun.extend_from_slice(&sample.flags.to_be_bytes());
+                    trun.extend_from_slice(&sample.composition_offset.to_be_bytes());
+                }
+                offset += sample.data.len() as u32;
+            }
+            traf.extend(full_box(b"trun", 1, flags, &trun));
+            payload.extend(mp4_box(b"traf", &traf));
+        }
+        mp4_box(b"moof", &payload)
+    }
+
+    fn moov(&self, video: &VideoConfig) -> Vec<u8> {
+        let mut mvhd = vec![0u8; 8];
+        mvhd.extend_from_slice(&1000u32.to_be_bytes());
+        mvhd.extend_from_slice(&0u32.to_be_bytes());
+        mvhd.extend_from_slice(&0x0001_0000u32.to_be_bytes());
+        mvhd.extend_from_slice(&0x0100u16.to_be_bytes());
+        mvhd.extend_from_slice(&[0u8; 10]);
+        mvhd.extend(matrix());
+        mvhd.extend_from_slice(&[0u8; 24]);
+        mvhd.extend_from_slice(&(AUDIO_TRACK_ID + 1).to_be_bytes());
+
+        let mut payload = full_box(b"mvhd", 0, 0, &mvhd);
+        payload.extend(self.video_trak(video));
+        let mut mvex = trex(VIDEO_TRACK_ID);
+        if let Some(audio) = self.audio.as_ref() {
+            payload.extend(self.audio_trak(audio));
+            mvex.extend(trex(AUDIO_TRACK_ID));
+        }
+        payload.extend(mp4_box(b"mvex", &mvex));
+        mp4_box(b"moov", &payload)
+    }
+
+    fn video_trak(&self, video: &VideoConfig) -> Vec<u8> {
+        let mut entry = vec![0u8; 6];
+        entry.extend_from_slice(&1u16.to_be_bytes());
+        entry.extend_from_slice(&[0u8; 16]);
+        entry.extend_from_slice(&video.width.to_be_bytes());
+        entry.extend_from_slice(&video.height.to_be_bytes());
+        entry.extend_from_slice(&0x0048_0000u32.to_be_bytes());
+        entry.extend_from_slice(&0x0048_0000u32.to_be_bytes());
+        entry.extend_from_slice(&0u32.to_be_bytes());
+        entry.extend_from_slice(&1u16.to_be_bytes());
+        entry.extend_from_slice(&[0u8; 32]);
+        entry.extend_from_slice(&0x0018u16.to_be_bytes());
+        entry.extend_from_slice(&0xFFFFu16.to_be_bytes());
+        let sample_entry = if video.is_h265() {
+            entry.extend(mp4_box(b"hvcC", &video.decoder_config()));
+            mp4_box(b"hvc1", &entry)
+        } else {
+            entry.extend(mp4_box(b"avcC", &video.decoder_config()));
+            mp4_box(b"avc1", &entry)
+        };
+
+        let vmhd = full_box(b"vmhd", 0, 1, &[0u8; 8]);
+        trak(
+            VIDEO_TRACK_ID,
+            VIDEO_TIMESCALE,
+            (video.width, video.height),
+            b"vide",
+            vmhd,
+            sample_entry,
+        )
+    }
+
+    fn audio_trak(&self, audio: &AudioConfig) -> Vec<u8> {
+        let mut entry = vec![0u8; 6];
+        entry.extend_from_slice(&1u16.to_be_bytes());
+        entry.extend_from_slice(&[0u8; 8]);
+        entry.extend_from_slice(&audio.channels.to_be_bytes());
+        entry.extend_from_slice(&16u16.to_be_bytes());
+        entry.extend_from_slice(&[0u8; 4]);
+        entry.extend_from_slice(&(audio.sample_rate << 16).to_be_bytes());
+        let sample_entry = match audio.codec {
+            StreamType::Aac => {
+                entry.extend(esds(&audio.specific_config));
+                mp4_box(b"mp4a", &entry)
+            }
+            StreamType::G711U => mp4_box(b"ulaw", &entry),
+            _ => mp4_box(b"alaw", &entry),
+        };
+
+        let smhd = full_box(b"smhd", 0, 0, &[0u8; 4]);
+        trak(
+            AUDIO_TRACK_ID,
+            audio.sample_rate,
+            (0, 0),
+            b"soun",
+            smhd,
+            sample_entry,
+        )
+    }
+}
+
+fn trex(track_id: u32) -> Vec<u8> {
+    let mut payload = track_id.to_be_bytes().to_vec();
+    payload.extend_from_slice(&1u32.to_be_bytes());
+    payload.extend_from_slice(&[0u8; 12]);
+    full_box(b"trex", 0, 0, &payload)
+}
+
+fn trak(
+    track_id: u32,
+    timescale: u32,
+    (width, height): (u16, u16),
+    handler: &[u8; 4],
+    media_header: Vec<u8>,
+    sample_entry: Vec<u8>,
+) -> Vec<u8> {
+    let is_audio = handler == b"soun";
+
+    let mut tkhd = vec![0u8; 8];
+    tkhd.extend_from_slice(&track_id.to_be_bytes());
+    tkhd.extend_from_slice(&[0u8; 4]);
+    tkhd.extend_from_slice(&0u32.to_be_bytes());
+    tkhd.extend_from_slice(&[0u8; 8]);
+    tkhd.extend_from_slice(&[0u8; 4]);
+    tkhd.extend_from_slice(&(if is_audio { 0x0100u16 } else { 0 }).to_be_bytes());
+    tkhd.extend_from_slice(&[0u8; 2]);
+    tkhd.extend(matrix());
+    tkhd.extend_from_slice(&((width as u32) << 16).to_be_bytes());
+    tkhd.extend_from_slice(&((height as u32) << 16).to_be_bytes());
+
+    let mut mdhd = vec![0u8; 8];
+    mdhd.extend_from_slice(&timescale.to_be_bytes());
+    mdhd.extend_from_slice(&0u32.to_be_bytes());
+    // 语言 und
+    mdhd.extend_from_slice(&0x55C4u16.to_be_bytes());
+    mdhd.extend_from_slice(&[0u8; 2]);
+
+    let mut hdlr = vec![0u8; 4];
+    hdlr.extend_from_slice(handler);
+    hdlr.extend_from_slice(&[0u8; 12]);
+    hdlr.extend_from_slice(if is_audio {
+        b"SoundHandler\0".as_slice()
+    } else {
+        b"VideoHandler\0".as_slice()
+    });
+
+    let dref = full_box(
+        b"dref",
+        0,
+        0,
+        &[1u32.to_be_bytes().as_slice(), &full_box(b"url ", 0, 1, &[])].concat(),
+    );
+
+    let mut stsd = 1u32.to_be_bytes().to_vec();
+    stsd.extend(sample_entry);
+    let mut stbl = full_box(b"stsd", 0, 0, &stsd);
+    for kind in [b"stts", b"stsc", b"stco"] {
+        stbl.extend(full_box(kind, 0, 0, &[0u8; 4]));
+    }
+    stbl.extend(full_box(b"stsz", 0, 0, &[0u8; 8]));
+
+    let mut minf = media_header;
+    minf.extend(mp4_box(b"dinf", &dref));
+    minf.extend(mp4_box(b"stbl", &stbl));
+
+    let mut mdia = full_box(b"mdhd", 0, 0, &mdhd);
+    mdia.extend(full_box(b"hdlr", 0, 0, &hdlr));
+    mdia.extend(mp4_box(b"minf", &minf));
+
+    let mut payload = full_box(b"tkhd", 0, 0x03, &tkhd);
+    payload.extend(mp4_box(b"mdia", &mdia));
+    mp4_box(b"trak", &payload)
+}
+
+// MPEG-4 描述符长度按单字节编码, AAC 配置足够短
+fn descriptor(tag: u8, payload: &[u8]) -> Vec<u8> {
+    let mut out = vec![tag, payload.len() as u8];
+    out.extend_from_slice(payload);
+    out
+}
+
+fn esds(specific_config: &[u8]) -> Vec<u8> {
+    let mut decoder = vec![0x40, 0x15, 0x00, 0x00, 0x00];
+    decoder.extend_from_slice(&[0u8; 8]);
+    decoder.extend(descriptor(0x05, specific_config));
+
+    let mut es = vec![0x00, AUDIO_TRACK_ID as u8, 0x00];
+    es.extend(descriptor(0x04, &decoder));
+    es.extend(descriptor(0x06, &[0x02]));
+    full_box(b"esds", 0, 0, &descriptor(0x03, &es))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Fmp4Muxer;
+    use crate::media::ps::{AccessUnit, StreamType};
+
+    // 返回 (类型, 在 data 中的起始位置, 内容)
+    fn boxes(data: &[u8]) -> Vec<(String, usize, &[u8])> {
+        let mut out = vec![];
+        let mut pos = 0;
+        while pos + 8 <= data.len() {
+            let size = u32::from_be_bytes(data[pos..pos + 4].try_into().unwrap()) as usize;
+            assert!(size >= 8 && pos + size <= data.len(), "bad box size");
+            let kind = String::from_utf8_lossy(&data[pos + 4..pos + 8]).to_string();
+            out.push((kind, pos, &data[pos + 8..pos + size]));
+            pos += size;
+        }
+        assert_eq!(pos, data.len());
+        out
+    }
+
+    fn child<'a>(data: &'a [u8], kind: &str) -> Vec<&'a [u8]> {
+        boxes(data)
+            .into_iter()
+            .filter(|(k, _, _)| k == kind)
+            .map(|(_, _, payload)| payload)
+            .collect()
+    }
+
+    fn video(pts: u64, keyframe: bool) -> AccessUnit {
+        let data = if keyframe {
+            vec![
+                0x00, 0x00, 0x00, 0x01, 0x67, 0x42, 0xC0, 0x1E, 0xDA, 0x05, 0x82, 0x59, 0x00, 0x00,
+                0x00, 0x01, 0x68, 0xCE, 0x3C, 0x80, 0x00, 0x00, 0x00, 0x01, 0x65, 0x88, 0x84,
+            ]
+        } else {
+            vec![0x00, 0x00, 0x00, 0x01, 0x41, 0x9A, 0x02]
+        };
+        AccessUnit {
+            stream_id: 0xE0,
+            stream_type: StreamType::H264,
+            pts: Some(pts),
+            dts: Some(pts),
+            keyframe,
+            data,
+        }
+    }
+
+    fn audio(pts: u64) -> AccessUnit {
+        AccessUnit {
+            stream_id: 0xC0,
+            stream_type: StreamType::G711A,
+            pts: Some(pts),
+            dts: Some(pts),
+            keyframe: true,
+            data: vec![0xD5; 320],
+        }
+    }
+
+    #[test]
+    fn test_fragments() {
+        let mut muxer = Fmp4Muxer::new();
+        let mut out = vec![];
+        // 首帧之前的 P 帧丢弃
+        out.extend(muxer.push(&video(0, false)));
+        out.extend(muxer.push(&video(90000, true)));
+        out.extend(muxer.push(&audio(90000)));
+        out.extend(muxer.push(&video(93600, false)));
+        out.extend(muxer.push(&audio(93600)));
+        assert!(muxer.init_segment().is_none());
+        out.extend(muxer.push(&video(97200, true)));
+        out.extend(muxer.finish());
+
+        let top = boxes(&out);
+        let kinds: Vec<_> = top.iter().map(|(k, _, _)| k.as_str()).collect();
+        assert_eq!(kinds, ["ftyp", "moov", "moof", "mdat", "moof", "mdat"]);
+        assert_eq!(muxer.init_segment().unwrap(), out[..top[2].1]);
+
+        // 音频在首个关键帧之后到达, 仍应包含音频轨
+        let moov = top[1].2;
+        assert_eq!(child(moov, "trak").len(), 2);
+        assert_eq!(child(child(moov, "mvex")[0], "trex").len(), 2);
+
+        // 第一个分片: 视频 2 帧 + 音频 2 帧
+        let (moof_pos, moof) = (top[2].1, top[2].2);
+        let mdat_pos = top[3].1;
+        let trafs = child(moof, "traf");
+        assert_eq!(trafs.len(), 2);
+
+        let trun = child(trafs[0], "trun")[0];
+        assert_eq!(u32::from_be_bytes(trun[4..8].try_into().unwrap()), 2);
+        let data_offset = u32::from_be_bytes(trun[8..12].try_into().unwrap()) as usize;
+        assert_eq!(moof_pos + data_offset, mdat_pos + 8);
+        // 首个样本时长 3600, 长度前缀后为 IDR
+        assert_eq!(u32::from_be_bytes(trun[12..16].try_into().unwrap()), 3600);
+        assert_eq!(
+            &out[mdat_pos + 8..mdat_pos + 13],
+            &[0x00, 0x00, 0x00, 0x03, 0x65]
+        );
+
+        let audio_trun = child(trafs[1], "trun")[0];
+        assert_eq!(u32::from_be_bytes(audio_trun[4..8].try_into().unwrap()), 2);
+        assert_eq!(top[3].2.len(), 7 + 7 + 320 * 2);
+
+        // 第二个分片从第二个关键帧开始
+        let tfdt = child(child(top[4].2, "traf")[0], "tfdt")[0];
+        assert_eq!(u64::from_be_bytes(tfdt[4..12].try_into().unwrap()), 7200);
+    }
+}
@@ -0,0 +1,654 @@
+pub mod flv;
+pub mod fmp4;
+
+use super::ps::{AccessUnit, StreamType};
+
+// 按 Annex B 起始码切分 NAL
+pub fn split_annexb(data: &[u8]) -> Vec<&[u8]> {
+    let mut nals = vec![];
+    let mut start = None;
+    let mut i = 0;
+    while i + 3 <= data.len() {
+        if data[i] == 0 && data[i + 1] == 0 && data[i + 2] == 1 {
+            if let Some(s) = start {
+                nals.push(trim_zeros(&data[s..i]));
+            }
+            i += 3;
+            start = Some(i);
+        } else {
+            i += 1;
+        }
+    }
+    if let Some(s) = start {
+        nals.push(&data[s..]);
+    }
+    nals.into_iter().filter(|nal| !nal.is_empty()).collect()
+}
+
+// 去掉下一个四字节起始码的前导 0
+fn trim_zeros(nal: &[u8]) -> &[u8] {
+    let mut end = nal.len();
+    while end > 0 && nal[end - 1] == 0 {
+        end -= 1;
+    }
+    &nal[..end]
+}
+
+fn nal_type(stream_type: StreamType, nal: &[u8]) -> u8 {
+    match stream_type {
+        StreamType::H265 => (nal[0] >> 1) & 0x3F,
+        _ => nal[0] & 0x1F,
+    }
+}
+
+// 参数集与 AUD 放在解码配置中, 不写入样本
+fn is_config_nal(stream_type: StreamType, nal: &[u8]) -> bool {
+    match stream_type {
+        StreamType::H265 => matches!(nal_type(stream_type, nal), 32..=35),
+        _ => matches!(nal_type(stream_type, nal), 7..=9),
+    }
+}
+
+// Annex B 转为 4 字节长度前缀
+pub fn to_length_prefixed(stream_type: StreamType, data: &[u8]) -> Vec<u8> {
+    let mut sample = Vec::with_capacity(data.len());
+    for nal in split_annexb(data) {
+        if is_config_nal(stream_type, nal) {
+            continue;
+        }
+        sample.extend_from_slice(&(nal.len() as u32).to_be_bytes());
+        sample.extend_from_slice(nal);
+    }
+    sample
+}
+
+// 去除防竞争字节 00 00 03
+fn rbsp(nal: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(nal.len());
+    let mut zeros = 0;
+    for &byte in nal {
+        if zeros >= 2 && byte == 0x03 {
+            zeros = 0;
+            continue;
+        }
+        zeros = if byte == 0 { zeros + 1 } else { 0 };
+        out.push(byte);
+    }
+    out
+}
+
+struct BitReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        BitReader { data, pos: 0 }
+    }
+
+    fn bit(&mut self) -> Option<u32> {
+        let byte = *self.data.get(self.pos / 8)?;
+        let bit = (byte >> (7 - self.pos % 8)) & 0x01;
+        self.pos += 1;
+        Some(bit as u32)
+    }
+
+    fn bits(&mut self, n: usize) -> Option<u32> {
+        let mut value = 0;
+        for _ in 0..n {
+            value = (value << 1) | self.bit()?;
+        }
+        Some(value)
+    }
+
+    fn skip(&mut self, n: usize) -> Option<()> {
+        self.pos += n;
+        (self.pos <= self.data.len() * 8).then_some(())
+    }
+
+    // 指数哥伦布编码
+    fn ue(&mut self) -> Option<u32> {
+        let mut zeros = 0;
+        while self.bit()? == 0 {
+            zeros += 1;
+            if zeros > 31 {
+                return None;
+            }
+        }
+        Some((1u32 << zeros) - 1 + self.bits(zeros)?)
+    }
+
+    fn se(&mut self) -> Option<i32> {
+        let value = self.ue()?;
+        Some(if value & 1 == 1 {
+            value.div_ceil(2) as i32
+        } else {
+            -((value / 2) as i32)
+        })
+    }
+}
+
+// H.264 SPS 中的图像宽高
+fn h264_dimensions(sps: &[u8]) -> Option<(u16, u16)> {
+    let data = rbsp(sps);
+    let mut r = BitReader::new(data.get(1..)?);
+    let profile_idc = r.bits(8)?;
+    r.skip(16)?;
+    r.ue()?;
+
+    let mut chroma_format_idc = 1;
+    if matches!(
+        profile_idc,
+        100 | 110 | 122 | 244 | 44 | 83 | 86 | 118 | 128 | 138 | 139 | 134 | 135
+    ) {
+        chroma_format_idc = r.ue()?;
+        if chroma_format_idc == 3 {
+            r.skip(1)?;
+        }
+        r.ue()?;
+        r.ue()?;
+        r.skip(1)?;
+        if r.bit()? == 1 {
+            let count = if chroma_format_idc == 3 { 12 } else { 8 };
+            for i in 0..count {
+                if r.bit()? == 1 {
+                    let size = if i < 6 { 16 } else { 64 };
+                    let (mut last, mut next) = (8i32, 8i32);
+                    for _ in 0..size {
+                        if next != 0 {
+                            next = last.checked_add(r.se()?)?.rem_euclid(256);
+                        }
+                        last = if next == 0 { last } else { next };
+                    }
+                }
+            }
+        }
+    }
+
+    r.ue()?;
+    match r.ue()? {
+        0 => {
+            r.ue()?;
+        }
+        1 => {
+            r.skip(1)?;
+            r.se()?;
+            r.se()?;
+            for _ in 0..r.ue()? {
+                r.se()?;
+            }
+        }
+        _ => {}
+    }
+    r.ue()?;
+    r.skip(1)?;
+
+    // 异常码流中的字段可能很大, 运算溢出时视为无法解析
+    let width_mbs = r.ue()?.checked_add(1)?;
+    let height_map_units = r.ue()?.checked_add(1)?;
+    let frame_mbs_only = r.bit()?;
+    if frame_mbs_only == 0 {
+        r.skip(1)?;
+    }
+    r.skip(1)?;
+
+    let mut width = width_mbs.checked_mul(16)?;
+    let mut height = (2 - frame_mbs_only)
+        .checked_mul(height_map_units)?
+        .checked_mul(16)?;
+    if r.bit()? == 1 {
+        let (left, right, top, bottom) = (r.ue()?, r.ue()?, r.ue()?, r.ue()?);
+        let (crop_x, crop_y) = match chroma_format_idc {
+            0 => (1, 2 - frame_mbs_only),
+            3 => (1, 2 - frame_mbs_only),
+            2 => (2, 2 - frame_mbs_only),
+            _ => (2, 2 * (2 - frame_mbs_only)),
+        };
+        width = width.checked_sub(left.checked_add(right)?.checked_mul(crop_x)?)?;
+        height = height.checked_sub(top.checked_add(bottom)?.checked_mul(crop_y)?)?;
+    }
+
+    Some((u16::try_from(width).ok()?, u16::try_from(height).ok()?))
+}
+
+// H.265 SPS 中的图像宽高
+fn h265_dimensions(sps: &[u8]) -> Option<(u16, u16)> {
+    let data = rbsp(sps);
+    let mut r = BitReader::new(data.get(2..)?);
+    r.skip(4)?;
+    let max_sub_layers_minus1 = r.bits(3)? as usize;
+    r.skip(1)?;
+
+    // profile_tier_level
+    r.skip(96)?;
+    let mut sub_layer_flags = vec![];
+    for _ in 0..max_sub_layers_minus1 {
+        sub_layer_flags.push((r.bit()?, r.bit()?));
+    }
+    if max_sub_layers_minus1 > 0 {
+        r.skip(2 * (8 - max_sub_layers_minus1))?;
+    }
+    for (profile_present, level_present) in sub_layer_flags {
+        if profile_present == 1 {
+            r.skip(88)?;
+        }
+        if level_present == 1 {
+            r.skip(8)?;
+        }
+    }
+
+    r.ue()?;
+    let chroma_format_idc = r.ue()?;
+    if chroma_format_idc == 3 {
+        r.skip(1)?;
+    }
+    let mut width = r.ue()?;
+    let mut height = r.ue()?;
+    if r.bit()? == 1 {
+        let (left, right, top, bottom) = (r.ue()?, r.ue()?, r.ue()?, r.ue()?);
+        let (crop_x, crop_y) = match chroma_format_idc {
+            1 => (2, 2),
+            2 => (2, 1),
+            _ => (1, 1),
+        };
+        width = width.checked_sub(left.checked_add(right)?.checked_mul(crop_x)?)?;
+        height = height.checked_sub(top.checked_add(bottom)?.checked_mul(crop_y)?)?;
+    }
+
+    Some((u16::try_from(width).ok()?, u16::try_from(height).ok()?))
+}
+
+// 视频解码配置, 取自关键帧中的参数集
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct VideoConfig {
+    pub codec: Option<StreamType>,
+    pub vps: Vec<u8>,
+    pub sps: Vec<u8>,
+    pub pps: Vec<u8>,
+    pub width: u16,
+    pub height: u16,
+}
+
+impl VideoConfig {
+    pub fn from_unit(unit: &AccessUnit) -> Option<Self> {
+        let stream_type = unit.stream_type;
+        if !stream_type.is_video() {
+            return None;
+        }
+
+        let mut config = VideoConfig {
+            codec: Some(stream_type),
+            ..Default::default()
+        };
+        for nal in split_annexb(&unit.data) {
+            match (stream_type, nal_type(stream_type, nal)) {
+                (StreamType::H265, 32) => config.vps = nal.to_vec(),
+                (StreamType::H265, 33) | (StreamType::H264, 7) => config.sps = nal.to_vec(),
+                (StreamType::H265, 34) | (StreamType::H264, 8) => config.pps = nal.to_vec(),
+                _ => {}
+            }
+        }
+
+        if config.sps.is_empty()
+            || config.pps.is_empty()
+            || (stream_type == StreamType::H265 && config.vps.is_empty())
+        {
+            return None;
+        }
+
+        let dimensions = match stream_type {
+            StreamType::H265 => h265_dimensions(&config.sps),
+            _ => h264_dimensions(&config.sps),
+        };
+        if let Some((width, height)) = dimensions {
+            (config.width, config.height) = (width, height);
+        }
+        Some(config)
+    }
+
+    pub fn is_h265(&self) -> bool {
+        self.codec == Some(StreamType::H265)
+    }
+
+    // avcC 或 hvcC 的内容
+    pub fn decoder_config(&self) -> Vec<u8> {
+        if self.is_h265() {
+            self.hvcc()
+        } else {
+            self.avcc()
+        }
+    }
+
+    fn avcc(&self) -> Vec<u8> {
+        let mut out = vec![
+            0x01,
+            self.sps.get(1).copied().unwrap_or_default(),
+            self.sps.get(2).copied().unwrap_or_default(),
+            self.sps.get(3).copied().unwrap_or_default(),
+            // 长度前缀 4 字节
+            0xFF,
+            0xE1,
+        ];
+        out.extend_from_slice(&(self.sps.len() as u16).to_be_bytes());
+        out.extend_from_slice(&self.sps);
+        out.push(0x01);
+        out.extend_from_slice(&(self.pps.len() as u16).to_be_bytes());
+        out.extend_from_slice(&self.pps);
+        out
+    }
+
+    fn hvcc(&self) -> Vec<u8> {
+        // SPS 中 NAL 头 2 字节 + 1 字节后为 12 字节 general profile_tier_level
+        let sps = rbsp(&self.sps);
+        let mut ptl = [0u8; 12];
+        if sps.len() >= 15 {
+            ptl.copy_from_slice(&sps[3..15]);
+        }
+
+        let mut out = vec![0x01];
+        out.extend_from_slice(&ptl);
+        out.extend_from_slice(&[0xF0, 0x00, 0xFC, 0xFD, 0xF8, 0xF8, 0x00, 0x00, 0x0F]);
+        out.push(3);
+        for (nal_type, nal) in [(32u8, &self.vps), (33, &self.sps), (34, &self.pps)] {
+            out.push(0x80 | nal_type);
+            out.extend_from_slice(&1u16.to_be_bytes());
+            out.extend_from_slice(&(nal.len() as u16).to_be_bytes());
+            out.extend_from_slice(nal);
+        }
+        out
+    }
+}
+
+static AAC_SAMPLE_RATES: [u32; 13] = [
+    96000, 88200, 64000, 48000, 44100, 32000, 24000, 22050, 16000, 12000, 11025, 8000, 7350,
+];
+
+// 音频参数, G.711 固定 8kHz 单声道
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AudioConfig {
+    pub codec: StreamType,
+    pub sample_rate: u32,
+    pub channels: u16,
+    // AAC AudioSpecificConfig
+    pub specific_config: Vec<u8>,
+}
+
+impl AudioConfig {
+    pub fn from_unit(unit: &AccessUnit) -> Option<Self> {
+        match unit.stream_type {
+            StreamType::G711A | StreamType::G711U => Some(AudioConfig {
+                codec: unit.stream_type,
+                sample_rate: 8000,
+                channels: 1,
+                specific_config: vec![],
+            }),
+            StreamType::Aac => {
+                let header = unit.data.get(..7)?;
+                if header[0] != 0xFF || header[1] & 0xF0 != 0xF0 {
+                    return None;
+                }
+                let object_type = ((header[2] >> 6) & 0x03) + 1;
+                let rate_index = (header[2] >> 2) & 0x0F;
+                let channels = ((header[2] & 0x01) << 2) | (header[3] >> 6);
+                Some(AudioConfig {
+                    codec: StreamType::Aac,
+                    sample_rate: *AAC_SAMPLE_RATES.get(rate_index as usize)?,
+                    channels: channels as u16,
+                    specific_config: vec![
+                        (object_type << 3) | (rate_index >> 1),
+                        ((rate_index & 0x01) << 7) | (channels << 3),
+                    ],
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+// 拆出音频帧: AAC 去掉 ADTS 头, G.711 整包即一帧
+pub fn audio_frames(unit: &AccessUnit) -> Vec<&[u8]> {
+    if unit.stream_type != StreamType::Aac {
+        return vec![&unit.data];
+    }
+
+    let mut frames = vec![];
+    let data = &unit.data;
+    let mut pos = 0;
+    while pos + 7 <= data.len() {
+        if data[pos] != 0xFF || data[pos + 1] & 0xF0 != 0xF0 {
+            break;
+        }
+        let header_len = if data[pos + 1] & 0x01 == 0 { 9 } else { 7 };
+        let frame_len = (((data[pos + 3] & 0x03) as usize) << 11)
+            | ((data[pos + 4] as usize) << 3)
+            | ((data[pos + 5] as usize) >> 5);
+        if frame_len < header_len || pos + frame_len > data.len() {
+            break;
+        }
+        frames.push(&data[pos + header_len..pos + frame_len]);
+        pos += frame_len;
+    }
+    frames
+}
+
+// 单帧音频的采样数
+pub fn audio_frame_samples(codec: StreamType, frame: &[u8]) -> u32 {
+    match codec {
+        StreamType::Aac => 1024,
+        _ => frame.len() as u32,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        audio_frames, h264_dimensions, h265_dimensions, to_length_prefixed, AudioConfig,
+        VideoConfig,
+    };
+    use crate::media::ps::{AccessUnit, StreamType};
+
+    // Baseline 352x288, 手工编码的 SPS
+    const H264_SPS: [u8; 8] = [0x67, 0x42, 0xC0, 0x1E, 0xDA, 0x05, 0x82, 0x59];
+    const H264_PPS: [u8; 4] = [0x68, 0xCE, 0x3C, 0x80];
+
+    // Main 1280x720, PTL 中含防竞争字节
+    const H265_SPS: [u8; 24] = [
+        0x42, 0x01, 0x01, 0x01, 0x60, 0x00, 0x00, 0x03, 0x00, 0x90, 0x00, 0x00, 0x03, 0x00, 0x00,
+        0x03, 0x00, 0x5D, 0xA0, 0x02, 0x80, 0x80, 0x2D, 0x14,
+    ];
+
+    fn annexb(nals: &[&[u8]]) -> Vec<u8> {
+        nals.iter()
+            .flat_map(|nal| [&[0x00, 0x00, 0x00, 0x01], *nal].concat())
+            .collect()
+    }
+
+    fn unit(stream_type: StreamType, data: Vec<u8>) -> AccessUnit {
+        AccessUnit {
+            stream_id: 0xE0,
+            stream_type,
+            pts: Some(0),
+            dts: Some(0),
+            keyframe: true,
+            data,
+        }
+    }
+
+    #[test]
+    fn test_h264_config() {
+        let idr = [0x65, 0x88, 0x84, 0x00];
+        let data = annexb(&[&[0x09, 0xF0], &H264_SPS, &H264_PPS, &idr]);
+        let config = VideoConfig::from_unit(&unit(StreamType::H264, data.clone())).unwrap();
+        assert_eq!((config.width, config.height), (352, 288));
+
+        let avcc = config.decoder_config();
+        assert_eq!(&avcc[..6], &[0x01, 0x42, 0xC0, 0x1E, 0xFF, 0xE1]);
+        assert_eq!(&avcc[8..16], &H264_SPS);
+
+        // AUD 与参数集不进入样本
+        assert_eq!(
+            to_length_prefixed(StreamType::H264, &data),
+            [&[0x00, 0x00, 0x00, 0x04][..], &idr].concat()
+        );
+    }
+
+    #[test]
+    fn test_h265_config() {
+        let vps = [0x40, 0x01, 0x0C, 0x01];
+        let pps = [0x44, 0x01, 0xC1, 0x72];
+        let data = annexb(&[&vps, &H265_SPS, &pps, &[0x26, 0x01, 0xAF]]);
+        let config = VideoConfig::from_unit(&unit(StreamType::H265, data)).unwrap();
+        assert_eq!((config.width, config.height), (1280, 720));
+
+        let hvcc = config.decoder_config();
+        assert_eq!(hvcc[1], 0x01);
+        assert_eq!(&hvcc[2..6], &[0x60, 0x00, 0x00, 0x00]);
+        assert_eq!(hvcc[12], 93);
+        assert_eq!(hvcc[22], 3);
+    }
+
+    // 按位拼出 SPS, 用于构造异常字段
+    #[derive(Default)]
+    struct BitWriter(Vec<bool>);
+
+    impl BitWriter {
+        fn bits(&mut self, n: usize, value: u64) -> &mut Self {
+            self.0.extend(
+                (0..n)
+                    .rev()
+                    .map(|i| value.checked_shr(i as u32).unwrap_or(0) & 1 == 1),
+            );
+            self
+        }
+
+        fn ue(&mut self, value: u32) -> &mut Self {
+            let value = value as u64 + 1;
+            let len = 64 - value.leading_zeros() as usize;
+            self.bits(len - 1, 0).bits(len, value)
+        }
+
+        fn bytes(&mut self, header: &[u8]) -> Vec<u8> {
+            self.bits(8, 0xFF);
+            let mut out = header.to_vec();
+            out.extend(self.0.chunks(8).map(|chunk| {
+                chunk
+                    .iter()
+                    .enumerate()
+                    .fold(0u8, |byte, (i, bit)| byte | (*bit as u8) << (7 - i))
+            }));
+            out
+        }
+    }
+
+    #[test]
+    fn test_sps_overflow() {
+        // 全 0xFF 时各字段取最小值, 只要求不 panic
+        let sps = [0xFF; 32];
+        let _ = h264_dimensions(&sps);
+        let _ = h265_dimensions(&sps);
+
+        let sps = BitWriter::default()
+            .bits(24, 0x42_C0_1E)
+            .ue(0)
+            .ue(0)
+            .ue(2)
+            .ue(0)
+            .bits(1, 0)
+            .ue(21)
+            .ue(17)
+            .bits(3, 0b110)
+            .bytes(&[0x67]);
+        assert_eq!(h264_dimensions(&sps), Some((352, 288)));
+
+        // pic_width_in_mbs_minus1 接近 u32::MAX, 乘 16 溢出
+        let sps = BitWriter::default()
+            .bits(24, 0x42_C0_1E)
+            .ue(0)
+            .ue(0)
+            .ue(2)
+            .ue(0)
+            .bits(1, 0)
+            .ue(u32::MAX - 1)
+            .ue(0)
+            .bits(2, 0b11)
+            .bytes(&[0x67]);
+        assert_eq!(h264_dimensions(&sps), None);
+
+        // 裁剪量之和溢出
+        let sps = BitWriter::default()
+            .bits(8, 0x01)
+            .bits(96, 0)
+            .ue(0)
+            .ue(1)
+            .ue(1280)
+            .ue(720)
+            .bits(1, 1)
+            .ue(1 << 31)
+            .ue(1 << 31)
+            .ue(0)
+            .ue(0)
+            .bytes(&[0x42, 0x01]);
+        assert_eq!(h265_dimensions(&sps), None);
+
+        let sps = BitWriter::default()
+            .bits(8, 0x01)
+            .bits(96, 0)
+            .ue(0)
+            .ue(1)
+            .ue(1280)
+            .ue(720)
+            .bits(1, 0)
+            .bytes(&[0x42, 0x01]);
+        assert_eq!(h265_dimensions(&sps), Some((1280, 720)));
+
+        // 宽高超出 u16
+        let sps = BitWriter::default()
+            .bits(8, 0x01)
+            .bits(96, 0)
+            .ue(0)
+            .ue(1)
+            .ue(70000)
+            .ue(720)
+            .bits(1, 0)
+            .bytes(&[0x42, 0x01]);
+        assert_eq!(h265_dimensions(&sps), None);
+    }
+
+    #[test]
+    fn test_aac_adts() {
+        // AAC LC 44.1kHz 双声道, 两个 ADTS 帧
+        let frame = |payload: &[u8]| {
+            let len = 7 + payload.len();
+            let mut adts = vec![
+                0xFF,
+                0xF1,
+                0x50,
+                0x80 | ((len >> 11) as u8 & 0x03),
+                (len >> 3) as u8,
+                ((len as u8 & 0x07) << 5) | 0x1F,
+                0xFC,
+            ];
+            adts.extend_from_slice(payload);
+            adts
+        };
+        let data = [frame(&[0x21, 0x10]), frame(&[0x21, 0x20, 0x30])].concat();
+        let unit = AccessUnit {
+            stream_id: 0xC0,
+            stream_type: StreamType::Aac,
+            pts: Some(0),
+            dts: Some(0),
+            keyframe: true,
+            data,
+        };
+
+        let config = AudioConfig::from_unit(&unit).unwrap();
+        assert_eq!(config.sample_rate, 44100);
+        assert_eq!(config.channels, 2);
+        assert_eq!(config.specific_config, vec![0x12, 0x10]);
+        assert_eq!(
+            audio_frames(&unit),
+            vec![&[0x21, 0x10][..], &[0x21, 0x20, 0x30][..]]
+        );
+    }
+}
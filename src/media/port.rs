@@ -0,0 +1,97 @@
+use std::collections::HashSet;
+
+// 单个媒体节点的 RTP 收流端口池, 只分配偶数端口(奇数端口留给 RTCP)
+#[derive(Debug, Default)]
+pub struct PortPool {
+    min: u16,
+    max: u16,
+    // 下次分配的起点, 避免刚释放的端口被立即复用
+    next: u16,
+    used: HashSet<u16>,
+}
+
+impl PortPool {
+    pub fn new(min: u16, max: u16) -> Self {
+        // 范围内没有偶数端口时池为空
+        let (min, max) = match min.checked_add(min % 2) {
+            Some(min) => (min, max),
+            None => (1, 0),
+        };
+        PortPool {
+            min,
+            max,
+            next: min,
+            used: HashSet::new(),
+        }
+    }
+
+    pub fn capacity(&self) -> u32 {
+        if self.max < self.min {
+            return 0;
+        }
+        (self.max - self.min) as u32 / 2 + 1
+    }
+
+    pub fn in_use(&self) -> u32 {
+        self.used.len() as u32
+    }
+
+    pub fn allocate(&mut self) -> Option<u16> {
+        let capacity = self.capacity();
+        let mut port = self.next;
+        for _ in 0..capacity {
+            if port > self.max || port < self.min {
+                port = self.min;
+            }
+            if self.used.insert(port) {
+                self.next = port.checked_add(2).unwrap_or(self.min);
+                return Some(port);
+            }
+            port = port.checked_add(2).unwrap_or(self.min);
+        }
+        None
+    }
+
+    pub fn release(&mut self, port: u16) -> bool {
+        self.used.remove(&port)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PortPool;
+
+    #[test]
+    fn test_allocate_even_ports_until_exhausted() {
+        let mut pool = PortPool::new(30001, 30006);
+        assert_eq!(pool.capacity(), 3);
+        assert_eq!(pool.allocate(), Some(30002));
+        assert_eq!(pool.allocate(), Some(30004));
+        assert_eq!(pool.allocate(), Some(30006));
+        assert_eq!(pool.allocate(), None);
+
+        assert!(pool.release(30004));
+        assert!(!pool.release(30004));
+        assert_eq!(pool.allocate(), Some(30004));
+        assert_eq!(pool.in_use(), 3);
+    }
+
+    #[test]
+    fn test_released_port_not_reused_immediately() {
+        let mut pool = PortPool::new(30000, 30010);
+        assert_eq!(pool.allocate(), Some(30000));
+        pool.release(30000);
+        assert_eq!(pool.allocate(), Some(30002));
+    }
+
+    #[test]
+    fn test_no_even_port_at_upper_bound() {
+        let mut pool = PortPool::new(65535, 65535);
+        assert_eq!(pool.capacity(), 0);
+        assert_eq!(pool.allocate(), None);
+
+        let mut pool = PortPool::new(65533, 65535);
+        assert_eq!(pool.allocate(), Some(65534));
+        assert_eq!(pool.allocate(), None);
+    }
+}
@@ -0,0 +1,691 @@
+use std::collections::HashMap;
+use std::fmt;
+
+// MPEG-PS 起始码 00 00 01 之后的标识
+pub static PACK_HEADER_ID: u8 = 0xBA;
+pub static PSM_ID: u8 = 0xBC;
+static PROGRAM_END_ID: u8 = 0xB9;
+static PRIVATE_STREAM_1_ID: u8 = 0xBD;
+
+// 单帧上限, 超过则认为流已损坏
+static MAX_UNIT_SIZE: usize = 8 * 1024 * 1024;
+
+// PSM 中的 stream_type
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamType {
+    H264,
+    H265,
+    G711A,
+    G711U,
+    Aac,
+    Unknown(u8),
+}
+
+impl From<u8> for StreamType {
+    fn from(value: u8) -> Self {
+        match value {
+            0x1B => StreamType::H264,
+            0x24 => StreamType::H265,
+            0x90 => StreamType::G711A,
+            0x91 => StreamType::G711U,
+            0x0F => StreamType::Aac,
+            _ => StreamType::Unknown(value),
+        }
+    }
+}
+
+impl fmt::Display for StreamType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StreamType::H264 => write!(f, "h264"),
+            StreamType::H265 => write!(f, "h265"),
+            StreamType::G711A => write!(f, "g711a"),
+            StreamType::G711U => write!(f, "g711u"),
+            StreamType::Aac => write!(f, "aac"),
+            StreamType::Unknown(value) => write!(f, "unknown(0x{:02X})", value),
+        }
+    }
+}
+
+impl StreamType {
+    pub fn is_video(&self) -> bool {
+        matches!(self, StreamType::H264 | StreamType::H265)
+    }
+}
+
+// 解复用输出的一帧, 时间戳单位 90kHz
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccessUnit {
+    pub stream_id: u8,
+    pub stream_type: StreamType,
+    pub pts: Option<u64>,
+    pub dts: Option<u64>,
+    pub keyframe: bool,
+    pub data: Vec<u8>,
+}
+
+// PS 流解复用, 输入可按任意边界切分 (如 RTP 负载)
+#[derive(Debug, Default)]
+pub struct PsDemuxer {
+    buffer: Vec<u8>,
+    // PSM 中的 elementary_stream_id -> stream_type
+    stream_types: HashMap<u8, StreamType>,
+    // 视频帧可能拆成多个 PES, 按 PTS 变化切帧
+    pending: HashMap<u8, AccessUnit>,
+    // 最近一个包头中的 SCR
+    pub scr: Option<u64>,
+}
+
+impl PsDemuxer {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn stream_type(&self, stream_id: u8) -> Option<StreamType> {
+        self.stream_types.get(&stream_id).copied()
+    }
+
+    pub fn push(&mut self, data: &[u8]) -> Vec<AccessUnit> {
+        let mut buffer = std::mem::take(&mut self.buffer);
+        buffer.extend_from_slice(data);
+
+        let mut units = vec![];
+        let mut pos = 0;
+        loop {
+            // 跳过起始码前的无效数据
+            match find_start_code(&buffer[pos..]) {
+                Some(offset) => pos += offset,
+                None => {
+                    // 保留末尾可能是半个起始码的字节
+                    pos = buffer.len().saturating_sub(3).max(pos);
+                    break;
+                }
+            }
+
+            match parse_packet(&buffer[pos..]) {
+                Packet::NeedMore => break,
+                Packet::Invalid => pos += 3,
+                Packet::Skip(len) => pos += len,
+                Packet::Pack(len, scr) => {
+                    self.scr = Some(scr);
+                    pos += len;
+                }
+                Packet::Psm(len, types) => {
+                    self.stream_types = types;
+                    pos += len;
+                }
+                Packet::Pes(len, pes) => {
+                    pos += len;
+                    self.on_pes(pes, &mut units);
+                }
+            }
+        }
+
+        buffer.drain(..pos);
+        self.buffer = buffer;
+        units
+    }
+
+    // 输出缓存中尚未结束的帧
+    pub fn flush(&mut self) -> Vec<AccessUnit> {
+        let mut units: Vec<_> = self.pending.drain().map(|(_, unit)| unit).collect();
+        units.sort_by_key(|unit| unit.stream_id);
+        for unit in units.iter_mut() {
+            unit.keyframe = is_keyframe(unit.stream_type, &unit.data);
+        }
+        self.buffer.clear();
+        units
+    }
+
+    fn on_pes(&mut self, pes: Pes, units: &mut Vec<AccessUnit>) {
+        let stream_type = match self.stream_types.get(&pes.stream_id) {
+            Some(stream_type) => *stream_type,
+            // 未收到 PSM 时按 stream_id 范围推断
+            None if (0xE0..=0xEF).contains(&pes.stream_id) => StreamType::H264,
+            None => StreamType::Unknown(0),
+        };
+
+        if !stream_type.is_video() {
+            // 音频一个 PES 即一帧
+            units.push(AccessUnit {
+                stream_id: pes.stream_id,
+                stream_type,
+                pts: pes.pts,
+                dts: pes.dts,
+                keyframe: true,
+                data: pes.payload.to_vec(),
+            });
+            return;
+        }
+
+        if let Some(unit) = self.pending.get_mut(&pes.stream_id) {
+            let same_frame = pes.pts.is_none() || pes.pts == unit.pts;
+            if same_frame && unit.data.len() + pes.payload.len() <= MAX_UNIT_SIZE {
+                unit.data.extend_from_slice(pes.payload);
+                return;
+            }
+
+            let mut unit = self.pending.remove(&pes.stream_id).unwrap();
+            if unit.data.len() + pes.payload.len() > MAX_UNIT_SIZE {
+                tracing::warn!("ps frame too large, stream_id: 0x{:02X}", pes.stream_id);
+            }
+            unit.keyframe = is_keyframe(unit.stream_type, &unit.data);
+            units.push(unit);
+        }
+
+        self.pending.insert(
+            pes.stream_id,
+            AccessUnit {
+                stream_id: pes.stream_id,
+                stream_type,
+                pts: pes.pts,
+                dts: pes.dts,
+                keyframe: false,
+                data: pes.payload.to_vec(),
+            },
+        );
+    }
+}
+
+struct Pes<'a> {
+    stream_id: u8,
+    pts: Option<u64>,
+    dts: Option<u64>,
+    payload: &'a [u8],
+}
+
+enum Packet<'a> {
+    NeedMore,
+    // 起始码后数据不合法, 跳过起始码重新同步
+    Invalid,
+    Skip(usize),
+    Pack(usize, u64),
+    Psm(usize, HashMap<u8, StreamType>),
+    Pes(usize, Pes<'a>),
+}
+
+fn parse_packet(data: &[u8]) -> Packet<'_> {
+    if data.len() < 4 {
+        return Packet::NeedMore;
+    }
+
+    let id = data[3];
+    if id == PACK_HEADER_ID {
+        return parse_pack_header(data);
+    }
+    if id == PROGRAM_END_ID {
+        return Packet::Skip(4);
+    }
+    if id < PROGRAM_END_ID {
+        // 不是 PS 层的起始码
+        return Packet::Invalid;
+    }
+
+    if data.len() < 6 {
+        return Packet::NeedMore;
+    }
+    let len = 6 + u16::from_be_bytes([data[4], data[5]]) as usize;
+    if id == PSM_ID {
+        if data.len() < len {
+            return Packet::NeedMore;
+        }
+        return match parse_psm(&data[..len]) {
+            Some(types) => Packet::Psm(len, types),
+            None => Packet::Skip(len),
+        };
+    }
+
+    if is_pes_stream(id) {
+        if len == 6 {
+            // 长度为 0 的 PES, 负载延续到下一个 PS 层起始码
+            return match find_system_start_code(&data[6..]) {
+                Some(offset) => parse_pes(&data[..6 + offset], 6 + offset),
+                None => Packet::NeedMore,
+            };
+        }
+        if data.len() < len {
+            return Packet::NeedMore;
+        }
+        return parse_pes(&data[..len], len);
+    }
+
+    // 系统头, 填充流等按长度跳过
+    if data.len() < len {
+        return Packet::NeedMore;
+    }
+    Packet::Skip(len)
+}
+
+fn is_pes_stream(id: u8) -> bool {
+    id == PRIVATE_STREAM_1_ID || (0xC0..=0xEF).contains(&id)
+}
+
+fn find_start_code(data: &[u8]) -> Option<usize> {
+    data.windows(3).position(|w| w == [0x00, 0x00, 0x01])
+}
+
+// 只匹配 PS 层起始码, ES 中的 NAL 起始码后一字节都小于 0x80
+fn find_system_start_code(data: &[u8]) -> Option<usize> {
+    data.windows(4)
+        .position(|w| w[..3] == [0x00, 0x00, 0x01] && w[3] >= PROGRAM_END_ID)
+}
+
+// MPEG-2 包头 14 字节 + 填充, MPEG-1 包头 12 字节
+fn parse_pack_header(data: &[u8]) -> Packet<'_> {
+    if data.len() < 5 {
+        return Packet::NeedMore;
+    }
+
+    if data[4] & 0xC0 == 0x40 {
+        if data.len() < 14 {
+            return Packet::NeedMore;
+        }
+        let len = 14 + (data[13] & 0x07) as usize;
+        if data.len() < len {
+            return Packet::NeedMore;
+        }
+        let b = &data[4..10];
+        let scr = ((b[0] as u64 & 0x38) << 27)
+            | ((b[0] as u64 & 0x03) << 28)
+            | ((b[1] as u64) << 20)
+            | ((b[2] as u64 & 0xF8) << 12)
+            | ((b[2] as u64 & 0x03) << 13)
+            | ((b[3] as u64) << 5)
+            | ((b[4] as u64) >> 3);
+        return Packet::Pack(len, scr);
+    }
+
+    if data[4] & 0xF0 == 0x20 {
+        if data.len() < 12 {
+            return Packet::NeedMore;
+        }
+        return Packet::Pack(12, parse_timestamp(&data[4..9]));
+    }
+
+    Packet::Invalid
+}
+
+// 33 位时间戳, 分布在 5 字节中
+fn parse_timestamp(b: &[u8]) -> u64 {
+    ((b[0] as u64 & 0x0E) << 29)
+        | ((b[1] as u64) << 22)
+        | ((b[2] as u64 & 0xFE) << 14)
+        | ((b[3] as u64) << 7)
+        | ((b[4] as u64) >> 1)
+}
+
+fn parse_psm(data: &[u8]) -> Option<HashMap<u8, StreamType>> {
+    // 6 字节包头 + 2 字节版本 + 2 字节 info 长度
+    let info_len = u16::from_be_bytes([*data.get(8)?, *data.get(9)?]) as usize;
+    let map_pos = 10 + info_len;
+    let map_len = u16::from_be_bytes([*data.get(map_pos)?, *data.get(map_pos + 1)?]) as usize;
+
+    let mut types = HashMap::new();
+    let mut pos = map_pos + 2;
+    let end = (pos + map_len).min(data.len().saturating_sub(4));
+    while pos + 4 <= end {
+        let stream_type = data[pos];
+        let stream_id = data[pos + 1];
+        let es_info_len = u16::from_be_bytes([data[pos + 2], data[pos + 3]]) as usize;
+        types.insert(stream_id, StreamType::from(stream_type));
+        pos += 4 + es_info_len;
+    }
+
+    Some(types)
+}
+
+fn parse_pes(data: &[u8], len: usize) -> Packet<'_> {
+    if data.len() < 9 || data[6] & 0xC0 != 0x80 {
+        return Packet::Skip(len);
+    }
+
+    let header_len = data[8] as usize;
+    let payload_pos = 9 + header_len;
+    if payload_pos > data.len() {
+        return Packet::Skip(len);
+    }
+
+    let (mut pts, mut dts) = (None, None);
+    let flags = data[7] >> 6;
+    if flags & 0x02 != 0 && header_len >= 5 {
+        pts = Some(parse_timestamp(&data[9..14]));
+        dts = pts;
+    }
+    if flags == 0x03 && header_len >= 10 {
+        dts = Some(parse_timestamp(&data[14..19]));
+    }
+
+    Packet::Pes(
+        len,
+        Pes {
+            stream_id: data[3],
+            pts,
+            dts,
+            payload: &data[payload_pos..],
+        },
+    )
+}
+
+// 帧内是否包含 H.264 IDR 或 H.265 IRAP
+fn is_keyframe(stream_type: StreamType, data: &[u8]) -> bool {
+    let mut i = 0;
+    while i + 4 <= data.len() {
+        if data[i] != 0 || data[i + 1] != 0 || data[i + 2] != 1 {
+            i += 1;
+            continue;
+        }
+
+        let nal_header = data[i + 3];
+        match stream_type {
+            StreamType::H264 if nal_header & 0x1F == 5 => return true,
+            StreamType::H265 if matches!((nal_header >> 1) & 0x3F, 16..=21) => return true,
+            StreamType::H264 | StreamType::H265 => {}
+            _ => return true,
+        }
+        i += 3;
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AccessUnit, PsDemuxer, StreamType};
+    use crate::media::mux::VideoConfig;
+    use crate::media::rtp::{read_rfc4571_frame, RtpHeader};
+
+    fn encode_timestamp(prefix: u8, ts: u64) -> [u8; 5] {
+        [
+            (prefix << 4) | (((ts >> 30) & 0x07) as u8) << 1 | 1,
+            (ts >> 22) as u8,
+            (((ts >> 15) & 0x7F) as u8) << 1 | 1,
+            (ts >> 7) as u8,
+            ((ts & 0x7F) as u8) << 1 | 1,
+        ]
+    }
+
+    // MPEG-2 包头, 带 2 字节填充
+    fn pack_header(scr: u64) -> Vec<u8> {
+        vec![
+            0x00,
+            0x00,
+            0x01,
+            0xBA,
+            0x44 | (((scr >> 30) & 0x07) as u8) << 3 | ((scr >> 28) & 0x03) as u8,
+            (scr >> 20) as u8,
+            (((scr >> 15) & 0x1F) as u8) << 3 | 0x04 | ((scr >> 13) & 0x03) as u8,
+            (scr >> 5) as u8,
+            ((scr & 0x1F) as u8) << 3 | 0x04,
+            0x01,
+            0x01,
+            0x89,
+            0xC3,
+            0xFA,
+            0xFF,
+            0xFF,
+        ]
+    }
+
+    fn system_header() -> Vec<u8> {
+        vec![
+            0x00, 0x00, 0x01, 0xBB, 0x00, 0x0C, 0x80, 0xCC, 0xF5, 0x04, 0xE1, 0x7F, 0xE0, 0xE0,
+            0xE8, 0xC0, 0xC0, 0x20,
+        ]
+    }
+
+    fn psm(entries: &[(u8, u8)]) -> Vec<u8> {
+        let mut map = vec![];
+        for (stream_type, stream_id) in entries {
+            // 每项带 2 字节描述符, 验证 es_info_length 的跳过
+            map.extend_from_slice(&[*stream_type, *stream_id, 0x00, 0x02, 0x0A, 0x00]);
+        }
+        let mut body = vec![0xE0, 0xFF, 0x00, 0x00];
+        body.extend_from_slice(&(map.len() as u16).to_be_bytes());
+        body.extend_from_slice(&map);
+        body.extend_from_slice(&[0x00, 0x00, 0x00, 0x00]);
+
+        let mut packet = vec![0x00, 0x00, 0x01, 0xBC];
+        packet.extend_from_slice(&(body.len() as u16).to_be_bytes());
+        packet.extend_from_slice(&body);
+        packet
+    }
+
+    fn pes(stream_id: u8, pts: Option<u64>, dts: Option<u64>, payload: &[u8]) -> Vec<u8> {
+        let mut header = vec![];
+        let flags = match (pts, dts) {
+            (Some(pts), Some(dts)) => {
+                header.extend_from_slice(&encode_timestamp(0x03, pts));
+                header.extend_from_slice(&encode_timestamp(0x01, dts));
+                0xC0
+            }
+            (Some(pts), None) => {
+                header.extend_from_slice(&encode_timestamp(0x02, pts));
+                0x80
+            }
+            _ => 0x00,
+        };
+
+        let mut packet = vec![0x00, 0x00, 0x01, stream_id];
+        packet.extend_from_slice(&((3 + header.len() + payload.len()) as u16).to_be_bytes());
+        packet.extend_from_slice(&[0x80, flags, header.len() as u8]);
+        packet.extend_from_slice(&header);
+        packet.extend_from_slice(payload);
+        packet
+    }
+
+    const SPS: [u8; 8] = [0x00, 0x00, 0x00, 0x01, 0x67, 0x4D, 0x00, 0x1F];
+    const IDR: [u8; 8] = [0x00, 0x00, 0x00, 0x01, 0x65, 0x88, 0x80, 0x40];
+    const P_SLICE: [u8; 8] = [0x00, 0x00, 0x00, 0x01, 0x41, 0x9A, 0x02, 0x04];
+
+    // 按海康等设备的常见封装: 关键帧带系统头与 PSM, 大帧拆成多个 PES
+    fn h264_g711_stream() -> Vec<u8> {
+        let mut stream = pack_header(0x1_2345_6789);
+        stream.extend(system_header());
+        stream.extend(psm(&[(0x1B, 0xE0), (0x90, 0xC0)]));
+        stream.extend(pes(0xE0, Some(3600), None, &SPS));
+        stream.extend(pes(0xE0, None, None, &IDR));
+        stream.extend(pes(0xC0, Some(3600), None, &[0xD5; 16]));
+        stream.extend(pack_header(0x1_2345_7000));
+        stream.extend(pes(0xE0, Some(7200), Some(3600), &P_SLICE));
+        stream.extend(pes(0xE0, Some(7200), Some(3600), &P_SLICE[4..]));
+        stream.extend([0x00, 0x00, 0x01, 0xB9]);
+        stream
+    }
+
+    fn demux_all(demuxer: &mut PsDemuxer, chunks: &[&[u8]]) -> Vec<AccessUnit> {
+        let mut units = vec![];
+        for chunk in chunks {
+            units.extend(demuxer.push(chunk));
+        }
+        units.extend(demuxer.flush());
+        units
+    }
+
+    // 固定种子的 xorshift, 保证用例可复现
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn below(&mut self, n: usize) -> usize {
+            (self.next() % n as u64) as usize
+        }
+    }
+
+    #[test]
+    fn test_demux_h264_g711() {
+        let stream = h264_g711_stream();
+        let mut demuxer = PsDemuxer::new();
+        let units = demux_all(&mut demuxer, &[&stream]);
+
+        assert_eq!(demuxer.scr, Some(0x1_2345_7000));
+        assert_eq!(demuxer.stream_type(0xE0), Some(StreamType::H264));
+        assert_eq!(demuxer.stream_type(0xC0), Some(StreamType::G711A));
+        assert_eq!(units.len(), 3);
+
+        assert_eq!(units[0].stream_type, StreamType::G711A);
+        assert_eq!(units[0].pts, Some(3600));
+        assert_eq!(units[0].data, vec![0xD5; 16]);
+
+        assert_eq!(units[1].stream_type, StreamType::H264);
+        assert_eq!(units[1].pts, Some(3600));
+        assert_eq!(units[1].dts, Some(3600));
+        assert!(units[1].keyframe);
+        assert_eq!(units[1].data, [SPS, IDR].concat());
+
+        assert_eq!(units[2].pts, Some(7200));
+        assert_eq!(units[2].dts, Some(3600));
+        assert!(!units[2].keyframe);
+        assert_eq!(units[2].data, [&P_SLICE[..], &P_SLICE[4..]].concat());
+    }
+
+    // example/ps_h264_g711_gop.rtp: 按设备的封装方式生成, 非实机抓包
+    // 以 TCP 收流时的 RFC 4571 字节流保存, 一个 GOP 共 1 秒
+    // 1280x720 H.264 25fps, 关键帧带系统头、PSM 与 SPS/PPS/SEI, 帧按 8000 字节拆成多个 PES;
+    // 每 40ms 一包 G.711A, RTP 负载不超过 1400 字节, 帧的最后一包带 marker
+    #[tokio::test]
+    async fn test_replay_rtp_ps_gop() {
+        let mut reader: &[u8] = include_bytes!("../../example/ps_h264_g711_gop.rtp");
+        let mut demuxer = PsDemuxer::new();
+        let mut units = vec![];
+        let mut buffer = vec![];
+        let mut sequence = None;
+        while read_rfc4571_frame(&mut reader, &mut buffer).await.is_ok() {
+            let (header, payload) = RtpHeader::parse(&buffer).unwrap();
+            assert_eq!(header.payload_type, 96);
+            assert_eq!(header.ssrc, 100000001);
+            if let Some(sequence) = sequence {
+                assert_eq!(header.sequence, u16::wrapping_add(sequence, 1));
+            }
+            sequence = Some(header.sequence);
+            units.extend(demuxer.push(payload));
+        }
+        assert!(reader.is_empty());
+        units.extend(demuxer.flush());
+
+        assert_eq!(demuxer.stream_type(0xE0), Some(StreamType::H264));
+        assert_eq!(demuxer.stream_type(0xC0), Some(StreamType::G711A));
+        let (video, audio): (Vec<_>, Vec<_>) =
+            units.iter().partition(|unit| unit.stream_type.is_video());
+        assert_eq!(video.len(), 25);
+        assert_eq!(audio.len(), 25);
+
+        assert!(video[0].keyframe);
+        assert!(video[1..].iter().all(|unit| !unit.keyframe));
+        assert!(video
+            .windows(2)
+            .all(|w| w[1].pts.unwrap() - w[0].pts.unwrap() == 3600));
+        let config = VideoConfig::from_unit(video[0]).unwrap();
+        assert_eq!(config.codec, Some(StreamType::H264));
+        assert_eq!((config.width, config.height), (1280, 720));
+        assert!(audio.iter().all(|unit| unit.data.len() == 320));
+    }
+
+    #[test]
+    fn test_demux_h265() {
+        // VPS + IDR_W_RADL(19), 随后 TRAIL_R(1)
+        let vps = [0x00, 0x00, 0x00, 0x01, 0x40, 0x01, 0x0C, 0x01];
+        let idr = [0x00, 0x00, 0x01, 0x26, 0x01, 0xAF, 0x06];
+        let trail = [0x00, 0x00, 0x01, 0x02, 0x01, 0xD0];
+
+        let mut stream = pack_header(0);
+        stream.extend(psm(&[(0x24, 0xE0), (0x0F, 0xC0)]));
+        stream.extend(pes(0xE0, Some(90000), None, &[&vps[..], &idr[..]].concat()));
+        stream.extend(pes(0xC0, Some(90000), None, &[0xFF, 0xF1, 0x50, 0x80]));
+        stream.extend(pack_header(3600));
+        stream.extend(pes(0xE0, Some(93600), None, &trail));
+
+        let units = demux_all(&mut PsDemuxer::new(), &[&stream]);
+        assert_eq!(units.len(), 3);
+        assert_eq!(units[0].stream_type, StreamType::Aac);
+        assert_eq!(units[1].stream_type, StreamType::H265);
+        assert!(units[1].keyframe);
+        assert_eq!(units[2].pts, Some(93600));
+        assert!(!units[2].keyframe);
+    }
+
+    #[test]
+    fn test_stream_type() {
+        assert_eq!(StreamType::from(0x91), StreamType::G711U);
+        assert_eq!(StreamType::from(0x10), StreamType::Unknown(0x10));
+        assert_eq!(StreamType::G711U.to_string(), "g711u");
+        assert!(!StreamType::Aac.is_video());
+    }
+
+    #[test]
+    fn test_demux_split_anywhere() {
+        let stream = h264_g711_stream();
+        let expected = demux_all(&mut PsDemuxer::new(), &[&stream]);
+
+        for split in 1..stream.len() {
+            let (left, right) = stream.split_at(split);
+            assert_eq!(
+                demux_all(&mut PsDemuxer::new(), &[left, right]),
+                expected,
+                "split at {}",
+                split
+            );
+        }
+
+        // 逐字节输入
+        let bytes: Vec<&[u8]> = stream.chunks(1).collect();
+        assert_eq!(demux_all(&mut PsDemuxer::new(), &bytes), expected);
+    }
+
+    #[test]
+    fn test_demux_resync_after_garbage() {
+        let stream = h264_g711_stream();
+        let expected = demux_all(&mut PsDemuxer::new(), &[&stream]);
+
+        let mut noisy = vec![0x00, 0x00, 0x01, 0x65, 0x12, 0x00, 0x00];
+        noisy.extend_from_slice(&stream);
+        assert_eq!(demux_all(&mut PsDemuxer::new(), &[&noisy]), expected);
+    }
+
+    #[test]
+    fn test_demux_fuzz() {
+        let stream = h264_g711_stream();
+        let mut rng = Rng(0x9E37_79B9_7F4A_7C15);
+
+        for _ in 0..2000 {
+            // 随机翻转/截断/插入字节
+            let mut data = stream.clone();
+            for _ in 0..rng.below(8) {
+                let i = rng.below(data.len());
+                match rng.below(3) {
+                    0 => data[i] = rng.next() as u8,
+                    1 => data.truncate(i),
+                    _ => data.insert(i, rng.next() as u8),
+                }
+                if data.is_empty() {
+                    break;
+                }
+            }
+
+            let mut demuxer = PsDemuxer::new();
+            let mut pos = 0;
+            while pos < data.len() {
+                let len = 1 + rng.below(64);
+                let end = (pos + len).min(data.len());
+                for unit in demuxer.push(&data[pos..end]) {
+                    assert!(unit.data.len() <= data.len());
+                }
+                pos = end;
+            }
+            demuxer.flush();
+        }
+
+        // 纯随机数据
+        for _ in 0..200 {
+            let data: Vec<u8> = (0..rng.below(4096)).map(|_| rng.next() as u8).collect();
+            let mut demuxer = PsDemuxer::new();
+            demuxer.push(&data);
+            assert!(demuxer.buffer.len() <= 65535 + 6);
+            demuxer.flush();
+        }
+    }
+}
@@ -0,0 +1,495 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+
+use dashmap::DashMap;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::{broadcast, oneshot};
+
+use super::mux::{flv::FlvMuxer, fmp4::Fmp4Muxer};
+use super::ps::AccessUnit;
+use crate::sip::handler::SipHandler;
+use crate::utils::config::Config;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordFormat {
+    Mp4,
+    Flv,
+}
+
+impl fmt::Display for RecordFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecordFormat::Mp4 => write!(f, "mp4"),
+            RecordFormat::Flv => write!(f, "flv"),
+        }
+    }
+}
+
+impl FromStr for RecordFormat {
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "mp4" => Ok(Self::Mp4),
+            "flv" => Ok(Self::Flv),
+            _ => Err(String::from("Support lists: mp4, flv")),
+        }
+    }
+
+    type Err = String;
+}
+
+// 正在进行的录像
+#[derive(Debug)]
+pub struct RecordTask {
+    pub stream_id: u32,
+    pub format: RecordFormat,
+    pub start_ts: u32,
+    stop: Option<oneshot::Sender<()>>,
+}
+
+// 磁盘上的录像文件
+#[derive(Debug, Clone, Default)]
+pub struct RecordFile {
+    pub name: String,
+    pub path: String,
+    pub size: u64,
+    // 文件名即开始时间
+    pub start_ts: u32,
+    pub modified_ts: u32,
+}
+
+enum Muxer {
+    Mp4(Fmp4Muxer),
+    Flv(FlvMuxer),
+}
+
+impl Muxer {
+    fn new(format: RecordFormat) -> (Self, Vec<u8>) {
+        match format {
+            RecordFormat::Mp4 => (Muxer::Mp4(Fmp4Muxer::new()), vec![]),
+            RecordFormat::Flv => (Muxer::Flv(FlvMuxer::new()), FlvMuxer::header()),
+        }
+    }
+
+    fn push(&mut self, unit: &AccessUnit) -> Vec<u8> {
+        match self {
+            Muxer::Mp4(muxer) => muxer.push(unit),
+            Muxer::Flv(muxer) => muxer.push(unit),
+        }
+    }
+
+    fn finish(&mut self) -> Vec<u8> {
+        match self {
+            Muxer::Mp4(muxer) => muxer.finish(),
+            Muxer::Flv(_) => vec![],
+        }
+    }
+}
+
+// 当前写入的文件
+struct Segment {
+    file: tokio::fs::File,
+    path: PathBuf,
+    muxer: Muxer,
+    start_dts: u64,
+}
+
+fn ts_now() -> u32 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .expect("Time went backwards")
+        .as_secs() as u32
+}
+
+fn is_gb_code(code: &str) -> bool {
+    code.len() == 20 && code.bytes().all(|b| b.is_ascii_digit())
+}
+
+fn modified_ts(metadata: &std::fs::Metadata) -> u32 {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_secs() as u32)
+        .unwrap_or_default()
+}
+
+pub struct RecordManager {
+    pub dir: PathBuf,
+    pub format: RecordFormat,
+    pub segment_seconds: u32,
+    pub retention_seconds: u32,
+    keep_alive_seconds: u32,
+    tasks: Arc<DashMap<(String, String), RecordTask>>,
+}
+
+impl RecordManager {
+    pub fn new(config: &Config) -> Self {
+        let format = RecordFormat::from_str(&config.record_format).unwrap_or_else(|e| {
+            tracing::error!("record_format error: {}, e: {}", &config.record_format, e);
+            RecordFormat::Mp4
+        });
+
+        RecordManager {
+            dir: PathBuf::from(&config.record_dir),
+            format,
+            segment_seconds: config.record_segment_seconds.max(1),
+            retention_seconds: config.record_retention_seconds,
+            // 录像期间替代 HTTP 保活, 避免流被超时回收
+            keep_alive_seconds: (config.stream_timeout_seconds / 3).max(1),
+            tasks: Arc::new(DashMap::new()),
+        }
+    }
+
+    // 编码不是 20 位数字时返回 None, 避免拼出录像根目录之外的路径
+    pub fn channel_dir(&self, gb_code: &str, channel_id: &str) -> Option<PathBuf> {
+        if !is_gb_code(gb_code) || !is_gb_code(channel_id) {
+            return None;
+        }
+        Some(self.dir.join(gb_code).join(channel_id))
+    }
+
+    // 返回 (stream_id, format, start_ts)
+    pub fn find(&self, gb_code: &str, channel_id: &str) -> Option<(u32, RecordFormat, u32)> {
+        self.tasks
+            .get(&(gb_code.to_string(), channel_id.to_string()))
+            .map(|task| (task.stream_id, task.format, task.start_ts))
+    }
+
+    pub fn start(
+        &self,
+        sip_handler: Arc<SipHandler>,
+        gb_code: &str,
+        channel_id: &str,
+        stream_id: u32,
+        format: RecordFormat,
+        units: broadcast::Receiver<Arc<AccessUnit>>,
+    ) -> bool {
+        let Some(dir) = self.channel_dir(gb_code, channel_id) else {
+            return false;
+        };
+        let key = (gb_code.to_string(), channel_id.to_string());
+        let (stop_tx, stop_rx) = oneshot::channel();
+        self.tasks.insert(
+            key.clone(),
+            RecordTask {
+                stream_id,
+                format,
+                start_ts: ts_now(),
+                stop: Some(stop_tx),
+            },
+        );
+
+        let worker = RecordWorker {
+            sip_handler,
+            dir,
+            gb_code: gb_code.to_string(),
+            stream_id,
+            format,
+            segment_ticks: self.segment_seconds as u64 * 90000,
+            keep_alive_seconds: self.keep_alive_seconds,
+        };
+        let tasks = self.tasks.clone();
+        tokio::spawn(async move {
+            worker.run(units, stop_rx).await;
+            tasks.remove_if(&key, |_, task| task.stream_id == stream_id);
+        });
+        true
+    }
+
+    // 通知录像结束并返回对应的 stream_id, 由调用方关闭流
+    pub fn stop(&self, gb_code: &str, channel_id: &str) -> Option<u32> {
+        let (_, mut task) = self
+            .tasks
+            .remove(&(gb_code.to_string(), channel_id.to_string()))?;
+        if let Some(stop) = task.stop.take() {
+            let _ = stop.send(());
+        }
+        Some(task.stream_id)
+    }
+
+    pub fn files(&self, gb_code: &str, channel_id: &str) -> Vec<RecordFile> {
+        let mut files = vec![];
+        let Some(Ok(entries)) = self.channel_dir(gb_code, channel_id).map(std::fs::read_dir) else {
+            return files;
+        };
+
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            if !metadata.is_file() {
+                continue;
+            }
+            let name = entry.file_name().to_string_lossy().to_string();
+            let start_ts = Path::new(&name)
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(|stem| stem.parse().ok())
+                .unwrap_or_default();
+            files.push(RecordFile {
+                name,
+                path: path.to_string_lossy().to_string(),
+                size: metadata.len(),
+                start_ts,
+                modified_ts: modified_ts(&metadata),
+            });
+        }
+
+        files.sort_by(|a, b| a.name.cmp(&b.name));
+        files
+    }
+
+    // 删除超过保留时长的文件, 返回删除数量
+    pub fn cleanup(&self, now: u32) -> usize {
+        if self.retention_seconds == 0 {
+            return 0;
+        }
+
+        let mut removed = 0;
+        for device in std::fs::read_dir(&self.dir).into_iter().flatten().flatten() {
+            for channel in std::fs::read_dir(device.path())
+                .into_iter()
+                .flatten()
+                .flatten()
+            {
+                for entry in std::fs::read_dir(channel.path())
+                    .into_iter()
+                    .flatten()
+                    .flatten()
+                {
+                    let Ok(metadata) = entry.metadata() else {
+                        continue;
+                    };
+                    if !metadata.is_file() || modified_ts(&metadata) + self.retention_seconds > now
+                    {
+                        continue;
+                    }
+                    match std::fs::remove_file(entry.path()) {
+                        Ok(_) => removed += 1,
+                        Err(e) => {
+                            tracing::error!("remove_file({:?}) error, e: {:?}", entry.path(), e)
+                        }
+                    }
+                }
+            }
+        }
+        removed
+    }
+}
+
+struct RecordWorker {
+    sip_handler: Arc<SipHandler>,
+    dir: PathBuf,
+    gb_code: String,
+    stream_id: u32,
+    format: RecordFormat,
+    // 90kHz
+    segment_ticks: u64,
+    keep_alive_seconds: u32,
+}
+
+impl RecordWorker {
+    async fn run(
+        &self,
+        mut units: broadcast::Receiver<Arc<AccessUnit>>,
+        mut stop: oneshot::Receiver<()>,
+    ) {
+        let mut segment: Option<Segment> = None;
+        let mut keep_alive = tokio::time::interval(std::time::Duration::from_secs(
+            self.keep_alive_seconds as u64,
+        ));
+
+        loop {
+            tokio::select! {
+                _ = &mut stop => break,
+                _ = keep_alive.tick() => {
+                    self.sip_handler.store.stream_keep_alive(&self.gb_code, self.stream_id);
+                }
+                unit = units.recv() => match unit {
+                    Ok(unit) => {
+                        if !self.write(&mut segment, &unit).await {
+                            break;
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Lagged(n)) => {
+                        tracing::warn!("record lagged, stream_id: {}, skipped: {}", self.stream_id, n);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                },
+            }
+        }
+
+        if let Some(segment) = segment.take() {
+            self.close(segment).await;
+        }
+        tracing::info!("record finished, stream_id: {}", self.stream_id);
+    }
+
+    // 到达分段时长后在关键帧处切换文件, 写盘失败时结束录像
+    async fn write(&self, segment: &mut Option<Segment>, unit: &AccessUnit) -> bool {
+        let is_video_keyframe = unit.stream_type.is_video() && unit.keyframe;
+        let dts = unit.dts.or(unit.pts).unwrap_or_default();
+
+        if is_video_keyframe {
+            let rotate = segment.as_ref().is_some_and(|s| {
+                // 时间戳回退视为不连续, 同样切换文件
+                dts < s.start_dts || dts - s.start_dts >= self.segment_ticks
+            });
+            if rotate {
+                if let Some(s) = segment.take() {
+                    self.close(s).await;
+                }
+            }
+            if segment.is_none() {
+                match self.open(dts).await {
+                    Some(s) => *segment = Some(s),
+                    None => return false,
+                }
+            }
+        }
+
+        let Some(s) = segment.as_mut() else {
+            return true;
+        };
+        let data = s.muxer.push(unit);
+        if data.is_empty() {
+            return true;
+        }
+        match s.file.write_all(&data).await {
+            Ok(_) => true,
+            Err(e) => {
+                tracing::error!("write({:?}) error, e: {:?}", &s.path, e);
+                false
+            }
+        }
+    }
+
+    async fn open(&self, start_dts: u64) -> Option<Segment> {
+        if let Err(e) = tokio::fs::create_dir_all(&self.dir).await {
+            tracing::error!("create_dir_all({:?}) error, e: {:?}", &self.dir, e);
+            return None;
+        }
+
+        let path = self.dir.join(format!("{}.{}", ts_now(), self.format));
+        let mut file = match tokio::fs::File::create(&path).await {
+            Ok(file) => file,
+            Err(e) => {
+                tracing::error!("File::create({:?}) error, e: {:?}", &path, e);
+                return None;
+            }
+        };
+
+        let (muxer, header) = Muxer::new(self.format);
+        if let Err(e) = file.write_all(&header).await {
+            tracing::error!("write({:?}) error, e: {:?}", &path, e);
+            return None;
+        }
+        tracing::info!("record segment open: {:?}", &path);
+        Some(Segment {
+            file,
+            path,
+            muxer,
+            start_dts,
+        })
+    }
+
+    async fn close(&self, mut segment: Segment) {
+        let data = segment.muxer.finish();
+        if let Err(e) = segment.file.write_all(&data).await {
+            tracing::error!("write({:?}) error, e: {:?}", &segment.path, e);
+        }
+        let _ = segment.file.flush().await;
+    }
+}
+
+pub async fn run_forever(sip_handler: Arc<SipHandler>) {
+    if sip_handler.recorder.retention_seconds == 0 {
+        return;
+    }
+
+    loop {
+        let removed = sip_handler.recorder.cleanup(ts_now());
+        if removed > 0 {
+            tracing::info!("record cleanup, removed: {}", removed);
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(60)).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use dashmap::DashMap;
+
+    use super::{RecordFormat, RecordManager};
+
+    #[test]
+    fn test_files_and_cleanup() {
+        let dir = std::env::temp_dir().join(format!("wvprs-record-{}", std::process::id()));
+        let manager = RecordManager {
+            dir: dir.clone(),
+            format: RecordFormat::Mp4,
+            segment_seconds: 300,
+            retention_seconds: 3600,
+            keep_alive_seconds: 10,
+            tasks: Arc::new(DashMap::new()),
+        };
+
+        let channel_dir = manager
+            .channel_dir("34020000001180000000", "34020000001320000001")
+            .unwrap();
+        std::fs::create_dir_all(&channel_dir).unwrap();
+        let now = std::time::SystemTime::now();
+        for (name, age) in [("1700000000.mp4", 7200), ("1700000300.mp4", 60)] {
+            let file = std::fs::File::create(channel_dir.join(name)).unwrap();
+            file.set_len(16).unwrap();
+            file.set_modified(now - std::time::Duration::from_secs(age))
+                .unwrap();
+        }
+
+        let files = manager.files("34020000001180000000", "34020000001320000001");
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].start_ts, 1700000000);
+        assert_eq!(files[1].size, 16);
+
+        let now_ts = now.duration_since(std::time::UNIX_EPOCH).unwrap().as_secs() as u32;
+        assert_eq!(manager.cleanup(now_ts), 1);
+        let files = manager.files("34020000001180000000", "34020000001320000001");
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].name, "1700000300.mp4");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!("flv".parse::<RecordFormat>(), Ok(RecordFormat::Flv));
+    }
+
+    #[test]
+    fn test_reject_invalid_codes() {
+        let manager = RecordManager {
+            dir: std::env::temp_dir().join("wvprs-record"),
+            format: RecordFormat::Mp4,
+            segment_seconds: 300,
+            retention_seconds: 3600,
+            keep_alive_seconds: 10,
+            tasks: Arc::new(DashMap::new()),
+        };
+
+        let channel_id = "34020000001320000001";
+        for gb_code in [
+            "..",
+            "../../etc",
+            "3402000000118000000/",
+            r"34020000001180000\..",
+            "/tmp",
+            "3402000000118000000",
+            "3402000000118000000A",
+        ] {
+            assert!(manager.channel_dir(gb_code, channel_id).is_none());
+            assert!(manager.channel_dir(channel_id, gb_code).is_none());
+            assert!(manager.files(gb_code, channel_id).is_empty());
+        }
+    }
+}
@@ -0,0 +1,118 @@
+use tokio::io::AsyncReadExt;
+
+// RFC 3550 固定头长度
+static RTP_HEADER_SIZE: usize = 12;
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RtpHeader {
+    pub marker: bool,
+    pub payload_type: u8,
+    pub sequence: u16,
+    pub timestamp: u32,
+    pub ssrc: u32,
+}
+
+impl RtpHeader {
+    // 解析 RTP 头, 返回头与去除 CSRC/扩展头/填充后的负载
+    pub fn parse(data: &[u8]) -> Option<(RtpHeader, &[u8])> {
+        if data.len() < RTP_HEADER_SIZE || data[0] >> 6 != 2 {
+            return None;
+        }
+
+        let padding = data[0] & 0x20 != 0;
+        let extension = data[0] & 0x10 != 0;
+        let csrc_count = (data[0] & 0x0F) as usize;
+        let header = RtpHeader {
+            marker: data[1] & 0x80 != 0,
+            payload_type: data[1] & 0x7F,
+            sequence: u16::from_be_bytes([data[2], data[3]]),
+            timestamp: u32::from_be_bytes([data[4], data[5], data[6], data[7]]),
+            ssrc: u32::from_be_bytes([data[8], data[9], data[10], data[11]]),
+        };
+
+        let mut offset = RTP_HEADER_SIZE + csrc_count * 4;
+        if extension {
+            if data.len() < offset + 4 {
+                return None;
+            }
+            let words = u16::from_be_bytes([data[offset + 2], data[offset + 3]]) as usize;
+            offset += 4 + words * 4;
+        }
+
+        let mut end = data.len();
+        if padding {
+            let padding_size = *data.last()? as usize;
+            end = end.checked_sub(padding_size)?;
+        }
+        if offset > end {
+            return None;
+        }
+
+        Some((header, &data[offset..end]))
+    }
+}
+
+// RFC 4571: TCP 上每个 RTP 包前有 2 字节长度
+pub async fn read_rfc4571_frame<R: AsyncReadExt + Unpin>(
+    reader: &mut R,
+    buffer: &mut Vec<u8>,
+) -> std::io::Result<usize> {
+    let size = reader.read_u16().await? as usize;
+    buffer.resize(size, 0);
+    reader.read_exact(buffer.as_mut_slice()).await?;
+    Ok(size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{read_rfc4571_frame, RtpHeader};
+
+    #[test]
+    fn test_parse_rtp_header() {
+        let packet = [
+            0x80, 0xE0, 0x00, 0x2A, 0x00, 0x00, 0x0E, 0x10, 0x12, 0x34, 0x56, 0x78, 0x00, 0x00,
+            0x01, 0xBA,
+        ];
+        let (header, payload) = RtpHeader::parse(&packet).unwrap();
+        assert!(header.marker);
+        assert_eq!(header.payload_type, 96);
+        assert_eq!(header.sequence, 42);
+        assert_eq!(header.timestamp, 3600);
+        assert_eq!(header.ssrc, 0x12345678);
+        assert_eq!(payload, &[0x00, 0x00, 0x01, 0xBA]);
+    }
+
+    #[test]
+    fn test_parse_rtp_header_with_extension_and_padding() {
+        let packet = [
+            0xB1, 0x60, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, // header
+            0x00, 0x00, 0x00, 0x02, // csrc
+            0xBE, 0xDE, 0x00, 0x01, 0x01, 0x02, 0x03, 0x04, // extension
+            0xAA, 0xBB, // payload
+            0x00, 0x02, // padding
+        ];
+        let (header, payload) = RtpHeader::parse(&packet).unwrap();
+        assert!(!header.marker);
+        assert_eq!(header.ssrc, 1);
+        assert_eq!(payload, &[0xAA, 0xBB]);
+        assert!(RtpHeader::parse(&packet[..10]).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_read_rfc4571_frame() {
+        let data: Vec<u8> = vec![0x00, 0x03, 0x01, 0x02, 0x03, 0x00, 0x01, 0x04];
+        let mut reader = data.as_slice();
+        let mut buffer = vec![];
+        assert_eq!(
+            read_rfc4571_frame(&mut reader, &mut buffer).await.unwrap(),
+            3
+        );
+        assert_eq!(buffer, vec![0x01, 0x02, 0x03]);
+        assert_eq!(
+            read_rfc4571_frame(&mut reader, &mut buffer).await.unwrap(),
+            1
+        );
+        assert_eq!(buffer, vec![0x04]);
+        assert!(read_rfc4571_frame(&mut reader, &mut buffer).await.is_err());
+    }
+}
@@ -0,0 +1,359 @@
+use async_trait::async_trait;
+use serde::Deserialize;
+
+use super::{MediaNodeLoad, MediaServer, PlayUrls, RtpTransport, SendRtp};
+use crate::utils::config::MediaNode;
+
+// ZLMediaKit HTTP API 超时时间
+static API_TIMEOUT_SECONDS: u64 = 5;
+
+#[derive(Debug, Default, Deserialize)]
+struct ZlmResponse {
+    code: i32,
+    #[serde(default)]
+    msg: String,
+    #[serde(default)]
+    port: u16,
+    #[serde(default)]
+    online: bool,
+    #[serde(default)]
+    hit: u32,
+    #[serde(default)]
+    local_port: u16,
+    #[serde(default)]
+    data: Vec<ZlmMediaItem>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ZlmMediaItem {
+    #[serde(default)]
+    schema: String,
+    #[serde(rename = "bytesSpeed", default)]
+    bytes_speed: u64,
+}
+
+pub struct ZlmMediaServer {
+    pub id: String,
+    pub ip: String,
+    pub api_url: String,
+    pub secret: String,
+    client: reqwest::Client,
+}
+
+impl ZlmMediaServer {
+    pub fn new(node: &MediaNode) -> Self {
+        ZlmMediaServer {
+            id: node.id.clone(),
+            ip: node.ip.clone(),
+            api_url: node.api_url.trim_end_matches('/').to_string(),
+            secret: node.secret.clone(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    async fn call(&self, api: &str, params: &[(&str, String)]) -> Option<ZlmResponse> {
+        let url = format!("{}/index/api/{}", self.api_url, api);
+        let result = self
+            .client
+            .get(&url)
+            .query(&[("secret", self.secret.as_str())])
+            .query(params)
+            .timeout(std::time::Duration::from_secs(API_TIMEOUT_SECONDS))
+            .send()
+            .await;
+
+        let response = match result {
+            Ok(response) => response.json::<ZlmResponse>().await,
+            Err(e) => Err(e),
+        };
+        match response {
+            Ok(data) if data.code == 0 => Some(data),
+            Ok(data) => {
+                tracing::error!(
+                    "zlm api error, node: {}, api: {}, code: {}, msg: {}",
+                    self.id,
+                    api,
+                    data.code,
+                    data.msg
+                );
+                None
+            }
+            Err(e) => {
+                tracing::error!("zlm api error, node: {}, api: {}, e: {:?}", self.id, api, e);
+                None
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl MediaServer for ZlmMediaServer {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn ip(&self) -> &str {
+        &self.ip
+    }
+
+    async fn open_rtp_server(
+        &self,
+        stream_name: &str,
+        port: u16,
+        transport: RtpTransport,
+    ) -> Option<u16> {
+        let tcp_mode = match transport {
+            RtpTransport::Udp => 0,
+            RtpTransport::TcpPassive => 1,
+            RtpTransport::TcpActive => 2,
+        };
+        let data = self
+            .call(
+                "openRtpServer",
+                &[
+                    ("port", port.to_string()),
+                    ("tcp_mode", tcp_mode.to_string()),
+                    ("stream_id", stream_name.to_string()),
+                ],
+            )
+            .await?;
+        Some(data.port)
+    }
+
+    async fn connect_rtp_server(
+        &self,
+        stream_name: &str,
+        remote_ip: &str,
+        remote_port: u16,
+    ) -> bool {
+        self.call(
+            "connectRtpServer",
+            &[
+                ("dst_url", remote_ip.to_string()),
+                ("dst_port", remote_port.to_string()),
+                ("stream_id", stream_name.to_string()),
+            ],
+        )
+        .await
+        .is_some()
+    }
+
+    async fn close_rtp_server(&self, stream_name: &str) -> bool {
+        match self
+            .call("closeRtpServer", &[("stream_id", stream_name.to_string())])
+            .await
+        {
+            Some(data) => data.hit > 0,
+            None => false,
+        }
+    }
+
+    async fn stream_exists(&self, stream_name: &str) -> bool {
+        match self
+            .call(
+                "isMediaOnline",
+                &[
+                    ("schema", String::from("rtsp")),
+                    ("vhost", String::from("__defaultVhost__")),
+                    ("app", String::from("rtp")),
+                    ("stream", stream_name.to_string()),
+                ],
+            )
+            .await
+        {
+            Some(data) => data.online,
+            None => false,
+        }
+    }
+
+    // 音频源由客户端推送到节点, 只发送音频轨
+    async fn start_send_rtp(&self, params: &SendRtp) -> Option<u16> {
+        let data = self
+            .call(
+                "startSendRtp",
+                &[
+                    ("vhost", String::from("__defaultVhost__")),
+                    ("app", params.app.clone()),
+                    ("stream", params.stream.clone()),
+                    ("ssrc", params.ssrc.clone()),
+                    ("dst_url", params.dst_ip.clone()),
+                    ("dst_port", params.dst_port.to_string()),
+                    ("is_udp", (params.udp as u8).to_string()),
+                    ("use_ps", ((params.payload_type == 96) as u8).to_string()),
+                    ("pt", params.payload_type.to_string()),
+                    ("only_audio", String::from("1")),
+                ],
+            )
+            .await?;
+        Some(data.local_port)
+    }
+
+    async fn stop_send_rtp(&self, app: &str, stream: &str, ssrc: &str) -> bool {
+        self.call(
+            "stopSendRtp",
+            &[
+                ("vhost", String::from("__defaultVhost__")),
+                ("app", app.to_string()),
+                ("stream", stream.to_string()),
+                ("ssrc", ssrc.to_string()),
+            ],
+        )
+        .await
+        .is_some()
+    }
+
+    // ZLM 的 HTTP 服务与 API 同端口, rtp 收流挂在 app=rtp 下
+    fn play_urls(&self, stream_name: &str) -> Option<PlayUrls> {
+        let base = self.api_url.trim_end_matches('/');
+        Some(PlayUrls {
+            flv: format!("{}/rtp/{}.live.flv", base, stream_name),
+            hls: format!("{}/rtp/{}/hls.m3u8", base, stream_name),
+        })
+    }
+
+    // 同一路流在每种协议下各出现一次, 只统计 rtsp
+    async fn heartbeat(&self) -> Option<MediaNodeLoad> {
+        let data = self.call("getMediaList", &[]).await?;
+        let items: Vec<_> = data
+            .data
+            .iter()
+            .filter(|item| item.schema == "rtsp")
+            .collect();
+        Some(MediaNodeLoad {
+            streams: items.len() as u32,
+            bandwidth: items.iter().map(|item| item.bytes_speed).sum(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    use super::ZlmMediaServer;
+    use crate::media::{MediaManager, MediaServer, RtpTransport, SendRtp};
+    use crate::utils::config::{Config, MediaNode};
+
+    // 只应答一次请求的 ZLMediaKit API 替身, 返回收到的请求行
+    async fn serve_once(body: &'static str) -> (String, tokio::task::JoinHandle<String>) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let handle = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buffer = vec![0; 4096];
+            let n = stream.read(&mut buffer).await.unwrap();
+            let request = String::from_utf8_lossy(&buffer[..n]).to_string();
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            stream.write_all(response.as_bytes()).await.unwrap();
+            request.lines().next().unwrap_or_default().to_string()
+        });
+        (url, handle)
+    }
+
+    fn node(api_url: String) -> MediaNode {
+        MediaNode {
+            id: String::from("zlm-test"),
+            kind: String::from("zlm"),
+            ip: String::from("10.0.0.1"),
+            api_url,
+            secret: String::from("secret"),
+            weight: 1,
+            rtp_port_min: 30000,
+            rtp_port_max: 30500,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_open_rtp_server() {
+        let (url, handle) = serve_once(r#"{"code":0,"port":30012}"#).await;
+        let server = ZlmMediaServer::new(&node(url));
+        let port = server
+            .open_rtp_server("34020000001320000001_1", 30012, RtpTransport::TcpPassive)
+            .await;
+        assert_eq!(port, Some(30012));
+
+        let request_line = handle.await.unwrap();
+        assert!(request_line.starts_with("GET /index/api/openRtpServer?secret=secret"));
+        assert!(request_line.contains("port=30012"));
+        assert!(request_line.contains("tcp_mode=1"));
+        assert!(request_line.contains("stream_id=34020000001320000001_1"));
+    }
+
+    #[tokio::test]
+    async fn test_open_rtp_server_error() {
+        let (url, handle) = serve_once(r#"{"code":-300,"msg":"port busy"}"#).await;
+        let server = ZlmMediaServer::new(&node(url));
+        let port = server
+            .open_rtp_server("34020000001320000001_2", 30014, RtpTransport::Udp)
+            .await;
+        assert_eq!(port, None);
+        handle.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_node_returns_other_port() {
+        let (url, handle) = serve_once(r#"{"code":0,"port":30100}"#).await;
+        let mut config: Config = serde_yaml::from_str("{}").unwrap();
+        config.media_nodes = vec![node(url)];
+        let media = MediaManager::new(&config);
+        let (_, port) = media
+            .open_rtp_server("34020000001320000001_3", RtpTransport::Udp)
+            .await
+            .unwrap();
+        handle.await.unwrap();
+
+        // 应答的端口用于收流, 分配的端口立即归还
+        assert_eq!(port, 30100);
+        assert_eq!(
+            *media.streams.get("34020000001320000001_3").unwrap(),
+            (0, None, 30100)
+        );
+        assert_eq!(media.ports[0].lock().unwrap().in_use(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_start_send_rtp() {
+        let (url, handle) = serve_once(r#"{"code":0,"local_port":30020}"#).await;
+        let server = ZlmMediaServer::new(&node(url));
+        let port = server
+            .start_send_rtp(&SendRtp {
+                app: String::from("broadcast"),
+                stream: String::from("34020000001320000001_34020000001370000001"),
+                ssrc: String::from("0100000001"),
+                dst_ip: String::from("192.168.1.64"),
+                dst_port: 15060,
+                payload_type: 8,
+                udp: true,
+            })
+            .await;
+        assert_eq!(port, Some(30020));
+
+        let request_line = handle.await.unwrap();
+        assert!(request_line.starts_with("GET /index/api/startSendRtp?secret=secret"));
+        assert!(request_line.contains("app=broadcast"));
+        assert!(request_line.contains("dst_port=15060"));
+        assert!(request_line.contains("is_udp=1"));
+        assert!(request_line.contains("use_ps=0"));
+        assert!(request_line.contains("pt=8"));
+    }
+
+    #[tokio::test]
+    async fn test_heartbeat() {
+        let (url, handle) = serve_once(
+            r#"{"code":0,"data":[{"schema":"rtsp","bytesSpeed":1000},{"schema":"rtmp","bytesSpeed":1000},{"schema":"rtsp","bytesSpeed":500}]}"#,
+        )
+        .await;
+        let server = ZlmMediaServer::new(&node(url));
+        let load = server.heartbeat().await.unwrap();
+        assert_eq!(load.streams, 2);
+        assert_eq!(load.bandwidth, 1500);
+        assert!(handle
+            .await
+            .unwrap()
+            .starts_with("GET /index/api/getMediaList"));
+    }
+}
@@ -0,0 +1,190 @@
+use std::sync::Arc;
+
+use rsip::{
+    self,
+    prelude::{HeadersExt, ToTypedHeader},
+};
+use tokio::time::{sleep, sleep_until, Duration, Instant};
+
+use crate::sip::handler::{SipHandler, SipTransaction};
+use crate::utils::config::UpstreamPlatform;
+
+// 注册失败后的重试间隔
+static REGISTER_RETRY_SECONDS: u64 = 30;
+// 连续心跳失败次数达到后重新注册
+static KEEP_ALIVE_MAX_FAILURES: u32 = 3;
+
+// 每个上级平台一个注册/心跳循环
+pub async fn run_forever(sip_handler: Arc<SipHandler>) {
+    let tasks: Vec<_> = sip_handler
+        .upstream_platforms
+        .iter()
+        .cloned()
+        .map(|platform| tokio::spawn(register_loop(sip_handler.clone(), platform)))
+        .collect();
+
+    for task in tasks {
+        let _ = task.await;
+    }
+}
+
+async fn register_loop(sip_handler: Arc<SipHandler>, platform: UpstreamPlatform) {
+    let upstream_addr = match format!("{}:{}", platform.ip, platform.port).parse() {
+        Ok(addr) => addr,
+        Err(e) => {
+            tracing::error!(
+                "upstream address error, id: {}, ip: {}, e: {:?}",
+                platform.id,
+                platform.ip,
+                e
+            );
+            return;
+        }
+    };
+
+    // 同一注册会话内 Call-ID 与 From tag 保持不变, CSeq 递增
+    let caller_id = sip_handler.caller_id_str();
+    let from_tag = sip_handler.tag_new(32);
+    let mut seq: u32 = 0;
+
+    loop {
+        let expires = match sip_handler
+            .cascade_register(&platform, upstream_addr, &caller_id, &from_tag, &mut seq)
+            .await
+        {
+            Some(expires) if expires > 0 => expires,
+            _ => {
+                sleep(Duration::from_secs(REGISTER_RETRY_SECONDS)).await;
+                continue;
+            }
+        };
+        tracing::info!(
+            "registered to upstream {}, expires: {}",
+            platform.id,
+            expires
+        );
+
+        // 在有效期过半时刷新注册
+        let refresh_at = Instant::now() + Duration::from_secs(expires as u64 / 2);
+        let interval = Duration::from_secs(platform.keep_alive_interval.max(1) as u64);
+        let mut failures = 0;
+        loop {
+            let next = Instant::now() + interval;
+            if next >= refresh_at {
+                sleep_until(refresh_at).await;
+                break;
+            }
+            sleep_until(next).await;
+
+            if sip_handler
+                .cascade_keep_alive(&platform, upstream_addr)
+                .await
+            {
+                failures = 0;
+            } else {
+                failures += 1;
+                tracing::warn!(
+                    "upstream keepalive failed, id: {}, failures: {}",
+                    platform.id,
+                    failures
+                );
+                if failures >= KEEP_ALIVE_MAX_FAILURES {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+impl SipHandler {
+    // 返回上级认可的有效期, 失败返回 None
+    pub async fn cascade_register(
+        &self,
+        platform: &UpstreamPlatform,
+        upstream_addr: std::net::SocketAddr,
+        caller_id: &str,
+        from_tag: &str,
+        seq: &mut u32,
+    ) -> Option<u32> {
+        let mut authorization = None;
+        // 首次请求不带认证, 收到 401 后带摘要重发一次
+        for _ in 0..2 {
+            *seq += 1;
+            let transaction = SipTransaction {
+                caller_id: caller_id.to_string(),
+                from_tag: from_tag.to_string(),
+                to_tag: String::new(),
+                branch: format!("z9hG4bK{}", self.tag_new(16)),
+            };
+
+            let receiver = self.transaction_begin(caller_id);
+            if !self
+                .send_register(
+                    platform,
+                    upstream_addr,
+                    &transaction,
+                    *seq,
+                    platform.register_expires,
+                    authorization.take(),
+                )
+                .await
+            {
+                self.transaction_cancel(caller_id);
+                return None;
+            }
+
+            let response = self.transaction_wait(caller_id, receiver).await?;
+            match response.status_code() {
+                rsip::StatusCode::OK => {
+                    let expires = response
+                        .expires_header()
+                        .and_then(|exp| exp.seconds().ok())
+                        .unwrap_or(platform.register_expires);
+                    return Some(expires);
+                }
+                rsip::StatusCode::Unauthorized => {
+                    let challenge = response.www_authenticate_header()?.typed().ok()?;
+                    authorization = Some(self.authorization_new(
+                        &platform.password,
+                        &rsip::Method::Register,
+                        &self.upstream_uri(platform),
+                        &challenge,
+                    ));
+                }
+                status_code => {
+                    tracing::warn!(
+                        "upstream register rejected, id: {}, status: {}",
+                        platform.id,
+                        status_code
+                    );
+                    return None;
+                }
+            }
+        }
+
+        tracing::warn!("upstream register unauthorized, id: {}", platform.id);
+        None
+    }
+
+    pub async fn cascade_keep_alive(
+        &self,
+        platform: &UpstreamPlatform,
+        upstream_addr: std::net::SocketAddr,
+    ) -> bool {
+        let caller_id = self.caller_id_str();
+        let branch = format!("z9hG4bK{}", self.tag_new(16));
+        let receiver = self.transaction_begin(&caller_id);
+        if !self
+            .send_keep_alive(platform, upstream_addr, &branch, &caller_id)
+            .await
+        {
+            self.transaction_cancel(&caller_id);
+            return false;
+        }
+
+        matches!(
+            self.transaction_wait(&caller_id, receiver).await,
+            Some(response) if response.status_code() == &rsip::StatusCode::OK
+        )
+    }
+}
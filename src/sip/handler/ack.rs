@@ -1,3 +1,5 @@
+use rsip::prelude::{HeadersExt, UntypedHeader};
+
 use super::SipHandler;
 
 impl SipHandler {
@@ -5,8 +7,20 @@ impl SipHandler {
         &self,
         _device_addr: std::net::SocketAddr,
         _tcp_stream: Option<std::sync::Arc<tokio::sync::Mutex<tokio::net::tcp::OwnedWriteHalf>>>,
-        _request: rsip::Request,
+        request: rsip::Request,
     ) {
+        let Ok(call_id) = request.call_id_header() else {
+            return;
+        };
+        // 设备确认语音广播的应答, 会话建立
+        if let Some((gb_code, channel_id)) = self.broadcast_find_by_caller_id(call_id.value()) {
+            tracing::info!(
+                "broadcast established, gb_code: {}, channel_id: {}, call_id: {}",
+                gb_code,
+                channel_id,
+                call_id.value()
+            );
+        }
     }
 
     pub async fn on_rsp_ack(
@@ -0,0 +1,171 @@
+use dashmap::mapref::entry::Entry;
+
+use crate::store::DeviceInfo;
+
+use super::{BroadcastMedia, BroadcastSession, SipHandler, SipTransaction};
+
+impl SipHandler {
+    // 语音广播: 通知设备后等待其 INVITE, 应答后节点开始向设备发送音频
+    pub async fn broadcast_start(
+        &self,
+        gb_code: &str,
+        channel_id: &str,
+    ) -> Result<BroadcastMedia, (u32, String)> {
+        let Some(DeviceInfo {
+            branch,
+            socket_addr,
+            tcp_stream,
+        }) = self.store.find_device_by_gb_code(gb_code)
+        else {
+            return Err((404, String::from("device not found")));
+        };
+        let Ok(node) = self.media.select_node() else {
+            return Err((503, String::from("media server unavailable")));
+        };
+
+        // 检查与登记在同一个分片锁内完成, 并发的请求只有一个能成功
+        let key = (gb_code.to_string(), channel_id.to_string());
+        let (sender, waiter) = tokio::sync::oneshot::channel();
+        match self.broadcasts.entry(key.clone()) {
+            Entry::Occupied(_) => return Err((409, String::from("broadcast in progress"))),
+            Entry::Vacant(entry) => entry.insert(BroadcastSession {
+                node,
+                media: BroadcastMedia {
+                    media_ip: self.media.node_ip(node).unwrap_or_default().to_string(),
+                    stream: format!("{}_{}", gb_code, channel_id),
+                    ..Default::default()
+                },
+                caller_id: String::new(),
+                peer_id: String::new(),
+                from_tag: String::new(),
+                to_tag: String::new(),
+                waiter: Some(sender),
+            }),
+        };
+
+        let transport = if tcp_stream.is_some() {
+            rsip::Transport::Tcp
+        } else {
+            rsip::Transport::Udp
+        };
+        let call_id = self.caller_id_str();
+        let receiver = self.transaction_begin(&call_id);
+        if !self
+            .send_broadcast(
+                socket_addr,
+                tcp_stream,
+                transport,
+                &branch,
+                &call_id,
+                &gb_code.to_string(),
+                channel_id,
+            )
+            .await
+        {
+            self.transaction_cancel(&call_id);
+            self.broadcasts.remove(&key);
+            return Err((500, String::from("send failed")));
+        }
+        match self.transaction_wait(&call_id, receiver).await {
+            None => {
+                self.broadcasts.remove(&key);
+                return Err((408, String::from("device timeout")));
+            }
+            Some(response) if response.status_code().code() >= 300 => {
+                self.broadcasts.remove(&key);
+                return Err((
+                    response.status_code().code() as u32,
+                    format!("device rejected: {}", response.status_code()),
+                ));
+            }
+            Some(_) => {}
+        }
+
+        match tokio::time::timeout(
+            std::time::Duration::from_secs(self.invite_timeout_seconds as u64),
+            waiter,
+        )
+        .await
+        {
+            Ok(Ok(Ok(()))) => self
+                .broadcasts
+                .get(&key)
+                .map(|session| session.media.clone())
+                .ok_or((487, String::from("broadcast cancelled"))),
+            Ok(Ok(Err(e))) => Err(e),
+            Ok(Err(_)) => Err((487, String::from("broadcast cancelled"))),
+            Err(_) => {
+                self.broadcasts
+                    .remove_if(&key, |_, session| session.caller_id.is_empty());
+                Err((408, String::from("device no invite")))
+            }
+        }
+    }
+
+    // 挂断广播对话并停止发送音频
+    pub async fn broadcast_stop(
+        &self,
+        gb_code: &str,
+        channel_id: &str,
+    ) -> Result<(), (u32, String)> {
+        let Some((_, session)) = self
+            .broadcasts
+            .remove(&(gb_code.to_string(), channel_id.to_string()))
+        else {
+            return Err((404, String::from("broadcast not found")));
+        };
+        if session.caller_id.is_empty() {
+            return Ok(());
+        }
+
+        self.media.stop_send_rtp(&session.media.stream).await;
+        if let Some(DeviceInfo {
+            socket_addr,
+            tcp_stream,
+            ..
+        }) = self.store.find_device_by_gb_code(gb_code)
+        {
+            // 对话由设备发起, 本平台的 tag 在 To 中
+            self.send_bye(
+                socket_addr,
+                tcp_stream,
+                SipTransaction {
+                    caller_id: session.caller_id,
+                    from_tag: session.to_tag,
+                    to_tag: session.from_tag,
+                    branch: format!("z9hG4bK{}", self.tag_new(16)),
+                },
+                &session.peer_id,
+            )
+            .await;
+        }
+        Ok(())
+    }
+
+    // 设备 INVITE 的 From 可能是语音输出通道, 也可能是设备自身
+    pub fn broadcast_find(&self, from_id: &str) -> Option<(String, String)> {
+        self.broadcasts
+            .iter()
+            .find(|session| {
+                let (gb_code, channel_id) = session.key();
+                session.caller_id.is_empty() && (channel_id == from_id || gb_code == from_id)
+            })
+            .map(|session| session.key().clone())
+    }
+
+    pub fn broadcast_find_by_caller_id(&self, caller_id: &str) -> Option<(String, String)> {
+        self.broadcasts
+            .iter()
+            .find(|session| session.caller_id == caller_id)
+            .map(|session| session.key().clone())
+    }
+
+    // 设备拒绝广播或协商失败, 结束等待中的 /broadcast/start
+    pub fn broadcast_fail(&self, key: &(String, String), e: (u32, String)) {
+        if let Some((_, mut session)) = self.broadcasts.remove(key) {
+            if let Some(waiter) = session.waiter.take() {
+                let _ = waiter.send(Err(e));
+            }
+        }
+    }
+}
@@ -1,12 +1,87 @@
-use super::SipHandler;
+use rsip::{
+    self,
+    prelude::{HeadersExt, UntypedHeader},
+};
+
+use super::{SipHandler, SipTransaction};
+use crate::store::ByeResult;
 
 impl SipHandler {
     pub async fn on_req_bye(
         &self,
-        _device_addr: std::net::SocketAddr,
-        _tcp_stream: Option<std::sync::Arc<tokio::sync::Mutex<tokio::net::tcp::OwnedWriteHalf>>>,
-        _request: rsip::Request,
+        device_addr: std::net::SocketAddr,
+        tcp_stream: Option<std::sync::Arc<tokio::sync::Mutex<tokio::net::tcp::OwnedWriteHalf>>>,
+        request: rsip::Request,
     ) {
+        let call_id = request.call_id_header().unwrap().value().to_string();
+
+        // 设备挂断语音广播
+        if let Some((_, session)) = self
+            .broadcast_find_by_caller_id(&call_id)
+            .and_then(|key| self.broadcasts.remove(&key))
+        {
+            tracing::info!("broadcast closed by device, call_id: {}", call_id);
+            self.reply_ok(device_addr, tcp_stream, &request).await;
+            self.media.stop_send_rtp(&session.media.stream).await;
+            return;
+        }
+
+        // 设备挂断对讲, 对话已结束, 只释放流
+        if let Some(talk) = self.store.take_talk(&call_id) {
+            tracing::info!("talk closed by device, call_id: {}", call_id);
+            self.reply_ok(device_addr, tcp_stream, &request).await;
+            self.store.bye_all(&talk.gb_code, talk.stream_id);
+            self.stream_release(&talk.gb_code, talk.stream_id, None)
+                .await;
+            return;
+        }
+
+        // 上级平台挂断级联点播
+        let Some((_, session)) = self.cascade_sessions.remove(&call_id) else {
+            return;
+        };
+
+        let mut headers: rsip::Headers = Default::default();
+        headers.push(request.via_header().unwrap().clone().into());
+        headers.push(request.from_header().unwrap().clone().into());
+        headers.push(self.to_old(request.to_header().unwrap()).into());
+        headers.push(request.call_id_header().unwrap().clone().into());
+        headers.push(request.cseq_header().unwrap().clone().into());
+        headers.push(rsip::Header::ContentLength(Default::default()));
+
+        let response = rsip::Response {
+            status_code: rsip::StatusCode::OK,
+            headers,
+            version: rsip::Version::V2,
+            body: Default::default(),
+        };
+        self.socket_send_response(device_addr, tcp_stream, response)
+            .await;
+
+        // 级联会话与设备是独立的对话, 无论是否还有其他观看者都需要挂断
+        if let Some(ByeResult {
+            caller_id,
+            from_tag,
+            to_tag,
+            branch,
+            socket_addr,
+            tcp_stream,
+            ..
+        }) = self.store.bye(&session.gb_code, session.stream_id)
+        {
+            self.send_bye(
+                socket_addr,
+                tcp_stream,
+                SipTransaction {
+                    caller_id,
+                    from_tag,
+                    to_tag,
+                    branch,
+                },
+                &session.gb_code,
+            )
+            .await;
+        }
     }
 
     pub async fn on_rsp_bye(
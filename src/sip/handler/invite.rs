@@ -2,20 +2,391 @@ use std::str::FromStr;
 
 use rsip::{
     self,
-    prelude::{HeadersExt, UntypedHeader},
+    prelude::{HeadersExt, ToTypedHeader, UntypedHeader},
 };
 
 use sdp_rs;
 
-use super::SipHandler;
+use super::{CascadeSession, SipHandler, SipTransaction};
+use crate::media;
+use crate::sip::message::{sdp, SdpAnswer, SdpSessionType};
+use crate::store::InviteResult;
 
 impl SipHandler {
     pub async fn on_req_invite(
         &self,
-        _device_addr: std::net::SocketAddr,
-        _tcp_stream: Option<std::sync::Arc<tokio::sync::Mutex<tokio::net::tcp::OwnedWriteHalf>>>,
-        _request: rsip::Request,
+        device_addr: std::net::SocketAddr,
+        tcp_stream: Option<std::sync::Arc<tokio::sync::Mutex<tokio::net::tcp::OwnedWriteHalf>>>,
+        request: rsip::Request,
+    ) {
+        let from_id = request
+            .from_header()
+            .unwrap()
+            .uri()
+            .unwrap()
+            .auth
+            .map(|auth| auth.to_string())
+            .unwrap_or_default();
+        if self.upstream_find(&from_id).is_some() {
+            self.on_req_invite_upstream(device_addr, tcp_stream, request)
+                .await;
+        } else if let Some(key) = self.broadcast_find(&from_id) {
+            self.on_req_invite_broadcast(device_addr, tcp_stream, request, from_id, key)
+                .await;
+        } else {
+            tracing::warn!("unexpected invite from: {}", device_addr);
+        }
+    }
+
+    // 上级平台点播, 转发给通道所属的下级设备
+    async fn on_req_invite_upstream(
+        &self,
+        upstream_addr: std::net::SocketAddr,
+        upstream_tcp_stream: Option<
+            std::sync::Arc<tokio::sync::Mutex<tokio::net::tcp::OwnedWriteHalf>>,
+        >,
+        request: rsip::Request,
+    ) {
+        self.send_invite_reply(
+            upstream_addr,
+            upstream_tcp_stream.clone(),
+            &request,
+            rsip::StatusCode::Trying,
+            None,
+            None,
+        )
+        .await;
+
+        let channel_id = request
+            .uri()
+            .auth
+            .as_ref()
+            .map(|auth| auth.user.clone())
+            .unwrap_or_default();
+        let Some(gb_code) = self.cascade_find_device(&channel_id) else {
+            self.send_invite_reply(
+                upstream_addr,
+                upstream_tcp_stream,
+                &request,
+                rsip::StatusCode::NotFound,
+                Some(&self.tag_new(32)),
+                None,
+            )
+            .await;
+            return;
+        };
+
+        // 级联会话使用上级的 SDP, 媒体直接送往上级, 不与本地实时流共享
+        let call_id = self.caller_id_str();
+        let from_tag = self.tag_new(32);
+        let Some(InviteResult {
+            stream_id,
+            branch,
+            socket_addr,
+            tcp_stream,
+            ..
+        }) = self
+            .store
+            .invite(&gb_code, &channel_id, &call_id, &from_tag, false)
+        else {
+            self.send_invite_reply(
+                upstream_addr,
+                upstream_tcp_stream,
+                &request,
+                rsip::StatusCode::NotFound,
+                Some(&self.tag_new(32)),
+                None,
+            )
+            .await;
+            return;
+        };
+
+        let mut branch = branch;
+        let mut authorization = None;
+        let mut response = None;
+        // 设备要求认证时带摘要重发一次
+        for _ in 0..2 {
+            let receiver = self.transaction_begin(&call_id);
+            if !self
+                .send_invite_with_sdp(
+                    socket_addr,
+                    tcp_stream.clone(),
+                    SipTransaction {
+                        caller_id: call_id.clone(),
+                        from_tag: from_tag.clone(),
+                        to_tag: String::new(),
+                        branch: branch.clone(),
+                    },
+                    &channel_id,
+                    &gb_code,
+                    self.decode_body(request.body()),
+                    authorization.take(),
+                )
+                .await
+            {
+                self.transaction_cancel(&call_id);
+                response = None;
+                break;
+            }
+            response = self
+                .transaction_wait_timeout(&call_id, receiver, self.invite_timeout_seconds)
+                .await;
+            authorization = response.as_ref().and_then(|response| {
+                self.authorization_for(response, &rsip::Method::Invite, &self.invite_uri(&gb_code))
+            });
+            if authorization.is_none() {
+                break;
+            }
+            branch = format!("z9hG4bK{}", self.tag_new(16));
+        }
+
+        match response {
+            Some(device_response) if &rsip::StatusCode::OK == device_response.status_code() => {
+                self.cascade_sessions.insert(
+                    request.call_id_header().unwrap().value().to_string(),
+                    CascadeSession { gb_code, stream_id },
+                );
+                self.send_invite_reply(
+                    upstream_addr,
+                    upstream_tcp_stream,
+                    &request,
+                    rsip::StatusCode::OK,
+                    Some(&self.tag_new(32)),
+                    Some(device_response.body().clone()),
+                )
+                .await;
+            }
+            other => {
+                self.store.bye(&gb_code, stream_id);
+                let status_code = match other {
+                    Some(device_response) => device_response.status_code().clone(),
+                    None => rsip::StatusCode::RequestTimeout,
+                };
+                self.send_invite_reply(
+                    upstream_addr,
+                    upstream_tcp_stream,
+                    &request,
+                    status_code,
+                    Some(&self.tag_new(32)),
+                    None,
+                )
+                .await;
+            }
+        }
+    }
+
+    // 设备响应语音广播通知发来的 INVITE, 应答本节点的发送地址
+    async fn on_req_invite_broadcast(
+        &self,
+        device_addr: std::net::SocketAddr,
+        tcp_stream: Option<std::sync::Arc<tokio::sync::Mutex<tokio::net::tcp::OwnedWriteHalf>>>,
+        request: rsip::Request,
+        peer_id: String,
+        key: (String, String),
     ) {
+        self.send_invite_reply(
+            device_addr,
+            tcp_stream.clone(),
+            &request,
+            rsip::StatusCode::Trying,
+            None,
+            None,
+        )
+        .await;
+
+        // 设备 INVITE 中的 SDP 是其收音频的地址
+        let offer = SdpAnswer::parse(&self.decode_body(request.body()));
+        let Some(payload_type) = offer
+            .payloads
+            .iter()
+            .copied()
+            .find(|payload_type| matches!(payload_type, 8 | 0))
+        else {
+            self.send_invite_reply(
+                device_addr,
+                tcp_stream,
+                &request,
+                rsip::StatusCode::NotAcceptableHere,
+                Some(&self.tag_new(32)),
+                None,
+            )
+            .await;
+            self.broadcast_fail(&key, (488, String::from("audio codec unsupported")));
+            return;
+        };
+        let Some((node, media)) = self
+            .broadcasts
+            .get(&key)
+            .map(|session| (session.node, session.media.clone()))
+        else {
+            return;
+        };
+
+        // TCP 时由节点主动连接设备
+        let udp = !offer.transport.to_uppercase().starts_with("TCP");
+        let Some(local_port) = self
+            .media
+            .start_send_rtp(
+                node,
+                &media::SendRtp {
+                    app: media::BROADCAST_APP.to_string(),
+                    stream: media.stream.clone(),
+                    ssrc: offer.ssrc.clone(),
+                    dst_ip: offer.media_ip.clone(),
+                    dst_port: offer.media_port,
+                    payload_type,
+                    udp,
+                },
+            )
+            .await
+        else {
+            self.send_invite_reply(
+                device_addr,
+                tcp_stream,
+                &request,
+                rsip::StatusCode::ServerInternalError,
+                Some(&self.tag_new(32)),
+                None,
+            )
+            .await;
+            self.broadcast_fail(&key, (503, String::from("media send failed")));
+            return;
+        };
+
+        let to_tag = self.tag_new(32);
+        let waiter = match self.broadcasts.get_mut(&key) {
+            Some(mut session) => {
+                session.caller_id = request.call_id_header().unwrap().value().to_string();
+                session.peer_id = peer_id;
+                session.from_tag = request
+                    .from_header()
+                    .ok()
+                    .and_then(|from| from.tag().ok().flatten())
+                    .map(|tag| tag.to_string())
+                    .unwrap_or_default();
+                session.to_tag = to_tag.clone();
+                session.media.local_port = local_port;
+                session.media.device_ip = offer.media_ip.clone();
+                session.media.device_port = offer.media_port;
+                session.media.ssrc = offer.ssrc.clone();
+                session.media.payload_type = payload_type;
+                session.waiter.take()
+            }
+            None => {
+                // 等待期间已被停止
+                self.media.stop_send_rtp(&media.stream).await;
+                self.send_invite_reply(
+                    device_addr,
+                    tcp_stream,
+                    &request,
+                    rsip::StatusCode::RequestTerminated,
+                    Some(&to_tag),
+                    None,
+                )
+                .await;
+                return;
+            }
+        };
+
+        let sdp = sdp::generate_audio_sdp(
+            &media.media_ip,
+            local_port,
+            &self.id,
+            if udp { "" } else { "active" },
+            SdpSessionType::Play,
+            &[payload_type],
+            &offer.ssrc,
+        );
+        self.send_invite_reply(
+            device_addr,
+            tcp_stream,
+            &request,
+            rsip::StatusCode::OK,
+            Some(&to_tag),
+            Some(sdp.into_bytes()),
+        )
+        .await;
+        if let Some(waiter) = waiter {
+            let _ = waiter.send(Ok(()));
+        }
+    }
+
+    // 通道编码可能是设备自身, 也可能是设备目录中的通道
+    fn cascade_find_device(&self, channel_id: &str) -> Option<String> {
+        if self.store.find_device_by_gb_code(channel_id).is_some() {
+            return Some(channel_id.to_string());
+        }
+        self.store.find_gb_code_by_channel_id(channel_id)
+    }
+
+    // 应答收到的 INVITE, 100 以外的应答在 To 中带上本端 tag, 200 时携带 SDP
+    async fn send_invite_reply(
+        &self,
+        upstream_addr: std::net::SocketAddr,
+        upstream_tcp_stream: Option<
+            std::sync::Arc<tokio::sync::Mutex<tokio::net::tcp::OwnedWriteHalf>>,
+        >,
+        request: &rsip::Request,
+        status_code: rsip::StatusCode,
+        to_tag: Option<&str>,
+        sdp: Option<Vec<u8>>,
+    ) -> bool {
+        let mut headers: rsip::Headers = Default::default();
+        headers.push(request.via_header().unwrap().clone().into());
+        headers.push(request.from_header().unwrap().clone().into());
+        match to_tag {
+            Some(to_tag) if status_code.code() > 100 => {
+                headers.push(
+                    request
+                        .to_header()
+                        .unwrap()
+                        .typed()
+                        .unwrap()
+                        .with_tag(to_tag.into())
+                        .into(),
+                );
+            }
+            _ => {
+                headers.push(self.to_old(request.to_header().unwrap()).into());
+            }
+        }
+        headers.push(request.call_id_header().unwrap().clone().into());
+        headers.push(request.cseq_header().unwrap().clone().into());
+
+        let Some(bin_body) = sdp else {
+            headers.push(rsip::Header::ContentLength(Default::default()));
+            let response = rsip::Response {
+                status_code,
+                headers,
+                version: rsip::Version::V2,
+                body: Default::default(),
+            };
+            return self
+                .socket_send_response(upstream_addr, upstream_tcp_stream, response)
+                .await;
+        };
+
+        let str_body = self.decode_body(&bin_body);
+        headers.push(
+            rsip::headers::Contact::new(format!("<sip:{}@{}:{}>", self.id, self.ip, self.port))
+                .into(),
+        );
+        headers.push(rsip::headers::ContentType::from("APPLICATION/SDP").into());
+        headers.push(rsip::headers::ContentLength::from(bin_body.len() as u32).into());
+        let response = rsip::Response {
+            status_code,
+            headers,
+            version: rsip::Version::V2,
+            body: Default::default(),
+        };
+        self.socket_send_response_with_body(
+            upstream_addr,
+            upstream_tcp_stream,
+            response,
+            bin_body,
+            str_body,
+        )
+        .await
     }
 
     pub async fn on_rsp_invite(
@@ -24,23 +395,41 @@ impl SipHandler {
         tcp_stream: Option<std::sync::Arc<tokio::sync::Mutex<tokio::net::tcp::OwnedWriteHalf>>>,
         response: rsip::Response,
     ) {
-        if &rsip::StatusCode::Trying == response.status_code() {
-            self.on_rsp_invite_100(device_addr, tcp_stream, response)
+        let code = response.status_code().code();
+        if code < 200 {
+            self.on_rsp_invite_1xx(device_addr, tcp_stream, response)
                 .await;
-        } else if &rsip::StatusCode::OK == response.status_code() {
-            self.on_rsp_invite_200(device_addr, tcp_stream, response)
+            return;
+        } else if code < 300 {
+            self.on_rsp_invite_200(device_addr, tcp_stream, response.clone())
                 .await;
         } else {
-            tracing::warn!("unexpected response, method: {}", response.status_code());
+            self.on_rsp_invite_failure(device_addr, tcp_stream, &response)
+                .await;
+        }
+
+        // 唤醒等待 INVITE 结果的事务
+        if let Ok(call_id) = response.call_id_header() {
+            let call_id = call_id.value().to_string();
+            self.transaction_end(&call_id, response);
         }
     }
 
-    pub async fn on_rsp_invite_100(
+    // 100 Trying/180 Ringing/183 Session Progress: 设备仍在处理, 继续等待最终应答
+    pub async fn on_rsp_invite_1xx(
         &self,
         _device_addr: std::net::SocketAddr,
         _tcp_stream: Option<std::sync::Arc<tokio::sync::Mutex<tokio::net::tcp::OwnedWriteHalf>>>,
-        _response: rsip::Response,
+        response: rsip::Response,
     ) {
+        tracing::debug!(
+            "invite in progress, status: {}, call_id: {}",
+            response.status_code(),
+            response
+                .call_id_header()
+                .map(|call_id| call_id.value().to_string())
+                .unwrap_or_default()
+        );
     }
 
     pub async fn on_rsp_invite_200(
@@ -49,71 +438,109 @@ impl SipHandler {
         tcp_stream: Option<std::sync::Arc<tokio::sync::Mutex<tokio::net::tcp::OwnedWriteHalf>>>,
         response: rsip::Response,
     ) {
-        // decode body
-        let sdp_msg = self.decode_body(response.body());
+        let Some(ack) = self.invite_ack(&response) else {
+            tracing::error!("invalid invite response: {}", device_addr);
+            return;
+        };
+        let call_id = response.call_id_header().unwrap().value().to_string();
+        let from_tag = response
+            .from_header()
+            .ok()
+            .and_then(|from| from.tag().ok().flatten())
+            .map(|tag| tag.to_string())
+            .unwrap_or_default();
+        let to_tag = response
+            .to_header()
+            .ok()
+            .and_then(|to| to.tag().ok().flatten())
+            .map(|tag| tag.to_string())
+            .unwrap_or_default();
+        self.socket_send_request(device_addr, tcp_stream.clone(), ack)
+            .await;
+
+        // 点播方已超时放弃或已清理, 确认后立即挂断, 避免设备持续推流
+        if self.store.find_gb_code_by_caller_id(&call_id).is_none() {
+            tracing::warn!("invite answered after abandoned, call_id: {}", call_id);
+            let gb_code = self.invite_gb_code(&response);
+            self.send_bye(
+                device_addr,
+                tcp_stream,
+                SipTransaction {
+                    caller_id: call_id,
+                    from_tag,
+                    to_tag,
+                    branch: format!("z9hG4bK{}", self.tag_new(16)),
+                },
+                &gb_code,
+            )
+            .await;
+            return;
+        }
+        self.store.update_stream_tag_info(&from_tag, &to_tag);
 
-        match sdp_rs::SessionDescription::from_str(&sdp_msg) {
+        // tcp 主动模式: 按设备应答的地址去拉流
+        match sdp_rs::SessionDescription::from_str(&self.decode_body(response.body())) {
             Err(e) => {
                 tracing::error!("sdp_rs::SessionDescription::from_str error, e: {:?}", e);
             }
-            Ok(_) => {
-                if let Some(gb_code) = self
-                    .store
-                    .find_gb_code_by_caller_id(response.call_id_header().unwrap().clone().value())
-                {
-                    let mut headers: rsip::Headers = Default::default();
-                    let mut respones_clone = response.clone();
-                    let from_tag = response
-                        .from_header()
-                        .unwrap()
-                        .clone()
-                        .tag()
-                        .unwrap()
-                        .unwrap()
-                        .to_string();
-                    let to_tag = response
-                        .to_header()
-                        .unwrap()
-                        .clone()
-                        .tag()
-                        .unwrap()
-                        .unwrap()
-                        .to_string();
-                    self.store.update_stream_tag_info(&from_tag, &to_tag);
-                    headers.push(response.via_header().unwrap().clone().into());
-                    headers.push(response.from_header().unwrap().clone().into());
-                    headers.push(self.to_old(response.to_header().unwrap()).into());
-                    headers.push(response.call_id_header().unwrap().clone().into());
-                    headers.push(
-                        respones_clone
-                            .cseq_header_mut()
-                            .unwrap()
-                            .mut_method(rsip::Method::Ack)
-                            .unwrap()
-                            .clone()
-                            .into(),
-                    );
-                    headers.push(rsip::Header::ContentLength(Default::default()));
-
-                    let request = rsip::Request {
-                        method: rsip::Method::Ack,
-                        uri: rsip::Uri {
-                            scheme: Some(rsip::Scheme::Sip),
-                            auth: Some((gb_code.clone(), Option::<String>::None).into()),
-                            host_with_port: rsip::Domain::from(self.domain.clone()).into(),
-                            ..Default::default()
-                        },
-                        headers,
-                        version: rsip::Version::V2,
-                        body: Default::default(),
-                    };
-
-                    self.socket_send_request(device_addr, tcp_stream, request)
-                        .await;
-                } else {
-                    tracing::error!("device not found: {}", device_addr);
-                }
+            Ok(sdp) => {
+                self.media.on_answer(&call_id, &sdp).await;
             }
         }
     }
+
+    // 3xx~6xx: 回 ACK 结束 INVITE 事务, 流记录由等待方清理
+    pub async fn on_rsp_invite_failure(
+        &self,
+        device_addr: std::net::SocketAddr,
+        tcp_stream: Option<std::sync::Arc<tokio::sync::Mutex<tokio::net::tcp::OwnedWriteHalf>>>,
+        response: &rsip::Response,
+    ) {
+        tracing::warn!(
+            "invite rejected, status: {}, call_id: {}",
+            response.status_code(),
+            response
+                .call_id_header()
+                .map(|call_id| call_id.value().to_string())
+                .unwrap_or_default()
+        );
+        if let Some(ack) = self.invite_ack(response) {
+            self.socket_send_request(device_addr, tcp_stream, ack).await;
+        }
+    }
+
+    // ACK 沿用 INVITE 的 Via/From/Call-ID/CSeq 序号, To 取应答中带 tag 的版本
+    fn invite_ack(&self, response: &rsip::Response) -> Option<rsip::Request> {
+        let to = response.to_header().ok()?;
+        let mut cseq = response.cseq_header().ok()?.typed().ok()?;
+        cseq.method = rsip::Method::Ack;
+
+        let mut headers: rsip::Headers = Default::default();
+        headers.push(response.via_header().ok()?.clone().into());
+        headers.push(rsip::headers::MaxForwards::default().into());
+        headers.push(response.from_header().ok()?.clone().into());
+        headers.push(to.clone().into());
+        headers.push(response.call_id_header().ok()?.clone().into());
+        headers.push(cseq.into());
+        headers.push(rsip::Header::ContentLength(Default::default()));
+
+        Some(rsip::Request {
+            method: rsip::Method::Ack,
+            uri: self.invite_uri(&self.invite_gb_code(response)),
+            headers,
+            version: rsip::Version::V2,
+            body: Default::default(),
+        })
+    }
+
+    // INVITE 的 To 与 Request-URI 相同, 均为 sip:gb_code@domain
+    fn invite_gb_code(&self, response: &rsip::Response) -> String {
+        response
+            .to_header()
+            .ok()
+            .and_then(|to| to.uri().ok())
+            .and_then(|uri| uri.auth)
+            .map(|auth| auth.user)
+            .unwrap_or_default()
+    }
 }
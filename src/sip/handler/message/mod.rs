@@ -1,9 +1,16 @@
+pub mod on_alarm;
+pub mod on_broadcast;
 pub mod on_catalog;
+pub mod on_catalog_query;
 pub mod on_device_info;
 pub mod on_device_status;
 pub mod on_keep_alive;
+pub mod on_mobile_position;
+pub mod on_preset_query;
+pub mod on_record_info;
 
 use regex::Regex;
+use rsip::{self, prelude::HeadersExt, prelude::UntypedHeader};
 
 use super::SipHandler;
 
@@ -24,12 +31,45 @@ impl SipHandler {
                 self.on_keep_alive(device_addr, tcp_stream, request, msg)
                     .await;
             }
+            "DeviceInfo" => {
+                self.on_device_info(device_addr, tcp_stream, request, msg)
+                    .await;
+            }
             "DeviceStatus" => {
                 self.on_device_status(device_addr, tcp_stream, request, msg)
                     .await;
             }
             "Catalog" => {
-                self.on_catalog(device_addr, tcp_stream, request, msg).await;
+                // 上级平台发来的是查询, 下级设备发来的是应答或变化通知
+                if msg.contains("<Query>") {
+                    self.on_catalog_query(device_addr, tcp_stream, request, msg)
+                        .await;
+                } else if msg.contains("<Notify>") {
+                    // 部分设备以 MESSAGE 推送目录变化
+                    self.on_catalog_notify(device_addr, tcp_stream, request, msg)
+                        .await;
+                } else {
+                    self.on_catalog(device_addr, tcp_stream, request, msg).await;
+                }
+            }
+            "PresetQuery" => {
+                self.on_preset_query(device_addr, tcp_stream, request, msg)
+                    .await;
+            }
+            "Alarm" => {
+                self.on_alarm(device_addr, tcp_stream, request, msg).await;
+            }
+            "MobilePosition" => {
+                self.on_mobile_position(device_addr, tcp_stream, request, msg)
+                    .await;
+            }
+            "Broadcast" => {
+                self.on_broadcast(device_addr, tcp_stream, request, msg)
+                    .await;
+            }
+            "RecordInfo" => {
+                self.on_record_info(device_addr, tcp_stream, request, msg)
+                    .await;
             }
             _ => {}
         }
@@ -39,11 +79,19 @@ impl SipHandler {
         &self,
         _device_addr: std::net::SocketAddr,
         _tcp_stream: Option<std::sync::Arc<tokio::sync::Mutex<tokio::net::tcp::OwnedWriteHalf>>>,
-        _response: rsip::Response,
+        response: rsip::Response,
     ) {
+        if response.status_code().code() < 200 {
+            return;
+        }
+
+        if let Ok(call_id) = response.call_id_header() {
+            let call_id = call_id.value().to_string();
+            self.transaction_end(&call_id, response);
+        }
     }
 
-    fn extract_cmd_type(&self, body: &str) -> String {
+    pub fn extract_cmd_type(&self, body: &str) -> String {
         let regex = Regex::new(r"<CmdType>(\w+)</CmdType>").unwrap();
         if let Some(matches) = regex.captures(body) {
             if let Some(s) = matches.get(1).map(|m| m.as_str()) {
@@ -0,0 +1,69 @@
+use rsip::{self, prelude::HeadersExt};
+
+use super::SipHandler;
+
+use crate::{sip::message::Alarm, store::AlarmRecord};
+
+impl SipHandler {
+    // 报警通知, MESSAGE 与订阅后的 NOTIFY 共用, 记录后回复 200
+    pub async fn on_alarm(
+        &self,
+        device_addr: std::net::SocketAddr,
+        tcp_stream: Option<std::sync::Arc<tokio::sync::Mutex<tokio::net::tcp::OwnedWriteHalf>>>,
+        request: rsip::Request,
+        msg: String,
+    ) {
+        let data = Alarm::deserialize_from_xml(msg);
+        let gb_code = request
+            .from_header()
+            .unwrap()
+            .uri()
+            .unwrap()
+            .auth
+            .unwrap()
+            .to_string();
+        let mut record = AlarmRecord::from(&data);
+        record.gb_code = gb_code;
+        record.ts = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .expect("Time went backwards")
+            .as_secs() as u32;
+        tracing::warn!(
+            "alarm, gb_code: {}, device_id: {}, priority: {}, method: {}, time: {}, description: {}",
+            record.gb_code,
+            record.device_id,
+            record.priority,
+            record.method,
+            record.alarm_time,
+            record.description
+        );
+        self.store.save_alarm(&record);
+
+        self.reply_ok(device_addr, tcp_stream, &request).await;
+    }
+
+    pub async fn reply_ok(
+        &self,
+        device_addr: std::net::SocketAddr,
+        tcp_stream: Option<std::sync::Arc<tokio::sync::Mutex<tokio::net::tcp::OwnedWriteHalf>>>,
+        request: &rsip::Request,
+    ) {
+        let mut headers: rsip::Headers = Default::default();
+        headers.push(request.via_header().unwrap().clone().into());
+        headers.push(request.from_header().unwrap().clone().into());
+        headers.push(self.to_old(request.to_header().unwrap()).into());
+        headers.push(request.call_id_header().unwrap().clone().into());
+        headers.push(request.cseq_header().unwrap().clone().into());
+        headers.push(rsip::Header::ContentLength(Default::default()));
+
+        let response = rsip::Response {
+            status_code: rsip::StatusCode::OK,
+            headers,
+            version: rsip::Version::V2,
+            body: Default::default(),
+        };
+
+        self.socket_send_response(device_addr, tcp_stream, response)
+            .await;
+    }
+}
@@ -0,0 +1,28 @@
+use super::SipHandler;
+use crate::sip::message::BroadcastResponse;
+
+impl SipHandler {
+    // 设备对语音广播通知的应答, 成功时随后会发来 INVITE
+    pub async fn on_broadcast(
+        &self,
+        device_addr: std::net::SocketAddr,
+        tcp_stream: Option<std::sync::Arc<tokio::sync::Mutex<tokio::net::tcp::OwnedWriteHalf>>>,
+        request: rsip::Request,
+        msg: String,
+    ) {
+        let data = BroadcastResponse::deserialize_from_xml(msg);
+        tracing::debug!("on_broadcast: {:?}", data);
+        self.reply_ok(device_addr, tcp_stream, &request).await;
+
+        if data.result != "OK" {
+            tracing::warn!(
+                "broadcast rejected, device_id: {}, result: {}",
+                data.device_id,
+                data.result
+            );
+            if let Some(key) = self.broadcast_find(&data.device_id) {
+                self.broadcast_fail(&key, (500, format!("device rejected: {}", data.result)));
+            }
+        }
+    }
+}
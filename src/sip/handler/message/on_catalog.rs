@@ -1,5 +1,8 @@
 use super::SipHandler;
-use crate::sip::message::Catalog;
+use crate::sip::handler::CatalogChange;
+use crate::sip::message::{Catalog, CatalogEvent, DeviceList};
+use crate::sip::utils::query::QueryResponse;
+use crate::store::CatalogSyncStatus;
 use rsip::{self, prelude::HeadersExt};
 
 impl SipHandler {
@@ -12,10 +15,6 @@ impl SipHandler {
     ) {
         let data = Catalog::deserialize_from_xml(msg);
         tracing::debug!("on_catalog: {:?}", data);
-        if data.sn > 0 {
-            self.store.set_global_sn(data.sn);
-        }
-
         let gb_code = request
             .from_header()
             .unwrap()
@@ -25,8 +24,9 @@ impl SipHandler {
             .unwrap()
             .to_string();
 
-        // 存储 catalog 信息，例如调用 store 中的某个方法
-        self.store.save_catalog(&gb_code, data);
+        // 存储 catalog 信息, 收齐 SumNum 条后整体替换设备目录
+        let (device_id, sn) = (data.device_id.clone(), data.sn);
+        let state = self.store.save_catalog(&gb_code, data);
 
         let mut headers: rsip::Headers = Default::default();
         headers.push(request.via_header().unwrap().clone().into());
@@ -46,5 +46,82 @@ impl SipHandler {
         let tcp_stream_ref = &tcp_stream;
         self.socket_send_response(device_addr, tcp_stream_ref.clone(), response)
             .await;
+
+        if state.status == CatalogSyncStatus::Complete {
+            if let Some(items) = self.store.find_catalog(&gb_code) {
+                let catalog = Catalog {
+                    cmd_type: String::from("Catalog"),
+                    sn,
+                    device_id: device_id.clone(),
+                    sum_num: state.sum_num,
+                    device_list: DeviceList {
+                        num: items.len() as u32,
+                        items,
+                    },
+                };
+                self.query_end(&device_id, "Catalog", sn, QueryResponse::Catalog(catalog));
+            }
+        }
+    }
+
+    // 目录订阅通知, 逐条增量更新通道列表, 不带 Event 的条目按 UPDATE 处理
+    pub async fn on_catalog_notify(
+        &self,
+        device_addr: std::net::SocketAddr,
+        tcp_stream: Option<std::sync::Arc<tokio::sync::Mutex<tokio::net::tcp::OwnedWriteHalf>>>,
+        request: rsip::Request,
+        msg: String,
+    ) {
+        let data = Catalog::deserialize_from_xml(msg);
+        tracing::debug!("on_catalog_notify: {:?}", data);
+        let gb_code = request
+            .from_header()
+            .unwrap()
+            .uri()
+            .unwrap()
+            .auth
+            .unwrap()
+            .to_string();
+        let ts = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .expect("Time went backwards")
+            .as_secs() as u32;
+
+        for item in &data.device_list.items {
+            let event = match item.event.as_deref() {
+                None => CatalogEvent::Update,
+                Some(s) => match CatalogEvent::parse(s) {
+                    Some(event) => event,
+                    None => {
+                        tracing::warn!(
+                            "unknown catalog event, gb_code: {}, channel_id: {}, event: {}",
+                            gb_code,
+                            item.device_id,
+                            s
+                        );
+                        continue;
+                    }
+                },
+            };
+            if !self.store.apply_catalog_event(&gb_code, event, item) {
+                continue;
+            }
+
+            tracing::info!(
+                "catalog changed, gb_code: {}, channel_id: {}, event: {}",
+                gb_code,
+                item.device_id,
+                event
+            );
+            // 没有订阅者时发送失败, 忽略即可
+            let _ = self.catalog_events.send(CatalogChange {
+                gb_code: gb_code.clone(),
+                channel_id: item.device_id.clone(),
+                event,
+                ts,
+            });
+        }
+
+        self.reply_ok(device_addr, tcp_stream, &request).await;
     }
 }
@@ -0,0 +1,94 @@
+use rsip::{self, prelude::HeadersExt};
+
+use super::SipHandler;
+use crate::sip::message::{CatalogQuery, Device};
+
+// 单个 MESSAGE 携带的目录条目数, 避免超出 UDP 报文长度
+static CATALOG_ITEMS_PER_MESSAGE: usize = 4;
+
+impl SipHandler {
+    // 上级平台查询本平台目录
+    pub async fn on_catalog_query(
+        &self,
+        device_addr: std::net::SocketAddr,
+        tcp_stream: Option<std::sync::Arc<tokio::sync::Mutex<tokio::net::tcp::OwnedWriteHalf>>>,
+        request: rsip::Request,
+        msg: String,
+    ) {
+        let data = CatalogQuery::deserialize_from_xml(msg);
+        tracing::debug!("on_catalog_query: {:?}", data);
+
+        let upstream_id = request
+            .from_header()
+            .unwrap()
+            .uri()
+            .unwrap()
+            .auth
+            .unwrap()
+            .to_string();
+
+        let mut headers: rsip::Headers = Default::default();
+        headers.push(request.via_header().unwrap().clone().into());
+        headers.push(request.from_header().unwrap().clone().into());
+        headers.push(self.to_old(request.to_header().unwrap()).into());
+        headers.push(request.call_id_header().unwrap().clone().into());
+        headers.push(request.cseq_header().unwrap().clone().into());
+        headers.push(rsip::Header::ContentLength(Default::default()));
+
+        let response = rsip::Response {
+            status_code: rsip::StatusCode::OK,
+            headers,
+            version: rsip::Version::V2,
+            body: Default::default(),
+        };
+
+        self.socket_send_response(device_addr, tcp_stream, response)
+            .await;
+
+        let Some(platform) = self.upstream_find(&upstream_id) else {
+            tracing::warn!("catalog query from unknown platform: {}", upstream_id);
+            return;
+        };
+
+        let items = self.cascade_catalog_items();
+        let sum_num = items.len() as u32;
+        let branch = self.branch_get(request.via_header().unwrap());
+        if items.is_empty() {
+            self.send_catalog_response(platform, device_addr, &branch, data.sn, 0, vec![])
+                .await;
+            return;
+        }
+
+        for chunk in items.chunks(CATALOG_ITEMS_PER_MESSAGE) {
+            self.send_catalog_response(
+                platform,
+                device_addr,
+                &branch,
+                data.sn,
+                sum_num,
+                chunk.to_vec(),
+            )
+            .await;
+        }
+    }
+
+    // 汇总所有下级设备的目录, 未上报目录的设备以自身作为通道
+    fn cascade_catalog_items(&self) -> Vec<Device> {
+        let mut items = vec![];
+        for device in self.store.list_devices() {
+            match self.store.find_catalog(&device.gb_code) {
+                Some(channels) if !channels.is_empty() => items.extend(channels),
+                _ => items.push(Device {
+                    device_id: device.gb_code.clone(),
+                    name: device.gb_code.clone(),
+                    manufacturer: device.manufacturer.clone(),
+                    model: device.model.clone(),
+                    parent_id: self.id.clone(),
+                    status: Some(String::from(if device.online { "ON" } else { "OFF" })),
+                    ..Default::default()
+                }),
+            }
+        }
+        items
+    }
+}
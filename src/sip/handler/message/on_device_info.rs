@@ -3,6 +3,7 @@ use rsip::{self, prelude::HeadersExt};
 use super::SipHandler;
 
 use crate::sip::message::DeviceInfo;
+use crate::sip::utils::query::QueryResponse;
 
 impl SipHandler {
     pub async fn on_device_info(
@@ -13,10 +14,17 @@ impl SipHandler {
         msg: String,
     ) {
         let data: DeviceInfo = DeviceInfo::deserialize_from_xml(msg);
-        tracing::debug!("on_catalog: {:?}", data);
-        if data.sn > 0 {
-            self.store.set_global_sn(data.sn);
-        }
+        tracing::debug!("on_device_info: {:?}", data);
+        // 存储只保存设备自身的应答, 通道的应答只用于查询回调
+        let gb_code = request
+            .from_header()
+            .unwrap()
+            .uri()
+            .unwrap()
+            .auth
+            .unwrap()
+            .to_string();
+        self.store.save_device_info(&gb_code, &data);
 
         let mut headers: rsip::Headers = Default::default();
         headers.push(request.via_header().unwrap().clone().into());
@@ -35,5 +43,13 @@ impl SipHandler {
 
         self.socket_send_response(device_addr, tcp_stream.as_ref().cloned(), response)
             .await;
+
+        let (device_id, sn) = (data.device_id.clone(), data.sn);
+        self.query_end(
+            &device_id,
+            "DeviceInfo",
+            sn,
+            QueryResponse::DeviceInfo(data),
+        );
     }
 }
@@ -3,6 +3,7 @@ use rsip::{self, prelude::HeadersExt};
 use super::SipHandler;
 
 use crate::sip::message::DeviceStatus;
+use crate::sip::utils::query::QueryResponse;
 
 impl SipHandler {
     pub async fn on_device_status(
@@ -13,9 +14,16 @@ impl SipHandler {
         msg: String,
     ) {
         let data: DeviceStatus = DeviceStatus::deserialize_from_xml(msg);
-        if data.sn > 0 {
-            self.store.set_global_sn(data.sn);
-        }
+        // 存储只保存设备自身的应答, 通道的应答只用于查询回调
+        let gb_code = request
+            .from_header()
+            .unwrap()
+            .uri()
+            .unwrap()
+            .auth
+            .unwrap()
+            .to_string();
+        self.store.save_device_status(&gb_code, &data);
 
         let mut headers: rsip::Headers = Default::default();
         headers.push(request.via_header().unwrap().clone().into());
@@ -34,5 +42,13 @@ impl SipHandler {
 
         self.socket_send_response(device_addr, tcp_stream.as_ref().cloned(), response)
             .await;
+
+        let (device_id, sn) = (data.device_id.clone(), data.sn);
+        self.query_end(
+            &device_id,
+            "DeviceStatus",
+            sn,
+            QueryResponse::DeviceStatus(data),
+        );
     }
 }
@@ -13,10 +13,7 @@ impl SipHandler {
         msg: String,
     ) {
         let data = KeepAlive::deserialize_from_xml(msg);
-        if data.sn > 0 {
-            self.store.set_global_sn(data.sn);
-        }
-
+        tracing::debug!("on_keep_alive: {:?}", data);
         let gb_code = request
             .from_header()
             .unwrap()
@@ -0,0 +1,38 @@
+use rsip::{self, prelude::HeadersExt};
+
+use super::SipHandler;
+
+use crate::{sip::message::MobilePosition, store::TrackPoint};
+
+impl SipHandler {
+    // 移动位置上报, 追加到设备轨迹后回复 200
+    pub async fn on_mobile_position(
+        &self,
+        device_addr: std::net::SocketAddr,
+        tcp_stream: Option<std::sync::Arc<tokio::sync::Mutex<tokio::net::tcp::OwnedWriteHalf>>>,
+        request: rsip::Request,
+        msg: String,
+    ) {
+        let data = MobilePosition::deserialize_from_xml(msg);
+        let gb_code = request
+            .from_header()
+            .unwrap()
+            .uri()
+            .unwrap()
+            .auth
+            .unwrap()
+            .to_string();
+        // 解析失败时坐标为 0, 不写入轨迹
+        if data.longitude != 0.0 || data.latitude != 0.0 {
+            let mut point = TrackPoint::from(&data);
+            point.gb_code = gb_code;
+            point.ts = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .expect("Time went backwards")
+                .as_secs() as u32;
+            self.store.save_track_point(&point);
+        }
+
+        self.reply_ok(device_addr, tcp_stream, &request).await;
+    }
+}
@@ -0,0 +1,189 @@
+use dashmap::DashMap;
+use rsip::{self, prelude::HeadersExt};
+
+use super::SipHandler;
+
+use crate::sip::message::RecordInfo;
+use crate::sip::utils::query::QueryResponse;
+
+impl SipHandler {
+    pub async fn on_record_info(
+        &self,
+        device_addr: std::net::SocketAddr,
+        tcp_stream: Option<std::sync::Arc<tokio::sync::Mutex<tokio::net::tcp::OwnedWriteHalf>>>,
+        request: rsip::Request,
+        msg: String,
+    ) {
+        let data = RecordInfo::deserialize_from_xml(msg);
+        tracing::debug!("on_record_info: {:?}", data);
+        let mut headers: rsip::Headers = Default::default();
+        headers.push(request.via_header().unwrap().clone().into());
+        headers.push(request.from_header().unwrap().clone().into());
+        headers.push(self.to_old(request.to_header().unwrap()).into());
+        headers.push(request.call_id_header().unwrap().clone().into());
+        headers.push(request.cseq_header().unwrap().clone().into());
+        headers.push(rsip::Header::ContentLength(Default::default()));
+
+        let response = rsip::Response {
+            status_code: rsip::StatusCode::OK,
+            headers,
+            version: rsip::Version::V2,
+            body: Default::default(),
+        };
+
+        self.socket_send_response(device_addr, tcp_stream.as_ref().cloned(), response)
+            .await;
+
+        // 按 SN 拼接分包应答, 收齐 SumNum 条后返回给等待方
+        let key = (data.device_id.clone(), data.sn);
+        if !self
+            .queries
+            .contains_key(&(key.0.clone(), String::from("RecordInfo"), key.1))
+        {
+            self.record_infos.remove(&key);
+            return;
+        }
+        if let Some(record_info) = merge_record_info(&self.record_infos, data) {
+            self.query_end(
+                &key.0,
+                "RecordInfo",
+                key.1,
+                QueryResponse::RecordInfo(record_info),
+            );
+        }
+    }
+}
+
+// 分包可能乱序或重发: 按录像条目去重, 收齐后按开始时间排序并移出拼接表
+fn merge_record_info(
+    record_infos: &DashMap<(String, u32), RecordInfo>,
+    data: RecordInfo,
+) -> Option<RecordInfo> {
+    let key = (data.device_id.clone(), data.sn);
+    let complete = {
+        let mut entry = record_infos
+            .entry(key.clone())
+            .or_insert_with(|| RecordInfo {
+                cmd_type: data.cmd_type.clone(),
+                sn: data.sn,
+                device_id: data.device_id.clone(),
+                name: data.name.clone(),
+                sum_num: data.sum_num,
+                record_list: Default::default(),
+            });
+        for item in data.record_list.items {
+            if !entry.record_list.items.iter().any(|exist| {
+                exist.device_id == item.device_id
+                    && exist.start_time == item.start_time
+                    && exist.end_time == item.end_time
+                    && exist.file_path == item.file_path
+            }) {
+                entry.record_list.items.push(item);
+            }
+        }
+        entry.record_list.num = entry.record_list.items.len() as u32;
+        entry.record_list.num >= entry.sum_num
+    };
+    if !complete {
+        return None;
+    }
+
+    let (_, mut record_info) = record_infos.remove(&key)?;
+    record_info
+        .record_list
+        .items
+        .sort_by(|a, b| a.start_time.cmp(&b.start_time));
+    Some(record_info)
+}
+
+#[cfg(test)]
+mod tests {
+    use dashmap::DashMap;
+
+    use super::merge_record_info;
+    use crate::sip::message::{RecordInfo, RecordItem, RecordList};
+
+    const DEVICE_ID: &str = "34020000001320000001";
+
+    fn packet(sn: u32, sum_num: u32, hours: &[u32]) -> RecordInfo {
+        RecordInfo {
+            cmd_type: String::from("RecordInfo"),
+            sn,
+            device_id: String::from(DEVICE_ID),
+            sum_num,
+            record_list: RecordList {
+                num: hours.len() as u32,
+                items: hours
+                    .iter()
+                    .map(|hour| RecordItem {
+                        device_id: String::from(DEVICE_ID),
+                        start_time: format!("2024-01-01T{:02}:00:00", hour),
+                        end_time: format!("2024-01-01T{:02}:59:59", hour),
+                        ..Default::default()
+                    })
+                    .collect(),
+            },
+            ..Default::default()
+        }
+    }
+
+    fn start_hours(record_info: &RecordInfo) -> Vec<String> {
+        record_info
+            .record_list
+            .items
+            .iter()
+            .map(|item| item.start_time[11..13].to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_merge_multi_packet() {
+        let record_infos = DashMap::new();
+        assert!(merge_record_info(&record_infos, packet(7, 4, &[0, 1])).is_none());
+        let record_info = merge_record_info(&record_infos, packet(7, 4, &[2, 3])).unwrap();
+        assert_eq!(record_info.sum_num, 4);
+        assert_eq!(record_info.record_list.num, 4);
+        assert_eq!(start_hours(&record_info), ["00", "01", "02", "03"]);
+        assert!(record_infos.is_empty());
+    }
+
+    #[test]
+    fn test_merge_out_of_order() {
+        let record_infos = DashMap::new();
+        assert!(merge_record_info(&record_infos, packet(8, 5, &[4])).is_none());
+        assert!(merge_record_info(&record_infos, packet(8, 5, &[0, 1])).is_none());
+        let record_info = merge_record_info(&record_infos, packet(8, 5, &[3, 2])).unwrap();
+        assert_eq!(start_hours(&record_info), ["00", "01", "02", "03", "04"]);
+    }
+
+    #[test]
+    fn test_merge_duplicate_packet() {
+        let record_infos = DashMap::new();
+        assert!(merge_record_info(&record_infos, packet(9, 3, &[0, 1])).is_none());
+        // 重发的分包不应凑满 SumNum
+        assert!(merge_record_info(&record_infos, packet(9, 3, &[0, 1])).is_none());
+        assert_eq!(
+            record_infos
+                .get(&(String::from(DEVICE_ID), 9))
+                .unwrap()
+                .record_list
+                .num,
+            2
+        );
+        let record_info = merge_record_info(&record_infos, packet(9, 3, &[2])).unwrap();
+        assert_eq!(record_info.record_list.num, 3);
+    }
+
+    #[test]
+    fn test_merge_lost_final_packet() {
+        let record_infos = DashMap::new();
+        assert!(merge_record_info(&record_infos, packet(10, 3, &[0])).is_none());
+        assert!(merge_record_info(&record_infos, packet(10, 3, &[1])).is_none());
+        // 末包丢失时保持未完成, 由查询超时清理
+        assert_eq!(record_infos.len(), 1);
+        // 不同 SN 的应答互不影响
+        let record_info = merge_record_info(&record_infos, packet(11, 1, &[5])).unwrap();
+        assert_eq!(record_info.sn, 11);
+        assert_eq!(record_infos.len(), 1);
+    }
+}
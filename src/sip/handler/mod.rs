@@ -1,4 +1,5 @@
 pub mod ack;
+pub mod broadcast;
 pub mod bye;
 pub mod cancel;
 pub mod info;
@@ -10,7 +11,9 @@ pub mod prack;
 pub mod publish;
 pub mod refer;
 pub mod register;
+pub mod stream;
 pub mod subscribe;
+pub mod timeout;
 pub mod update;
 
 use std::net::SocketAddr;
@@ -22,8 +25,16 @@ use rsip::{
     SipMessage,
 };
 
+use dashmap::{DashMap, DashSet};
+
+use crate::media::{recorder::RecordManager, MediaManager};
+use crate::sip::message::{CatalogEvent, RecordInfo, SdpAnswer};
+use crate::sip::utils::query::QueryResponse;
 use crate::store::StoreEngine;
-use crate::utils::{color, config::Config};
+use crate::utils::{
+    color,
+    config::{Config, UpstreamPlatform},
+};
 pub struct SipTransaction {
     pub caller_id: String,
     pub from_tag: String,
@@ -31,6 +42,76 @@ pub struct SipTransaction {
     pub branch: String,
 }
 
+// 上级平台点播会话, 以上级的 Call-ID 关联
+pub struct CascadeSession {
+    pub gb_code: String,
+    pub stream_id: u32,
+}
+
+// 向设备发起的订阅, 以 (gb_code, CmdType) 区分, 到期前按原条件刷新
+pub struct Subscription {
+    pub caller_id: String,
+    pub from_tag: String,
+    pub to_tag: String,
+    pub event: String,
+    pub body: String,
+    pub expires: u32,
+    // 最近一次订阅成功的时间戳
+    pub ts: u32,
+}
+
+// 目录订阅通知引起的通道变化, 推送给 /catalog/events 的订阅者
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct CatalogChange {
+    pub gb_code: String,
+    pub channel_id: String,
+    pub event: CatalogEvent,
+    pub ts: u32,
+}
+
+// 语音广播中设备与媒体节点协商出的参数
+#[derive(Debug, Default, Clone)]
+pub struct BroadcastMedia {
+    // 客户端向该节点推送音频, app 为 media::BROADCAST_APP
+    pub media_ip: String,
+    pub stream: String,
+    // 节点发送音频的本地端口
+    pub local_port: u16,
+    pub device_ip: String,
+    pub device_port: u16,
+    pub ssrc: String,
+    pub payload_type: u8,
+}
+
+// 语音广播会话, 以设备与语音输出通道关联, 设备收到通知后主动 INVITE 本平台
+pub struct BroadcastSession {
+    // 音频源所在的媒体节点
+    pub node: usize,
+    pub media: BroadcastMedia,
+    // 设备发起的对话, 收到 INVITE 前为空
+    pub caller_id: String,
+    pub peer_id: String,
+    pub from_tag: String,
+    pub to_tag: String,
+    // 等待设备 INVITE 的 /broadcast/start 请求
+    pub waiter: Option<tokio::sync::oneshot::Sender<Result<(), (u32, String)>>>,
+}
+
+// 对讲两个方向的媒体地址
+#[derive(Debug, Default, Clone)]
+pub struct TalkMedia {
+    pub media_ip: String,
+    // 设备音频发往节点的收流端口
+    pub recv_port: u16,
+    // 客户端音频推送到节点的 app/stream, 由节点从 send_port 发往设备
+    pub stream: String,
+    pub send_port: u16,
+    pub payload_type: u8,
+}
+
+// 设备对 INVITE 的应答 SDP, 或失败的状态码与原因
+pub type InviteAnswer = Result<SdpAnswer, (u32, String)>;
+
 pub struct SipHandler {
     pub ip: String,
     pub port: u16,
@@ -40,9 +121,31 @@ pub struct SipHandler {
     pub algorithm: rsip::headers::auth::Algorithm,
     pub nonce: String,
     pub realm: String,
+    pub response_timeout_seconds: u32,
+    pub invite_timeout_seconds: u32,
     pub store: Box<dyn StoreEngine>,
+    pub media: MediaManager,
+    pub recorder: RecordManager,
     pub sip_udp_socket: tokio::net::UdpSocket,
     pub sip_tcp_listener: tokio::net::TcpListener,
+    pub transactions: DashMap<String, tokio::sync::oneshot::Sender<rsip::Response>>,
+    pub queries: DashMap<(String, String, u32), tokio::sync::oneshot::Sender<QueryResponse>>,
+    pub record_infos: DashMap<(String, u32), RecordInfo>,
+    pub upstream_platforms: Vec<UpstreamPlatform>,
+    pub cascade_sessions: DashMap<String, CascadeSession>,
+    // stream_name -> 设备对 INVITE 的应答, 发起 INVITE 期间为 None
+    // 共享会话的后续观看者等待同一个应答
+    pub answers: DashMap<String, tokio::sync::watch::Sender<Option<InviteAnswer>>>,
+    pub subscriptions: DashMap<(String, String), Subscription>,
+    pub alarm_subscribe_expires: u32,
+    pub mobile_position_interval: u32,
+    pub mobile_position_subscribe_expires: u32,
+    pub catalog_subscribe_expires: u32,
+    // 已注册、等待订阅服务发起目录订阅的设备
+    pub catalog_subscribe_pending: DashSet<String>,
+    pub catalog_events: tokio::sync::broadcast::Sender<CatalogChange>,
+    // (gb_code, channel_id) -> 广播会话
+    pub broadcasts: DashMap<(String, String), BroadcastSession>,
 }
 
 impl SipHandler {
@@ -61,11 +164,33 @@ impl SipHandler {
             algorithm: rsip::headers::auth::Algorithm::from_str(&config.sip_algorithm).unwrap(),
             nonce: config.sip_nonce.clone(),
             realm: config.sip_realm.clone(),
+            response_timeout_seconds: config.response_timeout_seconds,
+            invite_timeout_seconds: config.invite_timeout_seconds,
             store,
+            media: MediaManager::new(config),
+            recorder: RecordManager::new(config),
             sip_udp_socket,
             sip_tcp_listener,
+            transactions: DashMap::new(),
+            queries: DashMap::new(),
+            record_infos: DashMap::new(),
+            upstream_platforms: config.upstream_platforms.clone(),
+            cascade_sessions: DashMap::new(),
+            answers: DashMap::new(),
+            subscriptions: DashMap::new(),
+            alarm_subscribe_expires: config.alarm_subscribe_expires,
+            mobile_position_interval: config.mobile_position_interval,
+            mobile_position_subscribe_expires: config.mobile_position_subscribe_expires,
+            catalog_subscribe_expires: config.catalog_subscribe_expires,
+            catalog_subscribe_pending: DashSet::new(),
+            catalog_events: tokio::sync::broadcast::channel(1024).0,
+            broadcasts: DashMap::new(),
         }
     }
+
+    pub fn upstream_find(&self, id: &str) -> Option<&UpstreamPlatform> {
+        self.upstream_platforms.iter().find(|p| p.id == id)
+    }
 }
 
 impl SipHandler {
@@ -1,12 +1,30 @@
 use super::SipHandler;
 
 impl SipHandler {
+    // 订阅后设备推送的通知, 按 CmdType 分发, 其余一律回复 200
     pub async fn on_req_notify(
         &self,
-        _device_addr: std::net::SocketAddr,
-        _tcp_stream: Option<std::sync::Arc<tokio::sync::Mutex<tokio::net::tcp::OwnedWriteHalf>>>,
-        _request: rsip::Request,
+        device_addr: std::net::SocketAddr,
+        tcp_stream: Option<std::sync::Arc<tokio::sync::Mutex<tokio::net::tcp::OwnedWriteHalf>>>,
+        request: rsip::Request,
     ) {
+        let msg = self.decode_body(request.body());
+        match self.extract_cmd_type(&msg).as_str() {
+            "Alarm" => {
+                self.on_alarm(device_addr, tcp_stream, request, msg).await;
+            }
+            "Catalog" => {
+                self.on_catalog_notify(device_addr, tcp_stream, request, msg)
+                    .await;
+            }
+            "MobilePosition" => {
+                self.on_mobile_position(device_addr, tcp_stream, request, msg)
+                    .await;
+            }
+            _ => {
+                self.reply_ok(device_addr, tcp_stream, &request).await;
+            }
+        }
     }
 
     pub async fn on_rsp_notify(
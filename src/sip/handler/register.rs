@@ -1,6 +1,6 @@
 use rsip::{
     self,
-    prelude::{HeadersExt, ToTypedHeader},
+    prelude::{HeadersExt, ToTypedHeader, UntypedHeader},
 };
 
 use super::SipHandler;
@@ -131,6 +131,7 @@ impl SipHandler {
                 self.transport_get(via),
                 &self.branch_get(via),
                 gb_code,
+                self.store.add_fetch_global_sn(),
             )
             .await;
 
@@ -140,6 +141,7 @@ impl SipHandler {
                 self.transport_get(via),
                 &self.branch_get(via),
                 gb_code,
+                self.store.add_fetch_global_sn(),
             )
             .await;
 
@@ -149,15 +151,33 @@ impl SipHandler {
                 self.transport_get(via),
                 &self.branch_get(via),
                 gb_code,
+                self.store.add_fetch_global_sn(),
             )
             .await;
+            // 已有目录订阅时由订阅服务续订
+            if self.catalog_subscribe_expires > 0
+                && !self
+                    .subscriptions
+                    .contains_key(&(gb_code.to_string(), String::from("Catalog")))
+            {
+                self.catalog_subscribe_pending.insert(gb_code.to_string());
+            }
         }
     }
 
     pub async fn on_rsp_register(
         &self,
         _device_addr: std::net::SocketAddr,
-        _response: rsip::Response,
+        response: rsip::Response,
     ) {
+        if response.status_code().code() < 200 {
+            return;
+        }
+
+        // 向上级注册的应答
+        if let Ok(call_id) = response.call_id_header() {
+            let call_id = call_id.value().to_string();
+            self.transaction_end(&call_id, response);
+        }
     }
 }
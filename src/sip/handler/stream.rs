@@ -0,0 +1,553 @@
+use rsip::prelude::UntypedHeader;
+
+use crate::{
+    media,
+    sip::{
+        self,
+        message::{sdp, SdpAnswer, SdpSessionType},
+    },
+    store::{InviteResult, TalkSession},
+};
+
+use super::{InviteAnswer, SipHandler, TalkMedia};
+
+impl SipHandler {
+    // 点播实时流: 在媒体节点上开启收流并向设备发送 INVITE, 返回 stream_id
+    // 通道已在播放时复用已有会话, 不再重复 INVITE
+    pub async fn live_open(
+        &self,
+        gb_code: &str,
+        channel_id: &str,
+        setup_type: &str,
+    ) -> Result<(u32, SdpAnswer), (u32, String)> {
+        self.stream_open(
+            gb_code,
+            channel_id,
+            setup_type,
+            SdpSessionType::Play,
+            0,
+            0,
+            self.caller_id_str(),
+        )
+        .await
+    }
+
+    // 录像回放, 每次点播独占一路流
+    pub async fn replay_open(
+        &self,
+        gb_code: &str,
+        channel_id: &str,
+        setup_type: &str,
+        start_ts: u64,
+        stop_ts: u64,
+    ) -> Result<(u32, SdpAnswer), (u32, String)> {
+        self.stream_open(
+            gb_code,
+            channel_id,
+            setup_type,
+            SdpSessionType::Playback,
+            start_ts,
+            stop_ts,
+            self.caller_id_str(),
+        )
+        .await
+    }
+
+    // 双向对讲: 设备音频送往节点的收流端口, 客户端推到节点的音频再由节点发往设备
+    pub async fn talk_open(
+        &self,
+        gb_code: &str,
+        channel_id: &str,
+        setup_type: &str,
+    ) -> Result<(u32, SdpAnswer, TalkMedia), (u32, String)> {
+        let call_id = self.caller_id_str();
+        let (stream_id, answer) = self
+            .stream_open(
+                gb_code,
+                channel_id,
+                setup_type,
+                SdpSessionType::Talk,
+                0,
+                0,
+                call_id.clone(),
+            )
+            .await?;
+
+        let gb_code = gb_code.to_string();
+        let stream_name = media::stream_name(&gb_code, stream_id);
+        let Some(payload_type) = answer
+            .payloads
+            .iter()
+            .copied()
+            .find(|payload_type| sdp::TALK_PAYLOAD_TYPES.contains(payload_type))
+        else {
+            self.stream_close(&gb_code, stream_id).await;
+            return Err((488, String::from("audio codec unsupported")));
+        };
+        let Some((index, _, recv_port)) =
+            self.media.streams.get(&stream_name).map(|stream| *stream)
+        else {
+            self.stream_close(&gb_code, stream_id).await;
+            return Err((500, String::from("media stream lost")));
+        };
+
+        // 设备未在应答中带 y= 时, 发送方向自选 ssrc
+        let ssrc = if answer.ssrc.is_empty() {
+            sdp::generate_ssrc(SdpSessionType::Talk, &gb_code)
+        } else {
+            answer.ssrc.clone()
+        };
+        let Some(send_port) = self
+            .media
+            .start_send_rtp(
+                index,
+                &media::SendRtp {
+                    app: media::TALK_APP.to_string(),
+                    stream: stream_name.clone(),
+                    ssrc,
+                    dst_ip: answer.media_ip.clone(),
+                    dst_port: answer.media_port,
+                    payload_type,
+                    udp: !answer.transport.to_uppercase().starts_with("TCP"),
+                },
+            )
+            .await
+        else {
+            self.stream_close(&gb_code, stream_id).await;
+            return Err((503, String::from("media send failed")));
+        };
+
+        self.store.save_talk(&TalkSession {
+            caller_id: call_id,
+            gb_code: gb_code.clone(),
+            stream_id,
+        });
+        let media = TalkMedia {
+            media_ip: self.media.node_ip(index).unwrap_or_default().to_string(),
+            recv_port,
+            stream: stream_name,
+            send_port,
+            payload_type,
+        };
+        Ok((stream_id, answer, media))
+    }
+
+    // 等待设备对 INVITE 的最终应答, 失败时清理流记录与收流端口
+    #[allow(clippy::too_many_arguments)]
+    async fn stream_open(
+        &self,
+        gb_code: &str,
+        channel_id: &str,
+        setup_type: &str,
+        session_type: SdpSessionType,
+        start_ts: u64,
+        stop_ts: u64,
+        call_id: String,
+    ) -> Result<(u32, SdpAnswer), (u32, String)> {
+        let is_live = matches!(session_type, SdpSessionType::Play);
+        let from_tag = self.tag_new(32);
+        let Some(InviteResult {
+            success,
+            channel_id,
+            stream_id,
+            branch,
+            socket_addr,
+            tcp_stream,
+        }) = self
+            .store
+            .invite(gb_code, channel_id, &call_id, &from_tag, is_live)
+        else {
+            return Err((404, String::from("device not found")));
+        };
+
+        let stream_name = media::stream_name(gb_code, stream_id);
+        if success {
+            return self.stream_join(gb_code, stream_id, &stream_name).await;
+        }
+
+        let transport = media::RtpTransport::from_setup_type(setup_type);
+        let (server_ip, server_port) =
+            match self.media.open_rtp_server(&stream_name, transport).await {
+                Ok(server) => server,
+                Err(e) => {
+                    // 等待中的观看者一并收到失败, 会话整体删除
+                    self.store.bye_all(gb_code, stream_id);
+                    return self
+                        .answer_publish(
+                            &stream_name,
+                            Err(match e {
+                                media::MediaError::PortExhausted => {
+                                    (507, String::from("rtp port exhausted"))
+                                }
+                                media::MediaError::Unavailable => {
+                                    (503, String::from("media server unavailable"))
+                                }
+                            }),
+                        )
+                        .map(|answer| (stream_id, answer));
+                }
+            };
+        self.store
+            .update_stream_server_info(stream_id, &server_ip, server_port);
+
+        if transport == media::RtpTransport::TcpActive {
+            self.media.expect_connect(&call_id, &stream_name);
+        }
+        let mut branch = branch;
+        let mut authorization = None;
+        let mut sent = false;
+        let mut response = None;
+        // 设备要求认证时带摘要重发一次
+        for _ in 0..2 {
+            let receiver = self.transaction_begin(&call_id);
+            sent = self
+                .send_invite(sip::request::invite::SendInviteParams {
+                    device_addr: socket_addr,
+                    tcp_stream: tcp_stream.clone(),
+                    branch: branch.clone(),
+                    channel_id: channel_id.clone(),
+                    caller_id: call_id.clone(),
+                    from_tag: from_tag.clone(),
+                    media_server_ip: server_ip.clone(),
+                    media_server_port: server_port,
+                    session_type,
+                    gb_code: gb_code.to_string(),
+                    setup_type: setup_type.to_string(),
+                    start_ts,
+                    stop_ts,
+                    authorization: authorization.take(),
+                })
+                .await;
+            if !sent {
+                self.transaction_cancel(&call_id);
+                response = None;
+                break;
+            }
+            response = self
+                .transaction_wait_timeout(&call_id, receiver, self.invite_timeout_seconds)
+                .await;
+            authorization = response.as_ref().and_then(|response| {
+                self.authorization_for(response, &rsip::Method::Invite, &self.invite_uri(gb_code))
+            });
+            if authorization.is_none() {
+                break;
+            }
+            branch = format!("z9hG4bK{}", self.tag_new(16));
+        }
+
+        let result = match response {
+            Some(response) if response.status_code().code() < 300 => {
+                Ok(SdpAnswer::parse(&self.decode_body(response.body())))
+            }
+            other => {
+                self.store.bye_all(gb_code, stream_id);
+                self.media.close_rtp_server(&stream_name).await;
+                Err(match other {
+                    Some(response) => (
+                        response.status_code().code() as u32,
+                        invite_failure_reason(&response),
+                    ),
+                    None if sent => (408, String::from("device no response")),
+                    None => (500, String::from("send invite failed")),
+                })
+            }
+        };
+        self.answer_publish(&stream_name, result)
+            .map(|answer| (stream_id, answer))
+    }
+
+    // 通知等待同一路流的观看者, 失败时会话已被清理, 同时移除应答
+    fn answer_publish(&self, stream_name: &str, result: InviteAnswer) -> InviteAnswer {
+        self.answers
+            .entry(stream_name.to_string())
+            .or_insert_with(|| tokio::sync::watch::channel(None).0)
+            .send_replace(Some(result.clone()));
+        if result.is_err() {
+            self.answers.remove(stream_name);
+        }
+        result
+    }
+
+    // 加入通道已有的实时流, INVITE 仍在进行时等待设备应答
+    // 发起方可能带认证重发一次, 最多等待两轮 INVITE 超时
+    async fn stream_join(
+        &self,
+        gb_code: &str,
+        stream_id: u32,
+        stream_name: &str,
+    ) -> Result<(u32, SdpAnswer), (u32, String)> {
+        let mut receiver = self
+            .answers
+            .entry(stream_name.to_string())
+            .or_insert_with(|| tokio::sync::watch::channel(None).0)
+            .subscribe();
+        let result = match tokio::time::timeout(
+            std::time::Duration::from_secs(self.invite_timeout_seconds as u64 * 2),
+            receiver.wait_for(Option::is_some),
+        )
+        .await
+        {
+            Ok(Ok(result)) => result.clone(),
+            _ => None,
+        };
+        match result {
+            Some(result) => result.map(|answer| (stream_id, answer)),
+            None => {
+                // 放弃等待, 只减少自己这个观看者
+                self.stream_close(&gb_code.to_string(), stream_id).await;
+                Err((408, String::from("device no response")))
+            }
+        }
+    }
+}
+
+// 设备拒绝点播的原因, 附带 Warning 头中的说明
+fn invite_failure_reason(response: &rsip::Response) -> String {
+    let warning = response.headers.iter().find_map(|header| match header {
+        rsip::Header::Warning(warning) => Some(warning.value().to_string()),
+        _ => None,
+    });
+    match warning {
+        Some(warning) => format!("device rejected: {}, {}", response.status_code(), warning),
+        None => format!("device rejected: {}", response.status_code()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use rsip::prelude::{HeadersExt, ToTypedHeader};
+
+    use super::SipHandler;
+    use crate::sip::message::SdpSessionType;
+    use crate::store::memory::MemoryStore;
+    use crate::utils::config::{Config, MediaNode};
+
+    async fn handler() -> Arc<SipHandler> {
+        let mut config: Config = serde_yaml::from_str("{}").unwrap();
+        config.invite_timeout_seconds = 2;
+        config.media_nodes = vec![MediaNode {
+            id: String::from("embedded"),
+            kind: String::from("embedded"),
+            ip: String::from("127.0.0.1"),
+            api_url: String::new(),
+            secret: String::new(),
+            weight: 1,
+            rtp_port_min: 30000,
+            rtp_port_max: 30010,
+        }];
+        let store = Box::new(MemoryStore::new(&config));
+        let udp = tokio::net::UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let tcp = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        Arc::new(SipHandler::new(&config, store, udp, tcp))
+    }
+
+    // 按请求拼出应答, 带上额外的头
+    fn response(request: &rsip::Request, status: &str, extra: &str) -> Vec<u8> {
+        format!(
+            "SIP/2.0 {}\r\n{}\r\n{}\r\n{};tag=device\r\n{}\r\n{}\r\n{}Content-Length: 0\r\n\r\n",
+            status,
+            request.via_header().unwrap(),
+            request.from_header().unwrap(),
+            request.to_header().unwrap(),
+            request.call_id_header().unwrap(),
+            request.cseq_header().unwrap(),
+            extra
+        )
+        .into_bytes()
+    }
+
+    #[test]
+    fn test_authorization_for() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let handler = runtime.block_on(handler());
+        let uri = handler.invite_uri("34020000001180000000");
+        let request = rsip::Request::try_from(
+            "INVITE sip:34020000001320000001@3402000000 SIP/2.0\r\n\
+             Via: SIP/2.0/UDP 127.0.0.1:5060;branch=z9hG4bK1\r\n\
+             From: <sip:34020000002000000001@3402000000>;tag=1\r\n\
+             To: <sip:34020000001320000001@3402000000>\r\n\
+             Call-ID: 1@127.0.0.1\r\n\
+             CSeq: 1 INVITE\r\n\
+             Content-Length: 0\r\n\r\n",
+        )
+        .unwrap();
+
+        let challenge = r#"realm="3402000000", nonce="1234567890abcdef", algorithm=MD5"#;
+        let unauthorized = rsip::Response::try_from(response(
+            &request,
+            "401 Unauthorized",
+            &format!("WWW-Authenticate: Digest {}\r\n", challenge),
+        ))
+        .unwrap();
+        let Some(rsip::Header::Authorization(header)) =
+            handler.authorization_for(&unauthorized, &rsip::Method::Invite, &uri)
+        else {
+            panic!("missing authorization");
+        };
+        let authorization = header.typed().unwrap();
+        assert_eq!(authorization.username, handler.id);
+        assert_eq!(authorization.realm, "3402000000");
+        assert_eq!(authorization.nonce, "1234567890abcdef");
+        assert_eq!(authorization.uri, uri);
+        assert!(rsip::services::DigestGenerator::from(
+            &authorization,
+            &handler.password,
+            &rsip::Method::Invite
+        )
+        .verify(&authorization.response));
+
+        let proxy = rsip::Response::try_from(response(
+            &request,
+            "407 Proxy Authentication Required",
+            &format!("Proxy-Authenticate: Digest {}\r\n", challenge),
+        ))
+        .unwrap();
+        assert!(matches!(
+            handler.authorization_for(&proxy, &rsip::Method::Invite, &uri),
+            Some(rsip::Header::ProxyAuthorization(_))
+        ));
+
+        // 未带质询或其他失败不重发
+        let forbidden = rsip::Response::try_from(response(&request, "403 Forbidden", "")).unwrap();
+        assert!(handler
+            .authorization_for(&forbidden, &rsip::Method::Invite, &uri)
+            .is_none());
+        let unauthorized =
+            rsip::Response::try_from(response(&request, "401 Unauthorized", "")).unwrap();
+        assert!(handler
+            .authorization_for(&unauthorized, &rsip::Method::Invite, &uri)
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_stream_open_auth_retry_failure_cleanup() {
+        let handler = handler().await;
+        let gb_code = "34020000001180000000";
+        let channel_id = "34020000001320000001";
+        let device = tokio::net::UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let device_addr = device.local_addr().unwrap();
+        handler
+            .store
+            .register("z9hG4bK1", gb_code, device_addr, &None);
+
+        // 设备先要求认证, 带摘要重发后拒绝
+        let device_handler = handler.clone();
+        let device_task = tokio::spawn(async move {
+            let mut authorized = vec![];
+            let mut buffer = vec![0; 65535];
+            while authorized.len() < 2 {
+                let (amount, _) = device.recv_from(&mut buffer).await.unwrap();
+                let Ok(request) = rsip::Request::try_from(&buffer[..amount]) else {
+                    continue;
+                };
+                if request.method() != &rsip::Method::Invite {
+                    continue;
+                }
+                authorized.push(request.authorization_header().is_some());
+                let reply = if authorized.len() == 1 {
+                    response(
+                        &request,
+                        "401 Unauthorized",
+                        "WWW-Authenticate: Digest realm=\"3402000000\", nonce=\"abc\", algorithm=MD5\r\n",
+                    )
+                } else {
+                    response(&request, "403 Forbidden", "")
+                };
+                device_handler.dispatch(device_addr, None, &reply).await;
+            }
+            authorized
+        });
+
+        let call_id = handler.caller_id_str();
+        let result = handler
+            .stream_open(
+                gb_code,
+                channel_id,
+                "active",
+                SdpSessionType::Play,
+                0,
+                0,
+                call_id.clone(),
+            )
+            .await;
+        assert_eq!(result.unwrap_err().0, 403);
+        assert_eq!(device_task.await.unwrap(), [false, true]);
+
+        // 流记录、收流端口与事务都已释放
+        assert!(handler.media.streams.is_empty());
+        assert_eq!(handler.media.ports[0].lock().unwrap().in_use(), 0);
+        assert!(handler.transactions.is_empty());
+        let invite = handler
+            .store
+            .invite(gb_code, channel_id, "retry", "tag", true)
+            .unwrap();
+        assert!(!invite.success);
+    }
+
+    #[tokio::test]
+    async fn test_stream_join_pending_invite() {
+        let handler = handler().await;
+        let gb_code = "34020000001180000000";
+        let channel_id = "34020000001320000001";
+        let device = tokio::net::UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let device_addr = device.local_addr().unwrap();
+        handler
+            .store
+            .register("z9hG4bK1", gb_code, device_addr, &None);
+
+        // 设备收到 INVITE 后等测试放行再应答
+        let (invited_sender, invited_receiver) = tokio::sync::oneshot::channel();
+        let (reply_sender, reply_receiver) = tokio::sync::oneshot::channel::<()>();
+        let device_handler = handler.clone();
+        let device_task = tokio::spawn(async move {
+            let mut buffer = vec![0; 65535];
+            let request = loop {
+                let (amount, _) = device.recv_from(&mut buffer).await.unwrap();
+                if let Ok(request) = rsip::Request::try_from(&buffer[..amount]) {
+                    if request.method() == &rsip::Method::Invite {
+                        break request;
+                    }
+                }
+            };
+            invited_sender.send(()).unwrap();
+            reply_receiver.await.unwrap();
+            let sdp =
+                "v=0\r\nc=IN IP4 192.168.1.64\r\nm=video 15060 RTP/AVP 96\r\ny=0100000001\r\n";
+            let reply = String::from_utf8(response(&request, "200 OK", ""))
+                .unwrap()
+                .replace(
+                    "Content-Length: 0\r\n\r\n",
+                    &format!(
+                        "Content-Type: application/sdp\r\nContent-Length: {}\r\n\r\n{}",
+                        sdp.len(),
+                        sdp
+                    ),
+                );
+            device_handler
+                .dispatch(device_addr, None, reply.as_bytes())
+                .await;
+        });
+
+        let open = || {
+            let handler = handler.clone();
+            tokio::spawn(async move { handler.live_open(gb_code, channel_id, "udp").await })
+        };
+        let first = open();
+        invited_receiver.await.unwrap();
+        let second = open();
+        // 第二个观看者应等待设备应答, 不能先拿到空的 SDP
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        assert!(!second.is_finished());
+
+        reply_sender.send(()).unwrap();
+        device_task.await.unwrap();
+        let (first_id, first_answer) = first.await.unwrap().unwrap();
+        let (second_id, second_answer) = second.await.unwrap().unwrap();
+        assert_eq!(first_id, second_id);
+        assert_eq!(second_answer.media_port, 15060);
+        assert_eq!(second_answer.ssrc, first_answer.ssrc);
+        assert_eq!(second_answer.ssrc, "0100000001");
+    }
+}
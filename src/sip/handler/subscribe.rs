@@ -1,4 +1,16 @@
-use super::SipHandler;
+use std::sync::Arc;
+
+use rsip::prelude::{HeadersExt, UntypedHeader};
+
+use super::{SipHandler, SipTransaction, Subscription};
+use crate::{sip, store::DeviceInfo};
+
+fn ts_now() -> u32 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .expect("Time went backwards")
+        .as_secs() as u32
+}
 
 impl SipHandler {
     pub async fn on_req_subscribe(
@@ -13,7 +25,228 @@ impl SipHandler {
         &self,
         _device_addr: std::net::SocketAddr,
         _tcp_stream: Option<std::sync::Arc<tokio::sync::Mutex<tokio::net::tcp::OwnedWriteHalf>>>,
-        _response: rsip::Response,
+        response: rsip::Response,
     ) {
+        if response.status_code().code() < 200 {
+            return;
+        }
+
+        if let Ok(call_id) = response.call_id_header() {
+            let call_id = call_id.value().to_string();
+            self.transaction_end(&call_id, response);
+        }
+    }
+
+    // 报警订阅, 条件为空时订阅全部报警, 返回设备认可的有效期
+    pub async fn alarm_subscribe(
+        &self,
+        gb_code: &str,
+        mut query: sip::message::AlarmQuery,
+        expires: u32,
+    ) -> Result<u32, (u32, String)> {
+        query.sn = self.store.add_fetch_global_sn();
+        query.device_id = gb_code.to_string();
+        self.subscribe(
+            gb_code,
+            "Alarm",
+            "presence",
+            query.serialize_to_xml(),
+            expires,
+        )
+        .await
+    }
+
+    // 移动位置订阅, 设备按 interval 秒上报一次位置
+    pub async fn mobile_position_subscribe(
+        &self,
+        gb_code: &str,
+        interval: u32,
+        expires: u32,
+    ) -> Result<u32, (u32, String)> {
+        let query = sip::message::MobilePositionQuery::new(
+            self.store.add_fetch_global_sn(),
+            gb_code,
+            interval,
+        );
+        self.subscribe(
+            gb_code,
+            "MobilePosition",
+            "presence",
+            query.serialize_to_xml(),
+            expires,
+        )
+        .await
+    }
+
+    // 目录订阅, 设备在通道上下线、增删改时推送 NOTIFY
+    pub async fn catalog_subscribe(
+        &self,
+        gb_code: &str,
+        expires: u32,
+    ) -> Result<u32, (u32, String)> {
+        let query = sip::message::CatalogQuery::new(self.store.add_fetch_global_sn(), gb_code);
+        self.subscribe(
+            gb_code,
+            "Catalog",
+            "Catalog",
+            query.serialize_to_xml(),
+            expires,
+        )
+        .await
+    }
+
+    // 发起或刷新订阅, 同一 (gb_code, cmd_type) 的刷新沿用原对话, expires 为 0 时取消
+    pub async fn subscribe(
+        &self,
+        gb_code: &str,
+        cmd_type: &str,
+        event: &str,
+        body: String,
+        expires: u32,
+    ) -> Result<u32, (u32, String)> {
+        let Some(DeviceInfo {
+            socket_addr,
+            tcp_stream,
+            ..
+        }) = self.store.find_device_by_gb_code(gb_code)
+        else {
+            return Err((404, String::from("device not found")));
+        };
+
+        let key = (gb_code.to_string(), cmd_type.to_string());
+        let (caller_id, from_tag, to_tag) = match self.subscriptions.get(&key) {
+            Some(subscription) => (
+                subscription.caller_id.clone(),
+                subscription.from_tag.clone(),
+                subscription.to_tag.clone(),
+            ),
+            None => (self.caller_id_str(), self.tag_new(32), String::new()),
+        };
+
+        let receiver = self.transaction_begin(&caller_id);
+        if !self
+            .send_subscribe(
+                socket_addr,
+                tcp_stream,
+                &SipTransaction {
+                    caller_id: caller_id.clone(),
+                    from_tag: from_tag.clone(),
+                    to_tag: to_tag.clone(),
+                    branch: format!("z9hG4bK{}", self.tag_new(16)),
+                },
+                &gb_code.to_string(),
+                event,
+                expires,
+                &body,
+            )
+            .await
+        {
+            self.transaction_cancel(&caller_id);
+            return Err((500, String::from("send failed")));
+        }
+        let Some(response) = self.transaction_wait(&caller_id, receiver).await else {
+            return Err((408, String::from("device timeout")));
+        };
+        if response.status_code().code() >= 300 {
+            self.subscriptions.remove(&key);
+            return Err((
+                response.status_code().code() as u32,
+                format!("device rejected: {}", response.status_code()),
+            ));
+        }
+
+        let expires = response
+            .expires_header()
+            .and_then(|exp| exp.seconds().ok())
+            .unwrap_or(expires);
+        if expires == 0 {
+            self.subscriptions.remove(&key);
+            return Ok(0);
+        }
+        let to_tag = response
+            .to_header()
+            .ok()
+            .and_then(|to| to.tag().ok().flatten())
+            .map(|tag| tag.to_string())
+            .unwrap_or(to_tag);
+        self.subscriptions.insert(
+            key,
+            Subscription {
+                caller_id,
+                from_tag,
+                to_tag,
+                event: event.to_string(),
+                body,
+                expires,
+                ts: ts_now(),
+            },
+        );
+        Ok(expires)
+    }
+}
+
+// 订阅到期前按原条件刷新, 过期仍未刷新成功则放弃
+pub async fn run_forever(sip_handler: Arc<SipHandler>) {
+    loop {
+        // 注册处理中不能等待应答, 新注册设备的目录订阅在这里发起
+        let pending: Vec<String> = sip_handler
+            .catalog_subscribe_pending
+            .iter()
+            .map(|gb_code| gb_code.clone())
+            .collect();
+        for gb_code in pending {
+            sip_handler.catalog_subscribe_pending.remove(&gb_code);
+            let sip_handler = sip_handler.clone();
+            tokio::spawn(async move {
+                if let Err((code, msg)) = sip_handler
+                    .catalog_subscribe(&gb_code, sip_handler.catalog_subscribe_expires)
+                    .await
+                {
+                    tracing::warn!(
+                        "catalog subscribe failed, gb_code: {}, code: {}, msg: {}",
+                        gb_code,
+                        code,
+                        msg
+                    );
+                }
+            });
+        }
+
+        let now = ts_now();
+        let due: Vec<_> = sip_handler
+            .subscriptions
+            .iter()
+            .filter(|entry| now >= entry.ts + entry.expires * 2 / 3)
+            .map(|entry| {
+                (
+                    entry.key().clone(),
+                    entry.event.clone(),
+                    entry.body.clone(),
+                    entry.expires,
+                )
+            })
+            .collect();
+
+        for ((gb_code, cmd_type), event, body, expires) in due {
+            if let Err((code, msg)) = sip_handler
+                .subscribe(&gb_code, &cmd_type, &event, body, expires)
+                .await
+            {
+                tracing::warn!(
+                    "subscribe refresh failed, gb_code: {}, cmd_type: {}, code: {}, msg: {}",
+                    gb_code,
+                    cmd_type,
+                    code,
+                    msg
+                );
+                sip_handler
+                    .subscriptions
+                    .remove_if(&(gb_code, cmd_type), |_, subscription| {
+                        now >= subscription.ts + subscription.expires
+                    });
+            }
+        }
+
+        tokio::time::sleep(std::time::Duration::from_secs(5)).await;
     }
 }
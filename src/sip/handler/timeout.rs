@@ -0,0 +1,91 @@
+use std::sync::{mpsc, Arc};
+
+use super::{SipHandler, SipTransaction};
+use crate::media;
+use crate::store::ByeResult;
+
+impl SipHandler {
+    // 结束一路流: 最后一个观看者退出时挂断设备, 并释放媒体节点上的收流端口
+    pub async fn stream_close(&self, gb_code: &String, stream_id: u32) -> bool {
+        let bye_result = self.store.bye(gb_code, stream_id);
+        self.stream_release(gb_code, stream_id, bye_result).await
+    }
+
+    // 流超时: 不再等待其他观看者, 直接挂断
+    pub async fn stream_close_all(&self, gb_code: &String, stream_id: u32) -> bool {
+        let bye_result = self.store.bye_all(gb_code, stream_id);
+        self.stream_release(gb_code, stream_id, bye_result).await
+    }
+
+    pub async fn stream_release(
+        &self,
+        gb_code: &String,
+        stream_id: u32,
+        bye_result: Option<ByeResult>,
+    ) -> bool {
+        // 仍有其他观看者, 保留设备会话和收流端口
+        if let Some(ByeResult { success: false, .. }) = bye_result {
+            return true;
+        }
+
+        let found = bye_result.is_some();
+        if let Some(ByeResult {
+            success: true,
+            caller_id,
+            from_tag,
+            to_tag,
+            branch,
+            socket_addr,
+            tcp_stream,
+        }) = bye_result
+        {
+            self.send_bye(
+                socket_addr,
+                tcp_stream,
+                SipTransaction {
+                    caller_id,
+                    from_tag,
+                    to_tag,
+                    branch,
+                },
+                gb_code,
+            )
+            .await;
+        }
+
+        self.store.remove_talk(gb_code, stream_id);
+
+        // 存储中的流可能已过期被清理, 端口仍需释放
+        let stream_name = media::stream_name(gb_code, stream_id);
+        self.answers.remove(&stream_name);
+        let closed = self.media.close_rtp_server(&stream_name).await;
+        found || closed
+    }
+}
+
+// 处理存储层上报的超时设备与超时流
+pub async fn run_forever(
+    sip_handler: Arc<SipHandler>,
+    timeout_devices_receiver: mpsc::Receiver<Option<String>>,
+    timeout_streams_receiver: mpsc::Receiver<Option<(String, u32)>>,
+) {
+    loop {
+        while let Ok(Some((gb_code, stream_id))) = timeout_streams_receiver.try_recv() {
+            if sip_handler.stream_close_all(&gb_code, stream_id).await {
+                tracing::warn!(
+                    "stream timeout, gb_code: {}, stream_id: {}",
+                    gb_code,
+                    stream_id
+                );
+            }
+        }
+
+        while let Ok(Some(gb_code)) = timeout_devices_receiver.try_recv() {
+            if sip_handler.store.unregister(&gb_code) {
+                tracing::warn!("device timeout, gb_code: {}", gb_code);
+            }
+        }
+
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    }
+}
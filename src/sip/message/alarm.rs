@@ -0,0 +1,156 @@
+use serde::{Deserialize, Serialize};
+use serde_xml_rs::{from_str, to_string};
+
+use tracing;
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct AlarmInfo {
+    // 报警类型, 含义随 AlarmMethod 不同
+    #[serde(rename = "AlarmType", default)]
+    pub alarm_type: String,
+}
+
+/// 设备上报的报警通知, 通过 MESSAGE 或订阅后的 NOTIFY 送达
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(rename = "Notify")]
+pub struct Alarm {
+    #[serde(rename = "CmdType")]
+    pub cmd_type: String,
+    #[serde(rename = "SN")]
+    pub sn: u32,
+    #[serde(rename = "DeviceID")]
+    pub device_id: String,
+    // 1 一级警情 ~ 4 四级警情
+    #[serde(rename = "AlarmPriority", default)]
+    pub alarm_priority: String,
+    // 1 电话 2 设备 3 短信 4 GPS 5 视频 6 设备故障 7 其他
+    #[serde(rename = "AlarmMethod", default)]
+    pub alarm_method: String,
+    #[serde(rename = "AlarmTime", default)]
+    pub alarm_time: String,
+    #[serde(rename = "AlarmDescription", default)]
+    pub alarm_description: String,
+    #[serde(rename = "Longitude", default)]
+    pub longitude: Option<f64>,
+    #[serde(rename = "Latitude", default)]
+    pub latitude: Option<f64>,
+    #[serde(rename = "Info", default)]
+    pub info: Option<AlarmInfo>,
+}
+
+impl Alarm {
+    pub fn serialize_to_xml(&self) -> String {
+        match to_string(self) {
+            Ok(s) => s,
+            Err(e) => {
+                tracing::error!("serde_xml_rs::to_string({:?}) error, e: {:?}", self, e);
+                String::new()
+            }
+        }
+    }
+
+    pub fn deserialize_from_xml(s: String) -> Self {
+        match from_str(s.as_str()) {
+            Ok(k) => k,
+            Err(e) => {
+                tracing::error!("serde_xml_rs::from_str({}) error, e: {:?}", s, e);
+                Alarm::default()
+            }
+        }
+    }
+}
+
+/// 报警订阅的查询条件, 作为 SUBSCRIBE 的消息体
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename = "Query")]
+pub struct AlarmQuery {
+    #[serde(rename = "CmdType")]
+    pub cmd_type: String,
+    #[serde(rename = "SN")]
+    pub sn: u32,
+    #[serde(rename = "DeviceID")]
+    pub device_id: String,
+    // 优先级与方式为 0 时不限
+    #[serde(rename = "StartAlarmPriority")]
+    pub start_alarm_priority: String,
+    #[serde(rename = "EndAlarmPriority")]
+    pub end_alarm_priority: String,
+    #[serde(rename = "AlarmMethod")]
+    pub alarm_method: String,
+    #[serde(rename = "StartAlarmTime")]
+    pub start_alarm_time: String,
+    #[serde(rename = "EndAlarmTime")]
+    pub end_alarm_time: String,
+}
+
+impl AlarmQuery {
+    pub fn new(sn: u32, gb_code: &str) -> Self {
+        AlarmQuery {
+            cmd_type: String::from("Alarm"),
+            sn,
+            device_id: gb_code.to_string(),
+            start_alarm_priority: String::from("0"),
+            end_alarm_priority: String::from("0"),
+            alarm_method: String::from("0"),
+            ..Default::default()
+        }
+    }
+
+    pub fn serialize_to_xml(&self) -> String {
+        match to_string(self) {
+            Ok(s) => s,
+            Err(e) => {
+                tracing::error!("serde_xml_rs::to_string({:?}) error, e: {:?}", self, e);
+                String::new()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Alarm, AlarmQuery};
+
+    #[test]
+    fn test_alarm_deserialize() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<Notify>
+<CmdType>Alarm</CmdType>
+<SN>17</SN>
+<DeviceID>34020000001340000001</DeviceID>
+<AlarmPriority>1</AlarmPriority>
+<AlarmMethod>5</AlarmMethod>
+<AlarmTime>2024-05-01T08:30:00</AlarmTime>
+<AlarmDescription>motion</AlarmDescription>
+<Longitude>120.1</Longitude>
+<Latitude>30.2</Latitude>
+<Info>
+<AlarmType>2</AlarmType>
+</Info>
+</Notify>"#;
+        let alarm = Alarm::deserialize_from_xml(xml.to_string());
+        assert_eq!(alarm.sn, 17);
+        assert_eq!(alarm.device_id, "34020000001340000001");
+        assert_eq!(alarm.alarm_priority, "1");
+        assert_eq!(alarm.alarm_method, "5");
+        assert_eq!(alarm.alarm_time, "2024-05-01T08:30:00");
+        assert_eq!(alarm.longitude, Some(120.1));
+        assert_eq!(alarm.info.unwrap().alarm_type, "2");
+
+        // 部分设备不带坐标与 Info
+        let xml = "<Notify><CmdType>Alarm</CmdType><SN>1</SN>\
+            <DeviceID>34020000001340000001</DeviceID><AlarmPriority>4</AlarmPriority>\
+            <AlarmMethod>2</AlarmMethod><AlarmTime>2024-05-01T08:30:00</AlarmTime></Notify>";
+        let alarm = Alarm::deserialize_from_xml(xml.to_string());
+        assert_eq!(alarm.alarm_priority, "4");
+        assert!(alarm.longitude.is_none());
+        assert!(alarm.info.is_none());
+    }
+
+    #[test]
+    fn test_alarm_query_xml() {
+        let xml = AlarmQuery::new(3, "34020000001180000000").serialize_to_xml();
+        assert!(xml.contains("<CmdType>Alarm</CmdType>"));
+        assert!(xml.contains("<StartAlarmPriority>0</StartAlarmPriority>"));
+    }
+}
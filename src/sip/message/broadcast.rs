@@ -0,0 +1,89 @@
+use serde::{Deserialize, Serialize};
+use serde_xml_rs::{from_str, to_string};
+
+use tracing;
+
+/// 语音广播通知, 设备收到后向 SourceID 发起 INVITE 拉取音频
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename = "Notify")]
+pub struct Broadcast {
+    #[serde(rename = "CmdType")]
+    pub cmd_type: String,
+    #[serde(rename = "SN")]
+    pub sn: u32,
+    // 音频源, 即本平台编码
+    #[serde(rename = "SourceID")]
+    pub source_id: String,
+    // 设备的语音输出通道
+    #[serde(rename = "TargetID")]
+    pub target_id: String,
+}
+
+impl Broadcast {
+    pub fn new(sn: u32, source_id: &str, target_id: &str) -> Self {
+        Broadcast {
+            cmd_type: String::from("Broadcast"),
+            sn,
+            source_id: source_id.to_string(),
+            target_id: target_id.to_string(),
+        }
+    }
+
+    pub fn serialize_to_xml(&self) -> String {
+        match to_string(self) {
+            Ok(s) => s,
+            Err(e) => {
+                tracing::error!("serde_xml_rs::to_string({:?}) error, e: {:?}", self, e);
+                String::new()
+            }
+        }
+    }
+}
+
+/// 设备对语音广播通知的应答
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename = "Response")]
+pub struct BroadcastResponse {
+    #[serde(rename = "CmdType")]
+    pub cmd_type: String,
+    #[serde(rename = "SN")]
+    pub sn: u32,
+    #[serde(rename = "DeviceID")]
+    pub device_id: String,
+    // OK 或 ERROR
+    #[serde(rename = "Result", default)]
+    pub result: String,
+}
+
+impl BroadcastResponse {
+    pub fn deserialize_from_xml(s: String) -> Self {
+        match from_str(s.as_str()) {
+            Ok(k) => k,
+            Err(e) => {
+                tracing::error!("serde_xml_rs::from_str({}) error, e: {:?}", s, e);
+                BroadcastResponse::default()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Broadcast, BroadcastResponse};
+
+    #[test]
+    fn test_broadcast_xml() {
+        let xml =
+            Broadcast::new(5, "34020000002000000001", "34020000001370000001").serialize_to_xml();
+        assert!(xml.contains("<CmdType>Broadcast</CmdType>"));
+        assert!(xml.contains("<SourceID>34020000002000000001</SourceID>"));
+        assert!(xml.contains("<TargetID>34020000001370000001</TargetID>"));
+
+        let xml = "<Response><CmdType>Broadcast</CmdType><SN>5</SN>\
+            <DeviceID>34020000001370000001</DeviceID><Result>OK</Result></Response>";
+        let response = BroadcastResponse::deserialize_from_xml(xml.to_string());
+        assert_eq!(response.sn, 5);
+        assert_eq!(response.device_id, "34020000001370000001");
+        assert_eq!(response.result, "OK");
+    }
+}
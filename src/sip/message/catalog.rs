@@ -1,8 +1,9 @@
 use serde::{Deserialize, Serialize};
 use serde_xml_rs::{from_str, to_string};
+use std::fmt;
 use tracing;
 
-#[derive(Debug, Default, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 #[serde(rename = "Response")]
 pub struct Catalog {
     #[serde(rename = "CmdType")]
@@ -17,7 +18,7 @@ pub struct Catalog {
     pub device_list: DeviceList,
 }
 
-#[derive(Debug, Default, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 #[serde(rename = "DeviceList")]
 pub struct DeviceList {
     #[serde(rename = "Num", default)]
@@ -26,32 +27,32 @@ pub struct DeviceList {
     pub items: Vec<Device>,
 }
 
-#[derive(Debug, Default, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 #[serde(rename = "Item")]
 pub struct Device {
     #[serde(rename = "DeviceID")]
     pub device_id: String,
-    #[serde(rename = "Name")]
+    #[serde(rename = "Name", default)]
     pub name: String,
-    #[serde(rename = "Manufacturer")]
+    #[serde(rename = "Manufacturer", default)]
     pub manufacturer: String,
-    #[serde(rename = "Model")]
+    #[serde(rename = "Model", default)]
     pub model: String,
-    #[serde(rename = "Owner")]
+    #[serde(rename = "Owner", default)]
     pub owner: String,
-    #[serde(rename = "CivilCode")]
+    #[serde(rename = "CivilCode", default)]
     pub civil_code: String,
     #[serde(rename = "Block")]
     pub block: Option<String>,
-    #[serde(rename = "Address")]
+    #[serde(rename = "Address", default)]
     pub address: String,
-    #[serde(rename = "Parental")]
+    #[serde(rename = "Parental", default)]
     pub parental: u32,
-    #[serde(rename = "ParentID")]
+    #[serde(rename = "ParentID", default)]
     pub parent_id: String,
-    #[serde(rename = "RegisterWay")]
+    #[serde(rename = "RegisterWay", default)]
     pub register_way: u32,
-    #[serde(rename = "Secrecy")]
+    #[serde(rename = "Secrecy", default)]
     pub secrecy: u32,
     #[serde(rename = "IPAddress")]
     pub ip_address: Option<String>,
@@ -67,17 +68,104 @@ pub struct Device {
     pub latitude: Option<f64>,
     #[serde(rename = "PTZType")]
     pub ptz_type: Option<u32>,
+    // 目录订阅通知中的事件类型, 见 CatalogEvent
+    #[serde(rename = "Event", default)]
+    pub event: Option<String>,
+}
+
+/// 目录订阅通知中单个通道的变化
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum CatalogEvent {
+    // 上线
+    On,
+    // 离线
+    Off,
+    // 视频丢失
+    Vlost,
+    // 故障
+    Defect,
+    Add,
+    Del,
+    Update,
+}
+
+impl CatalogEvent {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_uppercase().as_str() {
+            "ON" => Some(CatalogEvent::On),
+            "OFF" => Some(CatalogEvent::Off),
+            "VLOST" => Some(CatalogEvent::Vlost),
+            "DEFECT" => Some(CatalogEvent::Defect),
+            "ADD" => Some(CatalogEvent::Add),
+            "DEL" => Some(CatalogEvent::Del),
+            "UPDATE" => Some(CatalogEvent::Update),
+            _ => None,
+        }
+    }
+
+    // 事件对应的通道状态, 视频丢失与故障按离线处理
+    pub fn status(&self) -> Option<&'static str> {
+        match self {
+            CatalogEvent::On => Some("ON"),
+            CatalogEvent::Off | CatalogEvent::Vlost | CatalogEvent::Defect => Some("OFF"),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for CatalogEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CatalogEvent::On => write!(f, "ON"),
+            CatalogEvent::Off => write!(f, "OFF"),
+            CatalogEvent::Vlost => write!(f, "VLOST"),
+            CatalogEvent::Defect => write!(f, "DEFECT"),
+            CatalogEvent::Add => write!(f, "ADD"),
+            CatalogEvent::Del => write!(f, "DEL"),
+            CatalogEvent::Update => write!(f, "UPDATE"),
+        }
+    }
 }
 
 impl Catalog {
+    // serde_xml_rs 不支持序列化 Vec 与属性, DeviceList 需手工拼装
     pub fn serialize_to_xml(&self) -> String {
-        match to_string(self) {
-            Ok(s) => s,
-            Err(e) => {
-                tracing::error!("serde_xml_rs::to_string({:?}) error, e: {:?}", self, e);
-                String::new()
-            }
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<Response>\n");
+        xml_element(&mut xml, "CmdType", &self.cmd_type);
+        xml_element(&mut xml, "SN", &self.sn);
+        xml_element(&mut xml, "DeviceID", &self.device_id);
+        xml_element(&mut xml, "SumNum", &self.sum_num);
+        xml.push_str(&format!(
+            "<DeviceList Num=\"{}\">\n",
+            self.device_list.items.len()
+        ));
+        for item in &self.device_list.items {
+            xml.push_str("<Item>\n");
+            xml_element(&mut xml, "DeviceID", &item.device_id);
+            xml_element(&mut xml, "Name", &item.name);
+            xml_element(&mut xml, "Manufacturer", &item.manufacturer);
+            xml_element(&mut xml, "Model", &item.model);
+            xml_element(&mut xml, "Owner", &item.owner);
+            xml_element(&mut xml, "CivilCode", &item.civil_code);
+            xml_optional(&mut xml, "Block", &item.block);
+            xml_element(&mut xml, "Address", &item.address);
+            xml_element(&mut xml, "Parental", &item.parental);
+            xml_element(&mut xml, "ParentID", &item.parent_id);
+            xml_element(&mut xml, "RegisterWay", &item.register_way);
+            xml_element(&mut xml, "Secrecy", &item.secrecy);
+            xml_optional(&mut xml, "IPAddress", &item.ip_address);
+            xml_optional(&mut xml, "Port", &item.port);
+            xml_optional(&mut xml, "Password", &item.password);
+            xml_optional(&mut xml, "Status", &item.status);
+            xml_optional(&mut xml, "Longitude", &item.longitude);
+            xml_optional(&mut xml, "Latitude", &item.latitude);
+            xml_optional(&mut xml, "PTZType", &item.ptz_type);
+            xml_optional(&mut xml, "Event", &item.event);
+            xml.push_str("</Item>\n");
         }
+        xml.push_str("</DeviceList>\n</Response>\n");
+        xml
     }
 
     pub fn deserialize_from_xml(s: String) -> Self {
@@ -91,6 +179,21 @@ impl Catalog {
     }
 }
 
+fn xml_element<T: std::fmt::Display>(xml: &mut String, name: &str, value: &T) {
+    let value = value
+        .to_string()
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;");
+    xml.push_str(&format!("<{name}>{value}</{name}>\n"));
+}
+
+fn xml_optional<T: std::fmt::Display>(xml: &mut String, name: &str, value: &Option<T>) {
+    if let Some(v) = value {
+        xml_element(xml, name, v);
+    }
+}
+
 #[derive(Debug, Default, Serialize, Deserialize)]
 #[serde(rename = "Query")]
 pub struct CatalogQuery {
@@ -131,3 +234,79 @@ impl CatalogQuery {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{Catalog, CatalogEvent, Device, DeviceList};
+
+    #[test]
+    fn test_catalog_xml_round_trip() {
+        let catalog = Catalog {
+            cmd_type: String::from("Catalog"),
+            sn: 17,
+            device_id: String::from("34020000002000000001"),
+            sum_num: 2,
+            device_list: DeviceList {
+                num: 2,
+                items: vec![
+                    Device {
+                        device_id: String::from("34020000001320000001"),
+                        name: String::from("A&B"),
+                        status: Some(String::from("ON")),
+                        ..Default::default()
+                    },
+                    Device {
+                        device_id: String::from("34020000001320000002"),
+                        port: Some(5060),
+                        ..Default::default()
+                    },
+                ],
+            },
+        };
+
+        let xml = catalog.serialize_to_xml();
+        assert!(xml.contains("<DeviceList Num=\"2\">"));
+        assert!(xml.contains("<Name>A&amp;B</Name>"));
+
+        let parsed = Catalog::deserialize_from_xml(xml);
+        assert_eq!(parsed.sum_num, 2);
+        assert_eq!(parsed.device_list.num, 2);
+        assert_eq!(parsed.device_list.items.len(), 2);
+        assert_eq!(parsed.device_list.items[0].name, "A&B");
+        assert_eq!(parsed.device_list.items[1].port, Some(5060));
+    }
+
+    #[test]
+    fn test_catalog_notify_events() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<Notify>
+<CmdType>Catalog</CmdType>
+<SN>9</SN>
+<DeviceID>34020000002000000001</DeviceID>
+<SumNum>2</SumNum>
+<DeviceList Num="2">
+<Item>
+<DeviceID>34020000001320000001</DeviceID>
+<Event>OFF</Event>
+</Item>
+<Item>
+<DeviceID>34020000001320000002</DeviceID>
+<Name>Gate</Name>
+<Status>ON</Status>
+<Event>ADD</Event>
+</Item>
+</DeviceList>
+</Notify>"#;
+        let parsed = Catalog::deserialize_from_xml(xml.to_string());
+        assert_eq!(parsed.device_list.items.len(), 2);
+        let events: Vec<_> = parsed
+            .device_list
+            .items
+            .iter()
+            .map(|item| CatalogEvent::parse(item.event.as_deref().unwrap_or_default()))
+            .collect();
+        assert_eq!(events, [Some(CatalogEvent::Off), Some(CatalogEvent::Add)]);
+        assert_eq!(parsed.device_list.items[1].name, "Gate");
+        assert_eq!(CatalogEvent::Vlost.status(), Some("OFF"));
+    }
+}
@@ -0,0 +1,307 @@
+use serde::{Deserialize, Serialize};
+use serde_xml_rs::{from_str, to_string};
+use std::fmt;
+use std::str::FromStr;
+
+use tracing;
+
+// PTZCmd 固定字段: 字节1 A5H, 字节2 版本号(0H)与校验码组合, 字节3 地址低8位
+static PTZ_CMD_HEADER: u8 = 0xA5;
+static PTZ_CMD_VERSION: u8 = 0x00;
+static PTZ_CMD_ADDRESS: u16 = 0x0001;
+
+pub enum PtzCommand {
+    Stop,
+    Left,
+    Right,
+    Up,
+    Down,
+    LeftUp,
+    LeftDown,
+    RightUp,
+    RightDown,
+    ZoomIn,
+    ZoomOut,
+    FocusNear,
+    FocusFar,
+    IrisOpen,
+    IrisClose,
+}
+
+impl fmt::Display for PtzCommand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PtzCommand::Stop => write!(f, "stop"),
+            PtzCommand::Left => write!(f, "left"),
+            PtzCommand::Right => write!(f, "right"),
+            PtzCommand::Up => write!(f, "up"),
+            PtzCommand::Down => write!(f, "down"),
+            PtzCommand::LeftUp => write!(f, "left_up"),
+            PtzCommand::LeftDown => write!(f, "left_down"),
+            PtzCommand::RightUp => write!(f, "right_up"),
+            PtzCommand::RightDown => write!(f, "right_down"),
+            PtzCommand::ZoomIn => write!(f, "zoom_in"),
+            PtzCommand::ZoomOut => write!(f, "zoom_out"),
+            PtzCommand::FocusNear => write!(f, "focus_near"),
+            PtzCommand::FocusFar => write!(f, "focus_far"),
+            PtzCommand::IrisOpen => write!(f, "iris_open"),
+            PtzCommand::IrisClose => write!(f, "iris_close"),
+        }
+    }
+}
+
+impl FromStr for PtzCommand {
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "stop" => Ok(Self::Stop),
+            "left" => Ok(Self::Left),
+            "right" => Ok(Self::Right),
+            "up" => Ok(Self::Up),
+            "down" => Ok(Self::Down),
+            "left_up" => Ok(Self::LeftUp),
+            "left_down" => Ok(Self::LeftDown),
+            "right_up" => Ok(Self::RightUp),
+            "right_down" => Ok(Self::RightDown),
+            "zoom_in" => Ok(Self::ZoomIn),
+            "zoom_out" => Ok(Self::ZoomOut),
+            "focus_near" => Ok(Self::FocusNear),
+            "focus_far" => Ok(Self::FocusFar),
+            "iris_open" => Ok(Self::IrisOpen),
+            "iris_close" => Ok(Self::IrisClose),
+            _ => Err(String::from(
+                "Support lists: stop, left, right, up, down, left_up, left_down, right_up, right_down, zoom_in, zoom_out, focus_near, focus_far, iris_open, iris_close",
+            )),
+        }
+    }
+
+    type Err = String;
+}
+
+/// GB/T28181 附录A PTZCmd 8字节指令
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PtzCmd {
+    pub instruction: u8,
+    pub data1: u8,
+    pub data2: u8,
+    // 仅低4位有效, 编码在字节7的高4位
+    pub data3: u8,
+}
+
+impl PtzCmd {
+    pub fn new(instruction: u8, data1: u8, data2: u8, data3: u8) -> Self {
+        PtzCmd {
+            instruction,
+            data1,
+            data2,
+            data3: data3 & 0x0F,
+        }
+    }
+
+    /// 方向/变倍/聚焦/光圈控制, speed 取值 0~255, 变倍速度按比例折算到 0~15
+    pub fn from_command(command: &PtzCommand, speed: u8) -> Self {
+        let zoom_speed = speed >> 4;
+        match command {
+            PtzCommand::Stop => Self::new(0x00, 0, 0, 0),
+            PtzCommand::Right => Self::new(0x01, speed, 0, 0),
+            PtzCommand::Left => Self::new(0x02, speed, 0, 0),
+            PtzCommand::Down => Self::new(0x04, 0, speed, 0),
+            PtzCommand::Up => Self::new(0x08, 0, speed, 0),
+            PtzCommand::RightDown => Self::new(0x05, speed, speed, 0),
+            PtzCommand::LeftDown => Self::new(0x06, speed, speed, 0),
+            PtzCommand::RightUp => Self::new(0x09, speed, speed, 0),
+            PtzCommand::LeftUp => Self::new(0x0A, speed, speed, 0),
+            PtzCommand::ZoomIn => Self::new(0x10, 0, 0, zoom_speed),
+            PtzCommand::ZoomOut => Self::new(0x20, 0, 0, zoom_speed),
+            PtzCommand::FocusFar => Self::new(0x41, speed, 0, 0),
+            PtzCommand::FocusNear => Self::new(0x42, speed, 0, 0),
+            PtzCommand::IrisOpen => Self::new(0x44, 0, speed, 0),
+            PtzCommand::IrisClose => Self::new(0x48, 0, speed, 0),
+        }
+    }
+
+    // 预置位: 设置/调用/删除, preset_id 取值 1~255
+    pub fn preset_set(preset_id: u8) -> Self {
+        Self::new(0x81, 0, preset_id, 0)
+    }
+
+    pub fn preset_call(preset_id: u8) -> Self {
+        Self::new(0x82, 0, preset_id, 0)
+    }
+
+    pub fn preset_delete(preset_id: u8) -> Self {
+        Self::new(0x83, 0, preset_id, 0)
+    }
+
+    // 巡航: preset_id 为 0 时删除整条巡航轨迹
+    pub fn cruise_add_point(cruise_id: u8, preset_id: u8) -> Self {
+        Self::new(0x84, cruise_id, preset_id, 0)
+    }
+
+    pub fn cruise_remove_point(cruise_id: u8, preset_id: u8) -> Self {
+        Self::new(0x85, cruise_id, preset_id, 0)
+    }
+
+    // 巡航速度/停留时间为12位数值, 低8位在字节6, 高4位在字节7高4位
+    pub fn cruise_speed(cruise_id: u8, speed: u16) -> Self {
+        Self::new(0x86, cruise_id, (speed & 0xFF) as u8, (speed >> 8) as u8)
+    }
+
+    pub fn cruise_dwell(cruise_id: u8, seconds: u16) -> Self {
+        Self::new(
+            0x87,
+            cruise_id,
+            (seconds & 0xFF) as u8,
+            (seconds >> 8) as u8,
+        )
+    }
+
+    pub fn cruise_start(cruise_id: u8) -> Self {
+        Self::new(0x88, cruise_id, 0, 0)
+    }
+
+    // 自动扫描: 字节6 为 0 开始扫描, 1 设置左边界, 2 设置右边界
+    pub fn scan_start(scan_id: u8) -> Self {
+        Self::new(0x89, scan_id, 0, 0)
+    }
+
+    pub fn scan_left_border(scan_id: u8) -> Self {
+        Self::new(0x89, scan_id, 1, 0)
+    }
+
+    pub fn scan_right_border(scan_id: u8) -> Self {
+        Self::new(0x89, scan_id, 2, 0)
+    }
+
+    pub fn scan_speed(scan_id: u8, speed: u16) -> Self {
+        Self::new(0x8A, scan_id, (speed & 0xFF) as u8, (speed >> 8) as u8)
+    }
+
+    // 停止巡航/扫描与停止云台动作相同
+    pub fn stop() -> Self {
+        Self::new(0x00, 0, 0, 0)
+    }
+
+    pub fn to_bytes(&self) -> [u8; 8] {
+        let mut bytes = [0u8; 8];
+        bytes[0] = PTZ_CMD_HEADER;
+        let check = ((PTZ_CMD_HEADER >> 4) + (PTZ_CMD_HEADER & 0x0F) + PTZ_CMD_VERSION) & 0x0F;
+        bytes[1] = (PTZ_CMD_VERSION << 4) | check;
+        bytes[2] = (PTZ_CMD_ADDRESS & 0xFF) as u8;
+        bytes[3] = self.instruction;
+        bytes[4] = self.data1;
+        bytes[5] = self.data2;
+        bytes[6] = (self.data3 << 4) | ((PTZ_CMD_ADDRESS >> 8) as u8 & 0x0F);
+        bytes[7] = bytes[..7]
+            .iter()
+            .fold(0u8, |sum, byte| sum.wrapping_add(*byte));
+        bytes
+    }
+}
+
+impl fmt::Display for PtzCmd {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for byte in self.to_bytes() {
+            write!(f, "{:02X}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ControlInfo {
+    #[serde(rename = "ControlPriority")]
+    pub control_priority: u32,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename = "Control")]
+pub struct DeviceControl {
+    #[serde(rename = "CmdType")]
+    pub cmd_type: String,
+    #[serde(rename = "SN")]
+    pub sn: u32,
+    #[serde(rename = "DeviceID")]
+    pub device_id: String,
+    #[serde(rename = "PTZCmd")]
+    pub ptz_cmd: String,
+    #[serde(rename = "Info")]
+    pub info: ControlInfo,
+}
+
+impl DeviceControl {
+    pub fn new(sn: u32, channel_id: &str, ptz_cmd: &PtzCmd) -> Self {
+        DeviceControl {
+            cmd_type: String::from("DeviceControl"),
+            sn,
+            device_id: channel_id.to_string(),
+            ptz_cmd: ptz_cmd.to_string(),
+            info: ControlInfo {
+                control_priority: 5,
+            },
+        }
+    }
+
+    pub fn serialize_to_xml(&self) -> String {
+        match to_string(self) {
+            Ok(s) => s,
+            Err(e) => {
+                tracing::error!("serde_xml_rs::to_string({:?}) error, e: {:?}", self, e);
+                String::new()
+            }
+        }
+    }
+
+    pub fn deserialize_from_xml(s: String) -> Self {
+        match from_str(s.as_str()) {
+            Ok(k) => k,
+            Err(e) => {
+                tracing::error!("serde_xml_rs::from_str({}) error, e: {:?}", s, e);
+                DeviceControl::default()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DeviceControl, PtzCmd, PtzCommand};
+
+    #[test]
+    fn test_ptz_cmd_up() {
+        let cmd = PtzCmd::from_command(&PtzCommand::Up, 0x80);
+        assert_eq!(cmd.to_string(), "A50F01080080003D");
+    }
+
+    #[test]
+    fn test_ptz_cmd_zoom_in() {
+        let cmd = PtzCmd::from_command(&PtzCommand::ZoomIn, 0xFF);
+        assert_eq!(cmd.to_string(), "A50F01100000F0B5");
+    }
+
+    #[test]
+    fn test_ptz_cmd_stop() {
+        let cmd = PtzCmd::from_command(&PtzCommand::Stop, 0xFF);
+        assert_eq!(cmd.to_string(), "A50F0100000000B5");
+    }
+
+    #[test]
+    fn test_ptz_cmd_preset_call() {
+        assert_eq!(PtzCmd::preset_call(3).to_string(), "A50F01820003003A");
+    }
+
+    #[test]
+    fn test_ptz_cmd_cruise_speed() {
+        assert_eq!(
+            PtzCmd::cruise_speed(1, 0x234).to_string(),
+            "A50F018601342090"
+        );
+    }
+
+    #[test]
+    fn test_device_control_xml() {
+        let cmd = PtzCmd::from_command(&PtzCommand::Left, 0x20);
+        let xml = DeviceControl::new(7, "34020000001320000001", &cmd).serialize_to_xml();
+        assert!(xml.contains("<CmdType>DeviceControl</CmdType>"));
+        assert!(xml.contains("<PTZCmd>A50F0102200000D7</PTZCmd>"));
+    }
+}
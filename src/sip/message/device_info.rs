@@ -3,13 +3,13 @@ use serde_xml_rs::{from_str, to_string};
 
 use tracing;
 
-#[derive(Debug, Default, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct AlarmStatus {
     #[serde(rename = "Num")]
     num: Option<String>,
 }
 
-#[derive(Debug, Default, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct DeviceInfo {
     #[serde(rename = "CmdType")]
     pub cmd_type: String,
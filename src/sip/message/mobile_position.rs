@@ -0,0 +1,112 @@
+use serde::{Deserialize, Serialize};
+use serde_xml_rs::{from_str, to_string};
+
+use tracing;
+
+/// 车载、单兵等移动设备上报的位置, 通过订阅后的 NOTIFY 或 MESSAGE 送达
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(rename = "Notify")]
+pub struct MobilePosition {
+    #[serde(rename = "CmdType")]
+    pub cmd_type: String,
+    #[serde(rename = "SN")]
+    pub sn: u32,
+    #[serde(rename = "DeviceID")]
+    pub device_id: String,
+    // 设备本地时间, 格式 2024-05-01T08:30:00
+    #[serde(rename = "Time", default)]
+    pub time: String,
+    #[serde(rename = "Longitude", default)]
+    pub longitude: f64,
+    #[serde(rename = "Latitude", default)]
+    pub latitude: f64,
+    // 单位 km/h
+    #[serde(rename = "Speed", default)]
+    pub speed: Option<f64>,
+    // 相对正北的顺时针角度
+    #[serde(rename = "Direction", default)]
+    pub direction: Option<f64>,
+    // 单位 m
+    #[serde(rename = "Altitude", default)]
+    pub altitude: Option<f64>,
+}
+
+impl MobilePosition {
+    pub fn deserialize_from_xml(s: String) -> Self {
+        match from_str(s.as_str()) {
+            Ok(k) => k,
+            Err(e) => {
+                tracing::error!("serde_xml_rs::from_str({}) error, e: {:?}", s, e);
+                MobilePosition::default()
+            }
+        }
+    }
+}
+
+/// 移动位置订阅, Interval 为设备上报间隔
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename = "Query")]
+pub struct MobilePositionQuery {
+    #[serde(rename = "CmdType")]
+    pub cmd_type: String,
+    #[serde(rename = "SN")]
+    pub sn: u32,
+    #[serde(rename = "DeviceID")]
+    pub device_id: String,
+    // 单位秒
+    #[serde(rename = "Interval")]
+    pub interval: u32,
+}
+
+impl MobilePositionQuery {
+    pub fn new(sn: u32, gb_code: &str, interval: u32) -> Self {
+        MobilePositionQuery {
+            cmd_type: String::from("MobilePosition"),
+            sn,
+            device_id: gb_code.to_string(),
+            interval,
+        }
+    }
+
+    pub fn serialize_to_xml(&self) -> String {
+        match to_string(self) {
+            Ok(s) => s,
+            Err(e) => {
+                tracing::error!("serde_xml_rs::to_string({:?}) error, e: {:?}", self, e);
+                String::new()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MobilePosition, MobilePositionQuery};
+
+    #[test]
+    fn test_mobile_position_deserialize() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<Notify>
+<CmdType>MobilePosition</CmdType>
+<SN>5</SN>
+<DeviceID>34020000001320000001</DeviceID>
+<Time>2024-05-01T08:30:00</Time>
+<Longitude>120.123456</Longitude>
+<Latitude>30.654321</Latitude>
+<Speed>36.5</Speed>
+<Direction>90</Direction>
+<Altitude>12</Altitude>
+</Notify>"#;
+        let position = MobilePosition::deserialize_from_xml(xml.to_string());
+        assert_eq!(position.sn, 5);
+        assert_eq!(position.time, "2024-05-01T08:30:00");
+        assert_eq!(position.longitude, 120.123456);
+        assert_eq!(position.latitude, 30.654321);
+        assert_eq!(position.speed, Some(36.5));
+        assert_eq!(position.direction, Some(90.0));
+
+        let xml = MobilePositionQuery::new(3, "34020000001320000001", 5).serialize_to_xml();
+        assert!(xml.contains("<CmdType>MobilePosition</CmdType>"));
+        assert!(xml.contains("<Interval>5</Interval>"));
+    }
+}
@@ -8,7 +8,25 @@ pub mod device_status;
 pub use device_status::{DeviceStatus, DeviceStatusQuery};
 
 pub mod catalog;
-pub use catalog::{Catalog, CatalogQuery, Device, DeviceList};
+pub use catalog::{Catalog, CatalogEvent, CatalogQuery, Device, DeviceList};
+
+pub mod device_control;
+pub use device_control::{DeviceControl, PtzCmd, PtzCommand};
+
+pub mod preset_query;
+pub use preset_query::{PresetItem, PresetList, PresetQuery, PresetQueryResponse};
+
+pub mod record_info;
+pub use record_info::{RecordInfo, RecordInfoQuery, RecordItem, RecordList};
 
 pub mod sdp;
-pub use sdp::{generate_media_sdp, SdpSessionType};
+pub use sdp::{generate_audio_sdp, generate_media_sdp, generate_ssrc, SdpAnswer, SdpSessionType};
+
+pub mod alarm;
+pub use alarm::{Alarm, AlarmQuery};
+
+pub mod mobile_position;
+pub use mobile_position::{MobilePosition, MobilePositionQuery};
+
+pub mod broadcast;
+pub use broadcast::{Broadcast, BroadcastResponse};
@@ -0,0 +1,98 @@
+use serde::{Deserialize, Serialize};
+use serde_xml_rs::{from_str, to_string};
+
+use tracing;
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(rename = "Response")]
+pub struct PresetQueryResponse {
+    #[serde(rename = "CmdType")]
+    pub cmd_type: String,
+    #[serde(rename = "SN")]
+    pub sn: u32,
+    #[serde(rename = "DeviceID")]
+    pub device_id: String,
+    #[serde(rename = "PresetList", default)]
+    pub preset_list: PresetList,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(rename = "PresetList")]
+pub struct PresetList {
+    #[serde(rename = "Num", default)]
+    pub num: u32,
+    #[serde(rename = "Item", default)]
+    pub items: Vec<PresetItem>,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(rename = "Item")]
+pub struct PresetItem {
+    #[serde(rename = "PresetID")]
+    pub preset_id: String,
+    #[serde(rename = "PresetName", default)]
+    pub preset_name: String,
+}
+
+impl PresetQueryResponse {
+    pub fn serialize_to_xml(&self) -> String {
+        match to_string(self) {
+            Ok(s) => s,
+            Err(e) => {
+                tracing::error!("serde_xml_rs::to_string({:?}) error, e: {:?}", self, e);
+                String::new()
+            }
+        }
+    }
+
+    pub fn deserialize_from_xml(s: String) -> Self {
+        match from_str(s.as_str()) {
+            Ok(k) => k,
+            Err(e) => {
+                tracing::error!("serde_xml_rs::from_str({}) error, e: {:?}", s, e);
+                PresetQueryResponse::default()
+            }
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename = "Query")]
+pub struct PresetQuery {
+    #[serde(rename = "CmdType")]
+    pub cmd_type: String,
+    #[serde(rename = "SN")]
+    pub sn: u32,
+    #[serde(rename = "DeviceID")]
+    pub device_id: String,
+}
+
+impl PresetQuery {
+    pub fn new(sn: u32, channel_id: &str) -> Self {
+        PresetQuery {
+            cmd_type: String::from("PresetQuery"),
+            sn,
+            device_id: channel_id.to_string(),
+        }
+    }
+
+    pub fn serialize_to_xml(&self) -> String {
+        match to_string(self) {
+            Ok(s) => s,
+            Err(e) => {
+                tracing::error!("serde_xml_rs::to_string({:?}) error, e: {:?}", self, e);
+                String::new()
+            }
+        }
+    }
+
+    pub fn deserialize_from_xml(s: String) -> Self {
+        match from_str(s.as_str()) {
+            Ok(k) => k,
+            Err(e) => {
+                tracing::error!("serde_xml_rs::from_str({}) error, e: {:?}", s, e);
+                PresetQuery::default()
+            }
+        }
+    }
+}
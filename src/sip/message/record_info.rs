@@ -0,0 +1,128 @@
+use serde::{Deserialize, Serialize};
+use serde_xml_rs::{from_str, to_string};
+
+use tracing;
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(rename = "Response")]
+pub struct RecordInfo {
+    #[serde(rename = "CmdType")]
+    pub cmd_type: String,
+    #[serde(rename = "SN")]
+    pub sn: u32,
+    #[serde(rename = "DeviceID")]
+    pub device_id: String,
+    #[serde(rename = "Name", default)]
+    pub name: String,
+    #[serde(rename = "SumNum", default)]
+    pub sum_num: u32,
+    #[serde(rename = "RecordList", default)]
+    pub record_list: RecordList,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(rename = "RecordList")]
+pub struct RecordList {
+    #[serde(rename = "Num", default)]
+    pub num: u32,
+    #[serde(rename = "Item", default)]
+    pub items: Vec<RecordItem>,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(rename = "Item")]
+pub struct RecordItem {
+    #[serde(rename = "DeviceID", default)]
+    pub device_id: String,
+    #[serde(rename = "Name", default)]
+    pub name: String,
+    #[serde(rename = "FilePath", default)]
+    pub file_path: String,
+    #[serde(rename = "Address", default)]
+    pub address: String,
+    #[serde(rename = "StartTime", default)]
+    pub start_time: String,
+    #[serde(rename = "EndTime", default)]
+    pub end_time: String,
+    #[serde(rename = "Secrecy", default)]
+    pub secrecy: u32,
+    #[serde(rename = "Type", default)]
+    pub record_type: String,
+    #[serde(rename = "RecorderID", default)]
+    pub recorder_id: String,
+}
+
+impl RecordInfo {
+    pub fn serialize_to_xml(&self) -> String {
+        match to_string(self) {
+            Ok(s) => s,
+            Err(e) => {
+                tracing::error!("serde_xml_rs::to_string({:?}) error, e: {:?}", self, e);
+                String::new()
+            }
+        }
+    }
+
+    pub fn deserialize_from_xml(s: String) -> Self {
+        match from_str(s.as_str()) {
+            Ok(k) => k,
+            Err(e) => {
+                tracing::error!("serde_xml_rs::from_str({}) error, e: {:?}", s, e);
+                RecordInfo::default()
+            }
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename = "Query")]
+pub struct RecordInfoQuery {
+    #[serde(rename = "CmdType")]
+    pub cmd_type: String,
+    #[serde(rename = "SN")]
+    pub sn: u32,
+    #[serde(rename = "DeviceID")]
+    pub device_id: String,
+    #[serde(rename = "StartTime")]
+    pub start_time: String,
+    #[serde(rename = "EndTime")]
+    pub end_time: String,
+    #[serde(rename = "Secrecy")]
+    pub secrecy: u32,
+    #[serde(rename = "Type")]
+    pub record_type: String,
+}
+
+impl RecordInfoQuery {
+    pub fn new(sn: u32, channel_id: &str, start_time: &str, end_time: &str) -> Self {
+        RecordInfoQuery {
+            cmd_type: String::from("RecordInfo"),
+            sn,
+            device_id: channel_id.to_string(),
+            start_time: start_time.to_string(),
+            end_time: end_time.to_string(),
+            secrecy: 0,
+            record_type: String::from("all"),
+        }
+    }
+
+    pub fn serialize_to_xml(&self) -> String {
+        match to_string(self) {
+            Ok(s) => s,
+            Err(e) => {
+                tracing::error!("serde_xml_rs::to_string({:?}) error, e: {:?}", self, e);
+                String::new()
+            }
+        }
+    }
+
+    pub fn deserialize_from_xml(s: String) -> Self {
+        match from_str(s.as_str()) {
+            Ok(k) => k,
+            Err(e) => {
+                tracing::error!("serde_xml_rs::from_str({}) error, e: {:?}", s, e);
+                RecordInfoQuery::default()
+            }
+        }
+    }
+}
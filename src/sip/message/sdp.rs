@@ -6,6 +6,7 @@ use sdp_rs;
 
 use vec1::vec1;
 
+#[derive(Debug, Clone, Copy)]
 pub enum SdpSessionType {
     Play,
     Playback,
@@ -157,22 +158,273 @@ pub fn generate_media_sdp(
     };
 
     match session_type {
-        SdpSessionType::Play => {
-            // play ssrc 0+gbcore[4:8]+random[4]
-            let mut rng = rand::thread_rng();
-            let gbcore_part = &device_gb_code[4..8];
-            let random_part = rng.gen_range(0..10000);
-            let ssrc = format!("0{}{:04}", gbcore_part, random_part);
-            format!("{}y={}\r\n", session_desc, ssrc)
-        }
-        SdpSessionType::Playback => {
-            // play ssrc 1+gbcore[4:8]+random[4]
-            let mut rng = rand::thread_rng();
-            let gbcore_part = &device_gb_code[4..8];
-            let random_part = rng.gen_range(0..10000);
-            let ssrc = format!("1{}{:04}", gbcore_part, random_part);
-            format!("{}y={}\r\n", session_desc, ssrc)
+        SdpSessionType::Play | SdpSessionType::Playback => {
+            format!(
+                "{}y={}\r\n",
+                session_desc,
+                generate_ssrc(session_type, device_gb_code)
+            )
         }
         _ => session_desc.to_string(),
     }
 }
+
+// ssrc: 实时 0 / 历史 1 + gbcore[4:8] + random[4]
+pub fn generate_ssrc(session_type: SdpSessionType, device_gb_code: &str) -> String {
+    let prefix = match session_type {
+        SdpSessionType::Playback | SdpSessionType::Download => 1,
+        _ => 0,
+    };
+    let mut rng = rand::thread_rng();
+    let gbcore_part = &device_gb_code[4..8];
+    let random_part = rng.gen_range(0..10000);
+    format!("{}{}{:04}", prefix, gbcore_part, random_part)
+}
+
+// 对讲向设备提供的全部音频负载, 由设备应答选定
+pub static TALK_PAYLOAD_TYPES: [u8; 3] = [8, 0, 97];
+
+// 本平台支持的音频负载: G.711A、G.711U、AAC
+pub fn audio_rtpmap(payload_type: u8) -> Option<sdp_rs::lines::attribute::Rtpmap> {
+    // 单声道省略声道数, sdp_rs 输出声道数的格式有误
+    let encoding_name = match payload_type {
+        8 => "PCMA",
+        0 => "PCMU",
+        97 => "MPEG4-GENERIC",
+        _ => return None,
+    };
+    Some(sdp_rs::lines::attribute::Rtpmap {
+        payload_type: payload_type as u32,
+        encoding_name: String::from(encoding_name),
+        clock_rate: 8000,
+        encoding_params: None,
+    })
+}
+
+// 语音广播与对讲的音频 SDP, 广播只向设备发送, 对讲双向收发
+pub fn generate_audio_sdp(
+    media_server_ip: &str,
+    media_server_port: u16,
+    username: &str,
+    setup_type: &str,
+    session_type: SdpSessionType,
+    payload_types: &[u8],
+    ssrc: &str,
+) -> String {
+    let payload_types: Vec<u8> = payload_types
+        .iter()
+        .copied()
+        .filter(|payload_type| audio_rtpmap(*payload_type).is_some())
+        .collect();
+    let mut attributes: Vec<sdp_rs::lines::Attribute> = payload_types
+        .iter()
+        .filter_map(|payload_type| audio_rtpmap(*payload_type))
+        .map(sdp_rs::lines::Attribute::Rtpmap)
+        .collect();
+    attributes.push(match session_type {
+        SdpSessionType::Talk => sdp_rs::lines::Attribute::Sendrecv,
+        _ => sdp_rs::lines::Attribute::Sendonly,
+    });
+    if !setup_type.is_empty() {
+        attributes.push(sdp_rs::lines::Attribute::Other(
+            String::from("setup"),
+            Some(setup_type.to_string()),
+        ));
+        attributes.push(sdp_rs::lines::Attribute::Other(
+            String::from("connection"),
+            Some(String::from("new")),
+        ));
+    }
+
+    let connection = sdp_rs::lines::Connection {
+        nettype: sdp_rs::lines::common::Nettype::In,
+        addrtype: sdp_rs::lines::common::Addrtype::Ip4,
+        connection_address: sdp_rs::lines::connection::ConnectionAddress {
+            base: std::net::IpAddr::V4(std::net::Ipv4Addr::from_str(media_server_ip).unwrap()),
+            ttl: None,
+            numaddr: None,
+        },
+    };
+
+    // media description
+    let media_desc = sdp_rs::MediaDescription {
+        media: sdp_rs::lines::Media {
+            media: sdp_rs::lines::media::MediaType::Audio,
+            port: media_server_port,
+            num_of_ports: None,
+            proto: if setup_type.is_empty() {
+                sdp_rs::lines::media::ProtoType::RtpAvp
+            } else {
+                sdp_rs::lines::media::ProtoType::Other(String::from("TCP/RTP/AVP"))
+            },
+            fmt: payload_types
+                .iter()
+                .map(|payload_type| payload_type.to_string())
+                .collect::<Vec<_>>()
+                .join(" "),
+        },
+        attributes,
+        info: None,
+        connections: vec![connection.clone()],
+        bandwidths: vec![],
+        key: None,
+    };
+
+    let session_desc = sdp_rs::SessionDescription {
+        version: sdp_rs::lines::Version::V0,
+        origin: sdp_rs::lines::Origin {
+            username: username.to_string(),
+            sess_id: String::from("0"),
+            sess_version: String::from("0"),
+            nettype: sdp_rs::lines::common::Nettype::In,
+            addrtype: sdp_rs::lines::common::Addrtype::Ip4,
+            unicast_address: std::net::IpAddr::V4(
+                std::net::Ipv4Addr::from_str(media_server_ip).unwrap(),
+            ),
+        },
+        session_name: sdp_rs::lines::SessionName::new(session_type.to_string()),
+        session_info: None,
+        uri: None,
+        times: vec1![sdp_rs::Time {
+            active: sdp_rs::lines::Active { start: 0, stop: 0 },
+            repeat: vec![],
+            zone: None,
+        }],
+        media_descriptions: vec![media_desc],
+        attributes: vec![],
+        emails: vec![],
+        phones: vec![],
+        connection: Some(connection),
+        bandwidths: vec![],
+        key: None,
+    };
+
+    if ssrc.is_empty() {
+        session_desc.to_string()
+    } else {
+        format!("{}y={}\r\n", session_desc, ssrc)
+    }
+}
+
+// 设备应答 INVITE 的 SDP 中协商出的媒体参数
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SdpAnswer {
+    pub ssrc: String,
+    // RTP/AVP 或 TCP/RTP/AVP
+    pub transport: String,
+    // TCP 传输时的 a=setup, active/passive
+    pub setup: String,
+    pub media_ip: String,
+    pub media_port: u16,
+    // m= 行列出的负载类型
+    pub payloads: Vec<u8>,
+}
+
+impl SdpAnswer {
+    // 设备常带 y=/f= 等 GB28181 扩展行, 逐行解析, 只取第一个媒体
+    pub fn parse(s: &str) -> Self {
+        let mut answer = SdpAnswer::default();
+        let mut in_media = false;
+        for line in s.lines().map(|line| line.trim()) {
+            if let Some(v) = line.strip_prefix("m=") {
+                if in_media {
+                    break;
+                }
+                in_media = true;
+                let mut parts = v.split_whitespace().skip(1);
+                answer.media_port = parts.next().and_then(|p| p.parse().ok()).unwrap_or(0);
+                answer.transport = parts.next().unwrap_or_default().to_string();
+                answer.payloads = parts.filter_map(|p| p.parse().ok()).collect();
+            } else if let Some(v) = line.strip_prefix("c=") {
+                // 媒体级 c= 覆盖会话级
+                if let Some(ip) = v.split_whitespace().nth(2) {
+                    answer.media_ip = ip.to_string();
+                }
+            } else if let Some(v) = line.strip_prefix("a=setup:") {
+                answer.setup = v.to_string();
+            } else if let Some(v) = line.strip_prefix("y=") {
+                answer.ssrc = v.to_string();
+            }
+        }
+        answer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{generate_audio_sdp, generate_ssrc, SdpAnswer, SdpSessionType};
+
+    #[test]
+    fn test_sdp_answer_parse() {
+        let sdp = "v=0\r\n\
+            o=34020000001320000001 0 0 IN IP4 192.168.1.64\r\n\
+            s=Play\r\n\
+            c=IN IP4 192.168.1.64\r\n\
+            t=0 0\r\n\
+            m=video 15060 TCP/RTP/AVP 96\r\n\
+            c=IN IP4 192.168.1.65\r\n\
+            a=sendonly\r\n\
+            a=rtpmap:96 PS/90000\r\n\
+            a=setup:passive\r\n\
+            y=0100000001\r\n\
+            f=\r\n";
+        assert_eq!(
+            SdpAnswer::parse(sdp),
+            SdpAnswer {
+                ssrc: String::from("0100000001"),
+                transport: String::from("TCP/RTP/AVP"),
+                setup: String::from("passive"),
+                media_ip: String::from("192.168.1.65"),
+                media_port: 15060,
+                payloads: vec![96],
+            }
+        );
+    }
+
+    #[test]
+    fn test_generate_audio_sdp() {
+        let sdp = generate_audio_sdp(
+            "192.168.1.10",
+            30000,
+            "34020000002000000001",
+            "",
+            SdpSessionType::Play,
+            &[8, 0, 101],
+            "0100000002",
+        );
+        assert!(sdp.contains("m=audio 30000 RTP/AVP 8 0\r\n"));
+        assert!(sdp.contains("a=rtpmap:8 PCMA/8000\r\n"));
+        assert!(sdp.contains("a=rtpmap:0 PCMU/8000\r\n"));
+        assert!(sdp.contains("a=sendonly\r\n"));
+        assert!(sdp.ends_with("y=0100000002\r\n"));
+
+        let answer = SdpAnswer::parse(&sdp);
+        assert_eq!(answer.media_ip, "192.168.1.10");
+        assert_eq!(answer.payloads, vec![8, 0]);
+
+        let sdp = generate_audio_sdp(
+            "192.168.1.10",
+            30002,
+            "34020000002000000001",
+            "passive",
+            SdpSessionType::Talk,
+            &[97],
+            "",
+        );
+        assert!(sdp.contains("m=audio 30002 TCP/RTP/AVP 97\r\n"));
+        assert!(sdp.contains("a=rtpmap:97 MPEG4-GENERIC/8000\r\n"));
+        assert!(sdp.contains("a=sendrecv\r\n"));
+        assert!(sdp.contains("a=setup:passive\r\n"));
+        assert!(!sdp.contains("y="));
+    }
+
+    #[test]
+    fn test_generate_ssrc() {
+        let ssrc = generate_ssrc(SdpSessionType::Talk, "34020000001320000001");
+        assert_eq!(ssrc.len(), 9);
+        assert!(ssrc.starts_with("00000"));
+        assert!(
+            generate_ssrc(SdpSessionType::Playback, "34020000001320000001").starts_with("10000")
+        );
+    }
+}
@@ -3,3 +3,4 @@ pub mod message;
 pub mod request;
 pub mod utils;
 pub mod server;
+pub mod cascade;
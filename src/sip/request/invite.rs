@@ -21,6 +21,8 @@ pub struct SendInviteParams {
     pub setup_type: String,
     pub start_ts: u64,
     pub stop_ts: u64,
+    // 设备返回 401/407 后重发时携带的认证头
+    pub authorization: Option<rsip::Header>,
 }
 
 impl SipHandler {
@@ -40,18 +42,30 @@ impl SipHandler {
             setup_type,
             start_ts,
             stop_ts,
+            authorization,
         } = params;
 
-        // body
-        let str_body = sip::message::generate_media_sdp(
-            &media_server_ip,
-            media_server_port,
-            &gb_code,
-            &setup_type,
-            session_type,
-            start_ts,
-            stop_ts,
-        );
+        // body, 对讲只协商音频
+        let str_body = match session_type {
+            sip::message::SdpSessionType::Talk => sip::message::generate_audio_sdp(
+                &media_server_ip,
+                media_server_port,
+                &gb_code,
+                &setup_type,
+                session_type,
+                &sip::message::sdp::TALK_PAYLOAD_TYPES,
+                &sip::message::generate_ssrc(session_type, &gb_code),
+            ),
+            _ => sip::message::generate_media_sdp(
+                &media_server_ip,
+                media_server_port,
+                &gb_code,
+                &setup_type,
+                session_type,
+                start_ts,
+                stop_ts,
+            ),
+        };
 
         let bin_body = str_body.as_bytes().to_vec();
 
@@ -66,6 +80,7 @@ impl SipHandler {
             &channel_id,
             &gb_code,
             tcp_stream.as_ref(),
+            authorization,
             &str_body,
         );
 
@@ -76,12 +91,40 @@ impl SipHandler {
             .await
     }
 
+    // 级联点播: 直接使用上级平台提供的 SDP 向设备发起 INVITE
+    #[allow(clippy::too_many_arguments)]
+    pub async fn send_invite_with_sdp(
+        &self,
+        device_addr: SocketAddr,
+        tcp_stream: Option<Arc<Mutex<OwnedWriteHalf>>>,
+        transaction: SipTransaction,
+        channel_id: &str,
+        gb_code: &str,
+        str_body: String,
+        authorization: Option<rsip::Header>,
+    ) -> bool {
+        let bin_body = str_body.as_bytes().to_vec();
+        let headers = self.build_headers(
+            transaction,
+            channel_id,
+            gb_code,
+            tcp_stream.as_ref(),
+            authorization,
+            &str_body,
+        );
+        let request = self.build_request(gb_code, &headers, &bin_body);
+
+        self.socket_send_request_with_body(device_addr, tcp_stream, request, bin_body, str_body)
+            .await
+    }
+
     fn build_headers(
         &self,
         transaction: SipTransaction,
         channel_id: &str,
         gb_code: &str,
         tcp_stream: Option<&Arc<Mutex<OwnedWriteHalf>>>,
+        authorization: Option<rsip::Header>,
         str_body: &str,
     ) -> rsip::Headers {
         let mut headers: rsip::Headers = Default::default();
@@ -124,6 +167,9 @@ impl SipHandler {
         );
         headers.push(rsip::headers::Supported::from(String::from("100rel")).into());
         headers.push(rsip::headers::Subject::from(format!("{channel_id}:0")).into());
+        if let Some(authorization) = authorization {
+            headers.push(authorization);
+        }
         headers.push(
             rsip::headers::UserAgent::from(format!(
                 "{} {}",
@@ -145,15 +191,20 @@ impl SipHandler {
     ) -> rsip::Request {
         rsip::Request {
             method: rsip::Method::Invite,
-            uri: rsip::Uri {
-                scheme: Some(rsip::Scheme::Sip),
-                auth: Some((gb_code.to_string(), Option::<String>::None).into()),
-                host_with_port: rsip::Domain::from(self.domain.clone()).into(),
-                ..Default::default()
-            },
+            uri: self.invite_uri(gb_code),
             version: rsip::Version::V2,
             headers: headers.clone(),
             body: Default::default(),
         }
     }
+
+    // INVITE 的 Request-URI, 计算认证摘要时也需要
+    pub fn invite_uri(&self, gb_code: &str) -> rsip::Uri {
+        rsip::Uri {
+            scheme: Some(rsip::Scheme::Sip),
+            auth: Some((gb_code.to_string(), Option::<String>::None).into()),
+            host_with_port: rsip::Domain::from(self.domain.clone()).into(),
+            ..Default::default()
+        }
+    }
 }
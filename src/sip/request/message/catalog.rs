@@ -1,4 +1,5 @@
 use crate::sip::handler::SipHandler;
+use crate::utils::config::UpstreamPlatform;
 use crate::{sip, version};
 
 impl SipHandler {
@@ -9,10 +10,10 @@ impl SipHandler {
         transport: rsip::Transport,
         branch: &String,
         gb_code: &String,
+        sn: u32,
     ) -> bool {
         // body
-        let text_body = sip::message::CatalogQuery::new(self.store.add_fetch_global_sn(), gb_code)
-            .serialize_to_xml();
+        let text_body = sip::message::CatalogQuery::new(sn, gb_code).serialize_to_xml();
         let bin_body = self.encode_body(&text_body);
 
         // headers
@@ -63,4 +64,75 @@ impl SipHandler {
         self.socket_send_request_with_body(device_addr, tcp_stream, request, bin_body, text_body)
             .await
     }
+
+    // 向上级平台应答目录, 单包携带部分条目, SumNum 为总数
+    pub async fn send_catalog_response(
+        &self,
+        platform: &UpstreamPlatform,
+        upstream_addr: std::net::SocketAddr,
+        branch: &String,
+        sn: u32,
+        sum_num: u32,
+        items: Vec<sip::message::Device>,
+    ) -> bool {
+        // body
+        let text_body = sip::message::Catalog {
+            cmd_type: String::from("Catalog"),
+            sn,
+            device_id: self.id.clone(),
+            sum_num,
+            device_list: sip::message::DeviceList {
+                num: items.len() as u32,
+                items,
+            },
+        }
+        .serialize_to_xml();
+        let bin_body = self.encode_body(&text_body);
+
+        // headers
+        let mut headers: rsip::Headers = Default::default();
+        headers.push(self.via(rsip::Transport::Udp, branch).into());
+        headers.push(rsip::headers::MaxForwards::default().into());
+        headers.push(self.from_new().into());
+        headers.push(
+            self.to_new_with_domain(&platform.id, &platform.domain)
+                .into(),
+        );
+        headers.push(self.caller_id_new().into());
+        headers.push(
+            rsip::typed::CSeq {
+                seq: self.store.add_fetch_global_sequence(),
+                method: rsip::Method::Message,
+            }
+            .into(),
+        );
+        headers.push(
+            rsip::headers::UserAgent::from(format!(
+                "{} {}",
+                version::APP_NAME,
+                version::APP_VERSION
+            ))
+            .into(),
+        );
+        headers.push(
+            rsip::typed::ContentType(rsip::headers::typed::MediaType::Other(
+                "Application/MANSCDP+xml".into(),
+                vec![],
+            ))
+            .into(),
+        );
+        headers.push(rsip::headers::ContentLength::from(bin_body.len() as u32).into());
+
+        // request
+        let request = rsip::Request {
+            method: rsip::Method::Message,
+            uri: self.upstream_uri(platform),
+            version: rsip::Version::V2,
+            headers,
+            body: Default::default(),
+        };
+
+        self.socket_send_request_with_body(upstream_addr, None, request, bin_body, text_body)
+            .await
+    }
 }
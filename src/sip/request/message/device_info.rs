@@ -9,11 +9,10 @@ impl SipHandler {
         transport: rsip::Transport,
         branch: &String,
         gb_code: &String,
+        sn: u32,
     ) -> bool {
         // body
-        let text_body =
-            sip::message::DeviceInfoQuery::new(self.store.add_fetch_global_sn(), gb_code)
-                .serialize_to_xml();
+        let text_body = sip::message::DeviceInfoQuery::new(sn, gb_code).serialize_to_xml();
         let bin_body = self.encode_body(&text_body);
 
         // headers
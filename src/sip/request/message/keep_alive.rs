@@ -0,0 +1,69 @@
+use crate::sip::handler::SipHandler;
+use crate::utils::config::UpstreamPlatform;
+use crate::{sip, version};
+
+impl SipHandler {
+    pub async fn send_keep_alive(
+        &self,
+        platform: &UpstreamPlatform,
+        upstream_addr: std::net::SocketAddr,
+        branch: &String,
+        caller_id: &String,
+    ) -> bool {
+        // body
+        let text_body = sip::message::KeepAlive {
+            cmd_type: String::from("Keepalive"),
+            sn: self.store.add_fetch_global_sn(),
+            device_id: self.id.clone(),
+            status: String::from("OK"),
+        }
+        .serialize_to_xml();
+        let bin_body = self.encode_body(&text_body);
+
+        // headers
+        let mut headers: rsip::Headers = Default::default();
+        headers.push(self.via(rsip::Transport::Udp, branch).into());
+        headers.push(rsip::headers::MaxForwards::default().into());
+        headers.push(self.from_new().into());
+        headers.push(
+            self.to_new_with_domain(&platform.id, &platform.domain)
+                .into(),
+        );
+        headers.push(self.caller_id_from_str(caller_id).into());
+        headers.push(
+            rsip::typed::CSeq {
+                seq: self.store.add_fetch_global_sequence(),
+                method: rsip::Method::Message,
+            }
+            .into(),
+        );
+        headers.push(
+            rsip::headers::UserAgent::from(format!(
+                "{} {}",
+                version::APP_NAME,
+                version::APP_VERSION
+            ))
+            .into(),
+        );
+        headers.push(
+            rsip::typed::ContentType(rsip::headers::typed::MediaType::Other(
+                "Application/MANSCDP+xml".into(),
+                vec![],
+            ))
+            .into(),
+        );
+        headers.push(rsip::headers::ContentLength::from(bin_body.len() as u32).into());
+
+        // request
+        let request = rsip::Request {
+            method: rsip::Method::Message,
+            uri: self.upstream_uri(platform),
+            version: rsip::Version::V2,
+            headers,
+            body: Default::default(),
+        };
+
+        self.socket_send_request_with_body(upstream_addr, None, request, bin_body, text_body)
+            .await
+    }
+}
@@ -1,3 +1,8 @@
+pub mod broadcast;
 pub mod catalog;
+pub mod device_control;
 pub mod device_info;
 pub mod device_status;
+pub mod keep_alive;
+pub mod preset_query;
+pub mod record_info;
@@ -0,0 +1,71 @@
+use crate::sip::handler::SipHandler;
+use crate::{sip, version};
+
+impl SipHandler {
+    #[allow(clippy::too_many_arguments)]
+    pub async fn send_record_info_query(
+        &self,
+        device_addr: std::net::SocketAddr,
+        tcp_stream: Option<std::sync::Arc<tokio::sync::Mutex<tokio::net::tcp::OwnedWriteHalf>>>,
+        transport: rsip::Transport,
+        branch: &String,
+        gb_code: &String,
+        channel_id: &str,
+        sn: u32,
+        start_time: &str,
+        end_time: &str,
+    ) -> bool {
+        // body
+        let text_body = sip::message::RecordInfoQuery::new(sn, channel_id, start_time, end_time)
+            .serialize_to_xml();
+        let bin_body = self.encode_body(&text_body);
+
+        // headers
+        let mut headers: rsip::Headers = Default::default();
+        headers.push(self.via(transport, branch).into());
+        headers.push(rsip::headers::MaxForwards::default().into());
+        headers.push(self.from_new().into());
+        headers.push(self.to_new(gb_code).into());
+        headers.push(self.caller_id_new().into());
+        headers.push(
+            rsip::typed::CSeq {
+                seq: self.store.add_fetch_global_sequence(),
+                method: rsip::Method::Message,
+            }
+            .into(),
+        );
+        headers.push(
+            rsip::headers::UserAgent::from(format!(
+                "{} {}",
+                version::APP_NAME,
+                version::APP_VERSION
+            ))
+            .into(),
+        );
+        headers.push(
+            rsip::typed::ContentType(rsip::headers::typed::MediaType::Other(
+                "Application/MANSCDP+xml".into(),
+                vec![],
+            ))
+            .into(),
+        );
+        headers.push(rsip::headers::ContentLength::from(bin_body.len() as u32).into());
+
+        // request
+        let request = rsip::Request {
+            method: rsip::Method::Message,
+            uri: rsip::Uri {
+                scheme: Some(rsip::Scheme::Sip),
+                auth: Some((gb_code.clone(), Option::<String>::None).into()),
+                host_with_port: rsip::Domain::from(self.domain.clone()).into(),
+                ..Default::default()
+            },
+            version: rsip::Version::V2,
+            headers,
+            body: Default::default(),
+        };
+
+        self.socket_send_request_with_body(device_addr, tcp_stream, request, bin_body, text_body)
+            .await
+    }
+}
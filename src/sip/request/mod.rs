@@ -1,3 +1,5 @@
 pub mod bye;
 pub mod message;
 pub mod invite;
+pub mod register;
+pub mod subscribe;
@@ -0,0 +1,69 @@
+use rsip::{self, prelude::UntypedHeader};
+
+use crate::sip::handler::{SipHandler, SipTransaction};
+use crate::utils::config::UpstreamPlatform;
+use crate::version;
+
+impl SipHandler {
+    pub fn upstream_uri(&self, platform: &UpstreamPlatform) -> rsip::Uri {
+        rsip::Uri {
+            scheme: Some(rsip::Scheme::Sip),
+            auth: Some((platform.id.clone(), Option::<String>::None).into()),
+            host_with_port: rsip::Domain::from(platform.domain.clone()).into(),
+            ..Default::default()
+        }
+    }
+
+    pub async fn send_register(
+        &self,
+        platform: &UpstreamPlatform,
+        upstream_addr: std::net::SocketAddr,
+        transaction: &SipTransaction,
+        seq: u32,
+        expires: u32,
+        authorization: Option<rsip::typed::Authorization>,
+    ) -> bool {
+        // headers
+        let mut headers: rsip::Headers = Default::default();
+        headers.push(self.via(rsip::Transport::Udp, &transaction.branch).into());
+        headers.push(rsip::headers::MaxForwards::default().into());
+        headers.push(self.from_old(&transaction.from_tag).into());
+        headers.push(self.to_new(&self.id).into());
+        headers.push(
+            rsip::headers::Contact::new(format!("<sip:{}@{}:{}>", self.id, self.ip, self.port))
+                .into(),
+        );
+        headers.push(self.caller_id_from_str(&transaction.caller_id).into());
+        headers.push(
+            rsip::typed::CSeq {
+                seq,
+                method: rsip::Method::Register,
+            }
+            .into(),
+        );
+        if let Some(authorization) = authorization {
+            headers.push(authorization.into());
+        }
+        headers.push(rsip::headers::Expires::from(expires).into());
+        headers.push(
+            rsip::headers::UserAgent::from(format!(
+                "{} {}",
+                version::APP_NAME,
+                version::APP_VERSION
+            ))
+            .into(),
+        );
+        headers.push(rsip::Header::ContentLength(Default::default()));
+
+        // request
+        let request = rsip::Request {
+            method: rsip::Method::Register,
+            uri: self.upstream_uri(platform),
+            version: rsip::Version::V2,
+            headers,
+            body: Default::default(),
+        };
+
+        self.socket_send_request(upstream_addr, None, request).await
+    }
+}
@@ -0,0 +1,95 @@
+use rsip::prelude::UntypedHeader;
+
+use crate::sip::handler::{SipHandler, SipTransaction};
+use crate::version;
+
+impl SipHandler {
+    // 订阅请求, 刷新时沿用对话的 Call-ID 与 tag, expires 为 0 表示取消订阅
+    #[allow(clippy::too_many_arguments)]
+    pub async fn send_subscribe(
+        &self,
+        device_addr: std::net::SocketAddr,
+        tcp_stream: Option<std::sync::Arc<tokio::sync::Mutex<tokio::net::tcp::OwnedWriteHalf>>>,
+        transaction: &SipTransaction,
+        gb_code: &String,
+        event: &str,
+        expires: u32,
+        text_body: &str,
+    ) -> bool {
+        let bin_body = self.encode_body(text_body);
+
+        // headers
+        let mut headers: rsip::Headers = Default::default();
+        headers.push(
+            self.via(
+                if tcp_stream.is_some() {
+                    rsip::Transport::Tcp
+                } else {
+                    rsip::Transport::Udp
+                },
+                &transaction.branch,
+            )
+            .into(),
+        );
+        headers.push(rsip::headers::MaxForwards::default().into());
+        headers.push(self.from_old(&transaction.from_tag).into());
+        if transaction.to_tag.is_empty() {
+            headers.push(self.to_new(gb_code).into());
+        } else {
+            headers.push(self.to_new_with_tag(gb_code, &transaction.to_tag).into());
+        }
+        headers.push(
+            rsip::headers::Contact::new(format!("<sip:{}@{}:{}>", self.id, self.ip, self.port))
+                .into(),
+        );
+        headers.push(self.caller_id_from_str(&transaction.caller_id).into());
+        headers.push(
+            rsip::typed::CSeq {
+                seq: self.store.add_fetch_global_sequence(),
+                method: rsip::Method::Subscribe,
+            }
+            .into(),
+        );
+        headers.push(rsip::headers::Event::new(event).into());
+        headers.push(rsip::headers::Expires::from(expires).into());
+        headers.push(
+            rsip::headers::UserAgent::from(format!(
+                "{} {}",
+                version::APP_NAME,
+                version::APP_VERSION
+            ))
+            .into(),
+        );
+        headers.push(
+            rsip::typed::ContentType(rsip::headers::typed::MediaType::Other(
+                "Application/MANSCDP+xml".into(),
+                vec![],
+            ))
+            .into(),
+        );
+        headers.push(rsip::headers::ContentLength::from(bin_body.len() as u32).into());
+
+        // request
+        let request = rsip::Request {
+            method: rsip::Method::Subscribe,
+            uri: rsip::Uri {
+                scheme: Some(rsip::Scheme::Sip),
+                auth: Some((gb_code.clone(), Option::<String>::None).into()),
+                host_with_port: rsip::Domain::from(self.domain.clone()).into(),
+                ..Default::default()
+            },
+            version: rsip::Version::V2,
+            headers,
+            body: Default::default(),
+        };
+
+        self.socket_send_request_with_body(
+            device_addr,
+            tcp_stream,
+            request,
+            bin_body,
+            text_body.to_string(),
+        )
+        .await
+    }
+}
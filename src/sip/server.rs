@@ -77,6 +77,39 @@ fn parse_sip_message(buffer: &[u8]) -> Option<(Vec<u8>, Vec<u8>)> {
     None
 }
 
+// 对端空闲超过该时长后退出队列任务
+const UDP_PEER_IDLE_SECONDS: u64 = 60;
+
+// 顺序处理同一对端的 UDP 报文, 处理过程中可能等待其他对端的应答(如级联点播)
+fn udp_peer_worker(
+    sip_handler: std::sync::Arc<SipHandler>,
+    addr: std::net::SocketAddr,
+) -> tokio::sync::mpsc::UnboundedSender<Vec<u8>> {
+    let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel::<Vec<u8>>();
+    tokio::spawn(async move {
+        loop {
+            match tokio::time::timeout(
+                std::time::Duration::from_secs(UDP_PEER_IDLE_SECONDS),
+                receiver.recv(),
+            )
+            .await
+            {
+                Ok(Some(sip_data)) => sip_handler.dispatch(addr, None, sip_data.as_slice()).await,
+                Ok(None) => return,
+                Err(_) => {
+                    // 关闭后再处理已入队的报文, 之后的报文由接收循环重建队列
+                    receiver.close();
+                    while let Ok(sip_data) = receiver.try_recv() {
+                        sip_handler.dispatch(addr, None, sip_data.as_slice()).await;
+                    }
+                    return;
+                }
+            }
+        }
+    });
+    sender
+}
+
 pub async fn run_forever(
     config: Config,
     sip_handler: std::sync::Arc<SipHandler>,
@@ -86,6 +119,11 @@ pub async fn run_forever(
     let udp_server_handle = tokio::spawn(async move {
         let mut recv_buff = Vec::<u8>::default();
         recv_buff.resize(config.socket_recv_buffer_size, 0);
+        // 每个来源地址一个队列, 同一对端的报文按到达顺序处理, 不同对端互不阻塞
+        let mut peers = std::collections::HashMap::<
+            std::net::SocketAddr,
+            tokio::sync::mpsc::UnboundedSender<Vec<u8>>,
+        >::new();
 
         loop {
             match sip_handler_udp
@@ -97,9 +135,18 @@ pub async fn run_forever(
                     tracing::error!("UdpSocket::recv_from error, e: {:?}", e);
                 }
                 Ok((amount, addr)) => {
-                    sip_handler_udp
-                        .dispatch(addr, None, &recv_buff.as_slice()[..amount])
-                        .await;
+                    let mut sip_data = recv_buff[..amount].to_vec();
+                    if let Some(sender) = peers.get(&addr) {
+                        match sender.send(sip_data) {
+                            Ok(()) => continue,
+                            // 队列已空闲退出, 重建
+                            Err(e) => sip_data = e.0,
+                        }
+                    }
+                    peers.retain(|_, sender| !sender.is_closed());
+                    let sender = udp_peer_worker(sip_handler_udp.clone(), addr);
+                    let _ = sender.send(sip_data);
+                    peers.insert(addr, sender);
                 }
             }
         }
@@ -1,3 +1,5 @@
+use rsip::prelude::{HeadersExt, ToTypedHeader};
+
 use crate::sip::handler::SipHandler;
 
 impl SipHandler {
@@ -29,4 +31,81 @@ impl SipHandler {
 
         generator.verify(digest)
     }
+
+    // 向上级注册时根据 401 质询计算摘要
+    pub fn authorization_new(
+        &self,
+        password: &str,
+        method: &rsip::Method,
+        uri: &rsip::Uri,
+        challenge: &rsip::typed::WwwAuthenticate,
+    ) -> rsip::typed::Authorization {
+        let algorithm = challenge
+            .algorithm
+            .unwrap_or(rsip::headers::auth::Algorithm::Md5);
+        let qop = challenge
+            .qop
+            .as_ref()
+            .map(|_| rsip::headers::auth::AuthQop::Auth {
+                cnonce: self.tag_new(16),
+                nc: 1,
+            });
+        let response = rsip::services::DigestGenerator {
+            username: &self.id,
+            password,
+            algorithm,
+            nonce: &challenge.nonce,
+            method,
+            qop: qop.as_ref(),
+            uri,
+            realm: &challenge.realm,
+        }
+        .compute();
+
+        rsip::typed::Authorization {
+            scheme: rsip::headers::auth::Scheme::Digest,
+            username: self.id.clone(),
+            realm: challenge.realm.clone(),
+            nonce: challenge.nonce.clone(),
+            uri: uri.clone(),
+            response,
+            algorithm: Some(algorithm),
+            opaque: challenge.opaque.clone(),
+            qop,
+        }
+    }
+
+    // 设备对 INVITE 返回 401/407 时, 按质询计算重发请求需要携带的认证头
+    pub fn authorization_for(
+        &self,
+        response: &rsip::Response,
+        method: &rsip::Method,
+        uri: &rsip::Uri,
+    ) -> Option<rsip::Header> {
+        match response.status_code() {
+            rsip::StatusCode::Unauthorized => {
+                let challenge = response.www_authenticate_header()?.typed().ok()?;
+                Some(
+                    self.authorization_new(&self.password, method, uri, &challenge)
+                        .into(),
+                )
+            }
+            rsip::StatusCode::ProxyAuthenticationRequired => {
+                let challenge = response.headers.iter().find_map(|header| match header {
+                    rsip::Header::ProxyAuthenticate(header) => header.typed().ok(),
+                    _ => None,
+                })?;
+                Some(
+                    rsip::typed::ProxyAuthorization(self.authorization_new(
+                        &self.password,
+                        method,
+                        uri,
+                        &challenge.0,
+                    ))
+                    .into(),
+                )
+            }
+            _ => None,
+        }
+    }
 }
@@ -9,8 +9,7 @@ static CHARSET: [char; 16] = [
 impl SipHandler {
     pub fn tag_new(&self, length: usize) -> String {
         let mut rng = rand::thread_rng();
-        std::iter::repeat(())
-            .take(length)
+        std::iter::repeat_n((), length)
             .map(|_| {
                 let index = rng.gen_range(0..CHARSET.len());
                 CHARSET[index]
@@ -31,4 +31,14 @@ impl SipHandler {
         .with_tag(tag.into())
         .into()
     }
+
+    pub fn to_new_with_domain(&self, gb_code: &str, domain: &str) -> rsip::headers::To {
+        let to_uri = format!("sip:{}@{}", gb_code, domain);
+        rsip::typed::To {
+            display_name: None,
+            uri: rsip::Uri::try_from(to_uri).unwrap(),
+            params: Default::default(),
+        }
+        .into()
+    }
 }
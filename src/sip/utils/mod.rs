@@ -1,4 +1,6 @@
 pub mod auth;
 pub mod codec;
 pub mod header;
+pub mod query;
 pub mod sock;
+pub mod transaction;
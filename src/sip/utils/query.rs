@@ -0,0 +1,186 @@
+use std::future::Future;
+
+use dashmap::mapref::entry::Entry;
+use tokio::sync::oneshot;
+
+use crate::sip::handler::SipHandler;
+use crate::sip::message::{Catalog, DeviceInfo, DeviceStatus, PresetQueryResponse, RecordInfo};
+use crate::store;
+
+// 分配到的 SN 已有同类查询在等待时最多重新分配的次数
+const QUERY_SN_ATTEMPTS: usize = 3;
+
+// 查询类应答, 按 (DeviceID, CmdType, SN) 关联到等待中的请求
+#[derive(Debug)]
+pub enum QueryResponse {
+    Catalog(Catalog),
+    DeviceInfo(DeviceInfo),
+    DeviceStatus(DeviceStatus),
+    PresetQuery(PresetQueryResponse),
+    RecordInfo(RecordInfo),
+}
+
+impl SipHandler {
+    // 分配 SN 并登记等待, SN 已被同类查询占用时重新分配, 不覆盖已有的等待者
+    pub fn query_begin(
+        &self,
+        device_id: &str,
+        cmd_type: &str,
+    ) -> Option<(u32, oneshot::Receiver<QueryResponse>)> {
+        for _ in 0..QUERY_SN_ATTEMPTS {
+            let sn = self.store.add_fetch_global_sn();
+            if let Entry::Vacant(entry) =
+                self.queries
+                    .entry((device_id.to_string(), cmd_type.to_string(), sn))
+            {
+                let (sender, receiver) = oneshot::channel();
+                entry.insert(sender);
+                return Some((sn, receiver));
+            }
+        }
+        None
+    }
+
+    pub fn query_end(&self, device_id: &str, cmd_type: &str, sn: u32, data: QueryResponse) -> bool {
+        if let Some((_, sender)) =
+            self.queries
+                .remove(&(device_id.to_string(), cmd_type.to_string(), sn))
+        {
+            return sender.send(data).is_ok();
+        }
+        false
+    }
+
+    pub fn query_cancel(&self, device_id: &str, cmd_type: &str, sn: u32) {
+        self.queries
+            .remove(&(device_id.to_string(), cmd_type.to_string(), sn));
+        // 丢弃未收齐的录像分包
+        self.record_infos.remove(&(device_id.to_string(), sn));
+    }
+
+    pub async fn query_wait(
+        &self,
+        device_id: &str,
+        cmd_type: &str,
+        sn: u32,
+        receiver: oneshot::Receiver<QueryResponse>,
+    ) -> Option<QueryResponse> {
+        match tokio::time::timeout(
+            std::time::Duration::from_secs(self.response_timeout_seconds as u64),
+            receiver,
+        )
+        .await
+        {
+            Ok(Ok(data)) => Some(data),
+            Ok(Err(_)) => None,
+            Err(_) => {
+                tracing::warn!(
+                    "query timeout, device_id: {}, cmd_type: {}, sn: {}",
+                    device_id,
+                    cmd_type,
+                    sn
+                );
+                self.query_cancel(device_id, cmd_type, sn);
+                None
+            }
+        }
+    }
+
+    // 查找设备, 分配 SN 并下发查询, 等待设备按 SN 应答
+    pub async fn query<F, Fut>(
+        &self,
+        gb_code: &str,
+        device_id: &str,
+        cmd_type: &str,
+        send: F,
+    ) -> Result<QueryResponse, (u32, String)>
+    where
+        F: FnOnce(store::DeviceInfo, rsip::Transport, u32) -> Fut,
+        Fut: Future<Output = bool>,
+    {
+        let Some(device) = self.store.find_device_by_gb_code(gb_code) else {
+            return Err((404, String::from("device not found")));
+        };
+        let transport = if device.tcp_stream.is_some() {
+            rsip::Transport::Tcp
+        } else {
+            rsip::Transport::Udp
+        };
+        let Some((sn, receiver)) = self.query_begin(device_id, cmd_type) else {
+            return Err((500, String::from("sn conflict")));
+        };
+        if !send(device, transport, sn).await {
+            self.query_cancel(device_id, cmd_type, sn);
+            return Err((500, String::from("send failed")));
+        }
+        self.query_wait(device_id, cmd_type, sn, receiver)
+            .await
+            .ok_or((408, String::from("device timeout")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::QueryResponse;
+    use crate::sip::handler::SipHandler;
+    use crate::sip::message::DeviceInfo;
+    use crate::store::memory::MemoryStore;
+    use crate::utils::config::Config;
+
+    #[tokio::test]
+    async fn test_concurrent_queries_same_type() {
+        let config: Config = serde_yaml::from_str("{}").unwrap();
+        let store = Box::new(MemoryStore::new(&config));
+        let udp = tokio::net::UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let tcp = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let handler = Arc::new(SipHandler::new(&config, store, udp, tcp));
+        let gb_code = "34020000001180000000";
+        handler.store.register(
+            "z9hG4bK1",
+            gb_code,
+            "127.0.0.1:5060".parse().unwrap(),
+            &None,
+        );
+
+        // 发出查询并把分配到的 SN 交给测试
+        let start = |name: &'static str| {
+            let handler = handler.clone();
+            let (sn_sender, sn_receiver) = tokio::sync::oneshot::channel();
+            let task = tokio::spawn(async move {
+                let result = handler
+                    .query(gb_code, gb_code, "DeviceInfo", |_, _, sn| async move {
+                        sn_sender.send(sn).is_ok()
+                    })
+                    .await;
+                (name, result)
+            });
+            (sn_receiver, task)
+        };
+        let (first_sn, first) = start("first");
+        let first_sn = first_sn.await.unwrap();
+        // 计数被回拨后第二个查询不能顶替第一个的等待者
+        handler.store.set_global_sn(first_sn - 1);
+        let (second_sn, second) = start("second");
+        let second_sn = second_sn.await.unwrap();
+        assert_ne!(first_sn, second_sn);
+
+        for (sn, name) in [(second_sn, "second"), (first_sn, "first")] {
+            let data = DeviceInfo {
+                sn,
+                device_id: gb_code.to_string(),
+                device_name: name.to_string(),
+                ..Default::default()
+            };
+            assert!(handler.query_end(gb_code, "DeviceInfo", sn, QueryResponse::DeviceInfo(data)));
+        }
+        for task in [first, second] {
+            let (name, result) = task.await.unwrap();
+            let Ok(QueryResponse::DeviceInfo(data)) = result else {
+                panic!("{} query failed", name);
+            };
+            assert_eq!(data.device_name, name);
+        }
+    }
+}
@@ -0,0 +1,54 @@
+use tokio::sync::oneshot;
+
+use crate::sip::handler::SipHandler;
+
+impl SipHandler {
+    // 登记一个等待最终应答的事务, 以 Call-ID 关联
+    pub fn transaction_begin(&self, caller_id: &str) -> oneshot::Receiver<rsip::Response> {
+        let (sender, receiver) = oneshot::channel();
+        self.transactions.insert(caller_id.to_string(), sender);
+        receiver
+    }
+
+    pub fn transaction_end(&self, caller_id: &str, response: rsip::Response) -> bool {
+        if let Some((_, sender)) = self.transactions.remove(caller_id) {
+            return sender.send(response).is_ok();
+        }
+        false
+    }
+
+    pub fn transaction_cancel(&self, caller_id: &str) {
+        self.transactions.remove(caller_id);
+    }
+
+    pub async fn transaction_wait(
+        &self,
+        caller_id: &str,
+        receiver: oneshot::Receiver<rsip::Response>,
+    ) -> Option<rsip::Response> {
+        self.transaction_wait_timeout(caller_id, receiver, self.response_timeout_seconds)
+            .await
+    }
+
+    pub async fn transaction_wait_timeout(
+        &self,
+        caller_id: &str,
+        receiver: oneshot::Receiver<rsip::Response>,
+        timeout_seconds: u32,
+    ) -> Option<rsip::Response> {
+        match tokio::time::timeout(
+            std::time::Duration::from_secs(timeout_seconds as u64),
+            receiver,
+        )
+        .await
+        {
+            Ok(Ok(response)) => Some(response),
+            Ok(Err(_)) => None,
+            Err(_) => {
+                tracing::warn!("transaction timeout, caller_id: {}", caller_id);
+                self.transaction_cancel(caller_id);
+                None
+            }
+        }
+    }
+}
@@ -1,23 +1,36 @@
 use tokio;
 
 use dashmap::DashMap;
+use std::collections::VecDeque;
 use std::sync::atomic::AtomicU32;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 use uuid::Uuid;
 
 use super::StoreEngine;
-use super::{GbStreamInfo, SipDeviceInfo};
-use crate::sip::message::Catalog;
+use super::{
+    AlarmRecord, CatalogSyncState, CatalogSyncStatus, DeviceRecord, DeviceStatusRecord,
+    GbStreamInfo, SipDeviceInfo, TalkSession, TrackPoint,
+};
+use crate::sip::message::{Catalog, CatalogEvent, Device, DeviceInfo, DeviceStatus};
 
 use crate::utils::config::Config;
 
+// 未收齐的目录分包
+#[derive(Debug, Default)]
+pub struct CatalogPending {
+    pub sum_num: u32,
+    pub items: Vec<Device>,
+    pub ts: u32,
+}
+
 pub struct MemoryStore {
     pub quit_flag: bool,
     pub task_handle: Option<tokio::task::JoinHandle<()>>,
     pub service_id: String, // 启动时随机生成，向负载均衡器报告
     pub stream_timeout_seconds: u32,
     pub device_timeout_seconds: u32,
+    pub catalog_timeout_seconds: u32,
     pub live_stream_id: AtomicU32,    // 自动递增
     pub replay_stream_id: AtomicU32,  // 自动递增
     pub global_sn: AtomicU32,         // SN
@@ -25,7 +38,19 @@ pub struct MemoryStore {
     pub global_sequence: AtomicU32,   // CSeq
     pub sip_devices: Arc<DashMap<String, SipDeviceInfo>>,
     pub gb_streams: Arc<DashMap<u32, GbStreamInfo>>,
-    pub gb_streams_rev: Arc<DashMap<String, Vec<u32>>>,
+    // (gb_code, channel_id) -> 正在共享的实时流 stream_id
+    pub live_streams: Arc<DashMap<(String, String), u32>>,
+    pub catalog_pending: Arc<DashMap<(String, u32), CatalogPending>>,
+    // channel_id -> gb_code
+    pub channel_owners: DashMap<String, String>,
+    // caller_id -> 对讲会话
+    pub talks: DashMap<String, TalkSession>,
+    // 报警历史, 新的在前
+    pub alarms: std::sync::Mutex<VecDeque<AlarmRecord>>,
+    pub alarm_history_size: usize,
+    // gb_code -> 轨迹点, 按时间正序
+    pub tracks: Arc<DashMap<String, VecDeque<TrackPoint>>>,
+    pub track_history_size: usize,
 }
 
 impl MemoryStore {
@@ -35,7 +60,8 @@ impl MemoryStore {
             task_handle: None,
             service_id: Uuid::new_v4().to_string(),
             stream_timeout_seconds: config.stream_timeout_seconds,
-            device_timeout_seconds: config.stream_timeout_seconds,
+            device_timeout_seconds: config.device_timeout_seconds,
+            catalog_timeout_seconds: config.catalog_timeout_seconds,
             live_stream_id: AtomicU32::new(1),
             replay_stream_id: AtomicU32::new(1),
             global_sn: AtomicU32::new(0),
@@ -43,7 +69,80 @@ impl MemoryStore {
             global_sequence: AtomicU32::new(0),
             sip_devices: Arc::new(DashMap::<String, SipDeviceInfo>::default()),
             gb_streams: Arc::new(DashMap::<u32, GbStreamInfo>::default()),
-            gb_streams_rev: Arc::new(DashMap::<String, Vec<u32>>::default()),
+            live_streams: Arc::new(DashMap::<(String, String), u32>::default()),
+            channel_owners: DashMap::new(),
+            talks: DashMap::new(),
+            catalog_pending: Arc::new(DashMap::<(String, u32), CatalogPending>::default()),
+            alarms: std::sync::Mutex::new(VecDeque::new()),
+            alarm_history_size: config.alarm_history_size as usize,
+            tracks: Arc::new(DashMap::<String, VecDeque<TrackPoint>>::default()),
+            track_history_size: config.track_history_size as usize,
+        }
+    }
+}
+
+impl MemoryStore {
+    // 减少一个观看者, 最后一个观看者离开或 force 时删除会话
+    fn close_stream(&self, gb_code: &str, stream_id: u32, force: bool) -> Option<super::ByeResult> {
+        let mut stream = self.gb_streams.get_mut(&stream_id)?;
+        let caller_id = stream.caller_id.clone();
+        let from_tag = stream.from_tag.clone();
+        let to_tag = stream.to_tag.clone();
+        let bye_to_device = force || stream.viewers <= 1;
+        if bye_to_device {
+            let live_key = (stream.gb_code.clone(), stream.channel_id.clone());
+            let is_live = stream.is_live;
+            drop(stream);
+            self.gb_streams.remove(&stream_id);
+            if is_live {
+                self.live_streams
+                    .remove_if(&live_key, |_, id| *id == stream_id);
+            }
+        } else {
+            stream.viewers -= 1;
+        }
+
+        let super::DeviceInfo {
+            branch,
+            socket_addr,
+            tcp_stream,
+        } = self.find_device_by_gb_code(gb_code)?;
+        Some(super::ByeResult {
+            success: bye_to_device,
+            caller_id,
+            from_tag,
+            to_tag,
+            branch,
+            socket_addr,
+            tcp_stream,
+        })
+    }
+
+    fn device_record(&self, gb_code: &str, device: &SipDeviceInfo) -> DeviceRecord {
+        let ts_now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .expect("Time went backwards")
+            .as_secs() as u32;
+        DeviceRecord {
+            gb_code: gb_code.to_string(),
+            socket_addr: device.udp_client_addr.to_string(),
+            transport: if device.tcp_stream_writer.is_some() {
+                String::from("TCP")
+            } else {
+                String::from("UDP")
+            },
+            register_ts: device.register_ts,
+            keep_alive_ts: device.ts,
+            online: ts_now.saturating_sub(device.ts) <= self.device_timeout_seconds,
+            manufacturer: device.manufacturer.clone(),
+            model: device.model.clone(),
+            firmware: device.firmware.clone(),
+            status: device.status.clone(),
+            channel_num: device
+                .sub_devices
+                .as_ref()
+                .map(|items| items.len() as u32)
+                .unwrap_or(0),
         }
     }
 }
@@ -53,6 +152,10 @@ impl StoreEngine for MemoryStore {
         true
     }
 
+    fn service_id(&self) -> String {
+        self.service_id.clone()
+    }
+
     fn set_global_sn(&self, v: u32) {
         self.global_sn
             .store(v, std::sync::atomic::Ordering::Relaxed);
@@ -111,6 +214,56 @@ impl StoreEngine for MemoryStore {
         None
     }
 
+    fn save_device_info(&self, gb_code: &str, data: &DeviceInfo) -> bool {
+        if !super::is_own_reply(gb_code, &data.device_id, &data.result) {
+            return false;
+        }
+        if let Some(mut device) = self.sip_devices.get_mut(gb_code) {
+            device.manufacturer = data.manufacturer.clone();
+            device.model = data.model.clone();
+            device.firmware = data.firmware.clone();
+            return true;
+        }
+        false
+    }
+
+    fn save_device_status(&self, gb_code: &str, data: &DeviceStatus) -> bool {
+        if !super::is_own_reply(gb_code, &data.device_id, &data.result) {
+            return false;
+        }
+        if let Some(mut device) = self.sip_devices.get_mut(gb_code) {
+            let ts = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .expect("Time went backwards")
+                .as_secs() as u32;
+            device.status = DeviceStatusRecord { ts, ..data.into() };
+            return true;
+        }
+        false
+    }
+
+    fn list_devices(&self) -> Vec<DeviceRecord> {
+        let mut devices: Vec<DeviceRecord> = self
+            .sip_devices
+            .iter()
+            .map(|entry| self.device_record(entry.key(), entry.value()))
+            .collect();
+        devices.sort_by(|a, b| a.gb_code.cmp(&b.gb_code));
+        devices
+    }
+
+    fn find_device_record(&self, gb_code: &str) -> Option<DeviceRecord> {
+        self.sip_devices
+            .get(gb_code)
+            .map(|device| self.device_record(gb_code, device.value()))
+    }
+
+    fn find_gb_code_by_channel_id(&self, channel_id: &str) -> Option<String> {
+        self.channel_owners
+            .get(channel_id)
+            .map(|owner| owner.value().clone())
+    }
+
     fn find_gb_code_by_caller_id(&self, key: &str) -> Option<String> {
         for entry in self.gb_streams.iter() {
             let (_, stream) = entry.pair();
@@ -153,7 +306,13 @@ impl StoreEngine for MemoryStore {
                     udp_client_addr: socket_addr,
                     tcp_stream_writer: tcp_stream.as_ref().cloned(),
                     ts,
+                    register_ts: ts,
+                    manufacturer: String::new(),
+                    model: String::new(),
+                    firmware: String::new(),
+                    status: DeviceStatusRecord::default(),
                     sub_devices: None,
+                    catalog_sync: CatalogSyncState::default(),
                 },
             );
             return true;
@@ -164,6 +323,7 @@ impl StoreEngine for MemoryStore {
     fn unregister(&self, gb_code: &str) -> bool {
         if self.sip_devices.get(gb_code).is_some() {
             self.sip_devices.remove(gb_code);
+            self.channel_owners.retain(|_, owner| owner != gb_code);
             return true;
         }
         false
@@ -182,21 +342,120 @@ impl StoreEngine for MemoryStore {
         false
     }
 
-    fn save_catalog(&self, gb_code: &str, data: Catalog) -> bool {
+    fn save_catalog(&self, gb_code: &str, data: Catalog) -> CatalogSyncState {
+        if self.sip_devices.get(gb_code).is_none() {
+            return CatalogSyncState::default();
+        }
+
+        let ts = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .expect("Time went backwards")
+            .as_secs() as u32;
+
+        // 丢弃该设备其他 SN 下已超时的分包
+        let catalog_timeout_seconds = self.catalog_timeout_seconds;
+        self.catalog_pending.retain(|(code, sn), pending| {
+            code != gb_code
+                || *sn == data.sn
+                || ts.saturating_sub(pending.ts) <= catalog_timeout_seconds
+        });
+
+        // 按 DeviceID 去重累积, 收齐 SumNum 后整体替换
+        let key = (gb_code.to_string(), data.sn);
+        let (received, complete) = {
+            let mut pending = self.catalog_pending.entry(key.clone()).or_default();
+            pending.sum_num = data.sum_num;
+            pending.ts = ts;
+            for device in data.device_list.items {
+                if let Some(item) = pending
+                    .items
+                    .iter_mut()
+                    .find(|item| item.device_id == device.device_id)
+                {
+                    *item = device;
+                } else {
+                    pending.items.push(device);
+                }
+            }
+            let received = pending.items.len() as u32;
+            (received, received >= pending.sum_num)
+        };
+
+        let items = if complete {
+            self.catalog_pending
+                .remove(&key)
+                .map(|(_, pending)| pending.items)
+        } else {
+            None
+        };
+
         let entry = self.sip_devices.entry(gb_code.to_string());
         if let dashmap::Entry::Occupied(mut device_entry) = entry {
             let device_info = device_entry.get_mut();
-            if device_info.sub_devices.is_none() {
-                device_info.sub_devices = Some(Vec::new());
-            }
-            if let Some(sub_devices) = &mut device_info.sub_devices {
-                for device in data.device_list.items {
-                    sub_devices.push(device);
+            device_info.catalog_sync = CatalogSyncState {
+                status: if items.is_some() {
+                    CatalogSyncStatus::Complete
+                } else {
+                    CatalogSyncStatus::InProgress
+                },
+                sn: data.sn,
+                received,
+                sum_num: data.sum_num,
+                ts,
+            };
+            if let Some(items) = items {
+                self.channel_owners.retain(|_, owner| owner != gb_code);
+                for item in &items {
+                    self.channel_owners
+                        .insert(item.device_id.clone(), gb_code.to_string());
                 }
-                return true;
+                device_info.sub_devices = Some(items);
             }
+            return device_info.catalog_sync.clone();
         }
-        false
+        CatalogSyncState::default()
+    }
+
+    fn find_catalog(&self, gb_code: &str) -> Option<Vec<Device>> {
+        if let Some(device) = self.sip_devices.get(gb_code) {
+            return device.value().sub_devices.clone();
+        }
+        None
+    }
+
+    fn find_catalog_sync_state(&self, gb_code: &str) -> Option<CatalogSyncState> {
+        if let Some(device) = self.sip_devices.get(gb_code) {
+            let mut state = device.value().catalog_sync.clone();
+            let ts_now = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .expect("Time went backwards")
+                .as_secs() as u32;
+            if state.status == CatalogSyncStatus::InProgress
+                && ts_now.saturating_sub(state.ts) > self.catalog_timeout_seconds
+            {
+                state.status = CatalogSyncStatus::Timeout;
+            }
+            return Some(state);
+        }
+        None
+    }
+
+    fn apply_catalog_event(&self, gb_code: &str, event: CatalogEvent, item: &Device) -> bool {
+        let Some(mut device) = self.sip_devices.get_mut(gb_code) else {
+            return false;
+        };
+        let items = device.sub_devices.get_or_insert_with(Vec::new);
+        if !super::apply_catalog_event_to(items, event, item) {
+            return false;
+        }
+        if event == CatalogEvent::Del {
+            self.channel_owners
+                .remove_if(&item.device_id, |_, owner| owner == gb_code);
+        } else {
+            self.channel_owners
+                .insert(item.device_id.clone(), gb_code.to_string());
+        }
+        true
     }
 
     fn invite(
@@ -215,6 +474,25 @@ impl StoreEngine for MemoryStore {
             tcp_stream,
         } = result.unwrap();
 
+        // 通道已有实时流时只增加观看者
+        let live_key = (gb_code.to_string(), channel_id.to_string());
+        if is_live {
+            let shared = self.live_streams.get(&live_key).map(|id| *id);
+            if let Some(stream_id) = shared {
+                if let Some(mut stream) = self.gb_streams.get_mut(&stream_id) {
+                    stream.viewers += 1;
+                    return Some(super::InviteResult {
+                        success: true,
+                        channel_id: channel_id.to_string(),
+                        stream_id,
+                        branch,
+                        socket_addr,
+                        tcp_stream,
+                    });
+                }
+            }
+        }
+
         let stream_id = if is_live {
             self.live_stream_id
                 .fetch_add(1, std::sync::atomic::Ordering::Relaxed)
@@ -232,26 +510,23 @@ impl StoreEngine for MemoryStore {
             stream_id,
             GbStreamInfo {
                 gb_code: gb_code.to_string(),
+                channel_id: channel_id.to_string(),
                 caller_id: caller_id.to_string(),
                 from_tag: from_tag.to_string(),
                 to_tag: String::new(),
                 stream_server_ip: String::new(),
                 stream_server_port: 0,
+                is_live,
+                viewers: 1,
                 ts,
             },
         );
-
-        let is_playing = self.gb_streams_rev.get(gb_code).is_some();
-
-        if let Some(mut streams) = self.gb_streams_rev.get_mut(gb_code) {
-            streams.push(stream_id);
-        } else {
-            self.gb_streams_rev
-                .insert(gb_code.to_string(), vec![stream_id]);
+        if is_live {
+            self.live_streams.insert(live_key, stream_id);
         }
 
         Some(super::InviteResult {
-            success: is_playing,
+            success: false,
             channel_id: channel_id.to_string(),
             stream_id,
             branch,
@@ -283,52 +558,11 @@ impl StoreEngine for MemoryStore {
     }
 
     fn bye(&self, gb_code: &str, stream_id: u32) -> Option<super::ByeResult> {
-        let caller_id: String;
-        let from_tag: String;
-        let to_tag: String;
-        if let Some(stream) = self.gb_streams.get(&stream_id) {
-            let GbStreamInfo {
-                caller_id: caller_id_inner,
-                from_tag: from_tag_inner,
-                to_tag: to_tag_inner,
-                ..
-            } = stream.value();
-            caller_id = caller_id_inner.clone();
-            from_tag = from_tag_inner.clone();
-            to_tag = to_tag_inner.clone();
-        } else {
-            return None;
-        }
-
-        self.gb_streams.remove(&stream_id);
-
-        let mut bye_to_device = false;
-        if let Some(mut streams) = self.gb_streams_rev.get_mut(gb_code) {
-            streams.retain(|&id| id != stream_id);
-            bye_to_device = streams.is_empty();
-        }
-        if bye_to_device {
-            self.gb_streams_rev.remove(gb_code);
-        }
-
-        if let Some(super::DeviceInfo {
-            branch,
-            socket_addr,
-            tcp_stream,
-        }) = self.find_device_by_gb_code(gb_code)
-        {
-            return Some(super::ByeResult {
-                success: bye_to_device,
-                caller_id,
-                from_tag,
-                to_tag,
-                branch,
-                socket_addr,
-                tcp_stream,
-            });
-        }
+        self.close_stream(gb_code, stream_id, false)
+    }
 
-        None
+    fn bye_all(&self, gb_code: &str, stream_id: u32) -> Option<super::ByeResult> {
+        self.close_stream(gb_code, stream_id, true)
     }
 
     fn stream_keep_alive(&self, gb_code: &str, stream_id: u32) -> bool {
@@ -344,6 +578,75 @@ impl StoreEngine for MemoryStore {
         }
         false
     }
+
+    fn save_talk(&self, talk: &TalkSession) -> bool {
+        self.talks.insert(talk.caller_id.clone(), talk.clone());
+        true
+    }
+
+    fn take_talk(&self, caller_id: &str) -> Option<TalkSession> {
+        self.talks.remove(caller_id).map(|(_, talk)| talk)
+    }
+
+    fn find_talk(&self, gb_code: &str, stream_id: u32) -> Option<TalkSession> {
+        self.talks
+            .iter()
+            .find(|talk| talk.gb_code == gb_code && talk.stream_id == stream_id)
+            .map(|talk| talk.value().clone())
+    }
+
+    fn remove_talk(&self, gb_code: &str, stream_id: u32) -> bool {
+        let before = self.talks.len();
+        self.talks
+            .retain(|_, talk| !(talk.gb_code == gb_code && talk.stream_id == stream_id));
+        self.talks.len() < before
+    }
+
+    fn save_alarm(&self, alarm: &AlarmRecord) -> bool {
+        let mut alarms = self.alarms.lock().unwrap();
+        alarms.push_front(alarm.clone());
+        alarms.truncate(self.alarm_history_size);
+        true
+    }
+
+    fn list_alarms(
+        &self,
+        gb_code: &str,
+        start_ts: u32,
+        end_ts: u32,
+        limit: usize,
+    ) -> Vec<AlarmRecord> {
+        self.alarms
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|alarm| gb_code.is_empty() || alarm.gb_code == gb_code)
+            .filter(|alarm| alarm.ts >= start_ts && (end_ts == 0 || alarm.ts <= end_ts))
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+
+    fn save_track_point(&self, point: &TrackPoint) -> bool {
+        let mut track = self.tracks.entry(point.gb_code.clone()).or_default();
+        track.push_back(point.clone());
+        while track.len() > self.track_history_size {
+            track.pop_front();
+        }
+        true
+    }
+
+    fn list_track(&self, gb_code: &str, from_ts: u32, to_ts: u32) -> Vec<TrackPoint> {
+        match self.tracks.get(gb_code) {
+            Some(track) => track
+                .iter()
+                .filter(|point| point.ts >= from_ts && (to_ts == 0 || point.ts <= to_ts))
+                .cloned()
+                .collect(),
+            None => vec![],
+        }
+    }
+
     fn start_timeout_check(
         &mut self,
         timeout_devices_sender: std::sync::mpsc::Sender<Option<String>>,
@@ -372,7 +675,7 @@ impl StoreEngine for MemoryStore {
                     for entry in gb_streams.iter() {
                         let (stream_id, stream) = entry.pair();
                         let GbStreamInfo { gb_code, ts, .. } = stream;
-                        if *ts - ts_now > stream_timeout_seconds {
+                        if ts_now.saturating_sub(*ts) > stream_timeout_seconds {
                             timeout_streams.push((gb_code.clone(), *stream_id));
                         }
                     }
@@ -387,7 +690,7 @@ impl StoreEngine for MemoryStore {
                     for entry in sip_devices.iter() {
                         let (gb_code, device) = entry.pair();
                         let SipDeviceInfo { ts, .. } = device;
-                        if *ts - ts_now > device_timeout_seconds {
+                        if ts_now.saturating_sub(*ts) > device_timeout_seconds {
                             timeout_devices.push(gb_code.clone());
                         }
                     }
@@ -419,3 +722,284 @@ impl StoreEngine for MemoryStore {
 unsafe impl Send for MemoryStore {}
 
 unsafe impl Sync for MemoryStore {}
+
+#[cfg(test)]
+mod tests {
+    use super::MemoryStore;
+    use crate::sip::message::{
+        Catalog, CatalogEvent, Device, DeviceInfo, DeviceList, DeviceStatus,
+    };
+    use crate::store::{AlarmRecord, CatalogSyncStatus, StoreEngine, TalkSession, TrackPoint};
+    use crate::utils::config::Config;
+
+    fn catalog(sn: u32, sum_num: u32, ids: &[&str]) -> Catalog {
+        Catalog {
+            cmd_type: String::from("Catalog"),
+            sn,
+            device_id: String::from("34020000001180000000"),
+            sum_num,
+            device_list: DeviceList {
+                num: ids.len() as u32,
+                items: ids
+                    .iter()
+                    .map(|id| Device {
+                        device_id: id.to_string(),
+                        ..Default::default()
+                    })
+                    .collect(),
+            },
+        }
+    }
+
+    #[test]
+    fn test_save_catalog_aggregate() {
+        let config: Config = serde_yaml::from_str("{}").unwrap();
+        let store = MemoryStore::new(&config);
+        let gb_code = "34020000001180000000";
+        store.register("", gb_code, "127.0.0.1:5060".parse().unwrap(), &None);
+
+        let state = store.save_catalog(gb_code, catalog(1, 3, &["1", "2"]));
+        assert_eq!(state.status, CatalogSyncStatus::InProgress);
+        assert!(store.find_catalog(gb_code).is_none());
+
+        // 重复条目按 DeviceID 去重
+        let state = store.save_catalog(gb_code, catalog(1, 3, &["2"]));
+        assert_eq!(state.status, CatalogSyncStatus::InProgress);
+        assert_eq!(state.received, 2);
+
+        let state = store.save_catalog(gb_code, catalog(1, 3, &["3"]));
+        assert_eq!(state.status, CatalogSyncStatus::Complete);
+        assert_eq!(store.find_catalog(gb_code).unwrap().len(), 3);
+
+        // 新一轮同步完成前保留旧快照, 完成后整体替换
+        let state = store.save_catalog(gb_code, catalog(2, 2, &["4"]));
+        assert_eq!(state.status, CatalogSyncStatus::InProgress);
+        assert_eq!(store.find_catalog(gb_code).unwrap().len(), 3);
+
+        assert_eq!(
+            store.find_gb_code_by_channel_id("3").as_deref(),
+            Some(gb_code)
+        );
+
+        let state = store.save_catalog(gb_code, catalog(2, 2, &["5"]));
+        assert_eq!(state.status, CatalogSyncStatus::Complete);
+        assert_eq!(store.find_catalog(gb_code).unwrap().len(), 2);
+        // 通道归属随目录快照整体替换
+        assert!(store.find_gb_code_by_channel_id("3").is_none());
+        assert_eq!(
+            store.find_gb_code_by_channel_id("5").as_deref(),
+            Some(gb_code)
+        );
+
+        store.unregister(gb_code);
+        assert!(store.find_gb_code_by_channel_id("5").is_none());
+    }
+
+    #[test]
+    fn test_save_device_info_and_status() {
+        let config: Config = serde_yaml::from_str("{}").unwrap();
+        let store = MemoryStore::new(&config);
+        let gb_code = "34020000001180000000";
+        store.register("", gb_code, "192.168.1.10:5060".parse().unwrap(), &None);
+
+        let info = DeviceInfo {
+            device_id: gb_code.to_string(),
+            result: String::from("OK"),
+            manufacturer: String::from("Hikvision"),
+            model: String::from("DS-7808N"),
+            ..Default::default()
+        };
+        let status = DeviceStatus {
+            device_id: gb_code.to_string(),
+            result: String::from("OK"),
+            online: String::from("ONLINE"),
+            record: String::from("ON"),
+            ..Default::default()
+        };
+        // 通道的应答与失败的应答不落库
+        let channel_id = String::from("34020000001320000001");
+        assert!(!store.save_device_info(
+            gb_code,
+            &DeviceInfo {
+                device_id: channel_id.clone(),
+                ..info.clone()
+            }
+        ));
+        assert!(!store.save_device_info(
+            gb_code,
+            &DeviceInfo {
+                result: String::from("ERROR"),
+                ..info.clone()
+            }
+        ));
+        assert!(!store.save_device_status(
+            gb_code,
+            &DeviceStatus {
+                device_id: channel_id,
+                ..status.clone()
+            }
+        ));
+        assert!(!store.save_device_status(
+            gb_code,
+            &DeviceStatus {
+                result: String::from("ERROR"),
+                ..status.clone()
+            }
+        ));
+        let device = store.find_device_record(gb_code).unwrap();
+        assert!(device.manufacturer.is_empty());
+        assert!(device.status.online.is_empty());
+
+        assert!(store.save_device_info(gb_code, &info));
+        assert!(store.save_device_status(gb_code, &status));
+        let device = store.find_device_record(gb_code).unwrap();
+        assert_eq!(device.manufacturer, "Hikvision");
+        assert_eq!(device.model, "DS-7808N");
+        assert_eq!(device.status.online, "ONLINE");
+        assert_eq!(device.status.record, "ON");
+    }
+
+    #[test]
+    fn test_live_stream_shared_per_channel() {
+        let config: Config = serde_yaml::from_str("{}").unwrap();
+        let store = MemoryStore::new(&config);
+        let gb_code = "34020000001180000000";
+        store.register("", gb_code, "127.0.0.1:5060".parse().unwrap(), &None);
+
+        let first = store
+            .invite(gb_code, "34020000001320000001", "call-1", "tag-1", true)
+            .unwrap();
+        assert!(!first.success);
+        let second = store
+            .invite(gb_code, "34020000001320000001", "call-2", "tag-2", true)
+            .unwrap();
+        assert!(second.success);
+        assert_eq!(second.stream_id, first.stream_id);
+
+        // 同一设备的其他通道单独建立会话
+        let other = store
+            .invite(gb_code, "34020000001320000002", "call-3", "tag-3", true)
+            .unwrap();
+        assert!(!other.success);
+        assert_ne!(other.stream_id, first.stream_id);
+
+        let bye = store.bye(gb_code, first.stream_id).unwrap();
+        assert!(!bye.success);
+        assert_eq!(bye.caller_id, "call-1");
+        assert!(store.bye(gb_code, first.stream_id).unwrap().success);
+        assert!(store.bye(gb_code, first.stream_id).is_none());
+
+        // 强制结束不考虑剩余观看者
+        store
+            .invite(gb_code, "34020000001320000002", "call-5", "tag-5", true)
+            .unwrap();
+        assert!(store.bye_all(gb_code, other.stream_id).unwrap().success);
+        let again = store
+            .invite(gb_code, "34020000001320000002", "call-6", "tag-6", true)
+            .unwrap();
+        assert!(!again.success);
+    }
+
+    #[test]
+    fn test_talk_sessions() {
+        let config: Config = serde_yaml::from_str("{}").unwrap();
+        let store = MemoryStore::new(&config);
+        let talk = |caller_id: &str, stream_id: u32| TalkSession {
+            caller_id: caller_id.to_string(),
+            gb_code: String::from("34020000001180000000"),
+            stream_id,
+        };
+        assert!(store.save_talk(&talk("call-1", 1)));
+        assert!(store.save_talk(&talk("call-2", 2)));
+
+        // 设备挂断只取出一次, 流关闭按 (gb_code, stream_id) 删除
+        assert_eq!(
+            store.find_talk("34020000001180000000", 2),
+            Some(talk("call-2", 2))
+        );
+        assert_eq!(store.find_talk("34020000001180000000", 3), None);
+        assert_eq!(store.take_talk("call-1"), Some(talk("call-1", 1)));
+        assert_eq!(store.take_talk("call-1"), None);
+        assert!(store.remove_talk("34020000001180000000", 2));
+        assert!(!store.remove_talk("34020000001180000000", 2));
+        assert_eq!(store.take_talk("call-2"), None);
+    }
+
+    #[test]
+    fn test_alarm_history() {
+        let mut config: Config = serde_yaml::from_str("{}").unwrap();
+        config.alarm_history_size = 2;
+        let store = MemoryStore::new(&config);
+        for (gb_code, ts) in [("1", 10), ("2", 20), ("1", 30)] {
+            store.save_alarm(&AlarmRecord {
+                gb_code: gb_code.to_string(),
+                ts,
+                ..Default::default()
+            });
+        }
+
+        // 超出容量时丢弃最旧的, 按时间倒序返回
+        let alarms = store.list_alarms("", 0, 0, 10);
+        assert_eq!(alarms.iter().map(|a| a.ts).collect::<Vec<_>>(), [30, 20]);
+        assert_eq!(store.list_alarms("1", 0, 0, 10).len(), 1);
+        assert_eq!(store.list_alarms("", 0, 25, 10)[0].ts, 20);
+        assert_eq!(store.list_alarms("", 0, 0, 1).len(), 1);
+    }
+
+    #[test]
+    fn test_track_per_device() {
+        let mut config: Config = serde_yaml::from_str("{}").unwrap();
+        config.track_history_size = 2;
+        let store = MemoryStore::new(&config);
+        for (gb_code, ts) in [("1", 10), ("2", 15), ("1", 20), ("1", 30)] {
+            store.save_track_point(&TrackPoint {
+                gb_code: gb_code.to_string(),
+                longitude: 120.0,
+                latitude: 30.0,
+                ts,
+                ..Default::default()
+            });
+        }
+
+        let track = store.list_track("1", 0, 0);
+        assert_eq!(track.iter().map(|p| p.ts).collect::<Vec<_>>(), [20, 30]);
+        assert_eq!(store.list_track("1", 25, 0).len(), 1);
+        assert_eq!(store.list_track("2", 0, 10).len(), 0);
+    }
+
+    #[test]
+    fn test_apply_catalog_event() {
+        let config: Config = serde_yaml::from_str("{}").unwrap();
+        let store = MemoryStore::new(&config);
+        let gb_code = "34020000001180000000";
+        store.register("", gb_code, "127.0.0.1:5060".parse().unwrap(), &None);
+        store.save_catalog(gb_code, catalog(1, 2, &["1", "2"]));
+
+        let item = |id: &str| Device {
+            device_id: id.to_string(),
+            ..Default::default()
+        };
+        assert!(store.apply_catalog_event(gb_code, CatalogEvent::Off, &item("1")));
+        // 状态未变化时不算变更
+        assert!(!store.apply_catalog_event(gb_code, CatalogEvent::Vlost, &item("1")));
+        assert!(!store.apply_catalog_event(gb_code, CatalogEvent::On, &item("9")));
+        assert!(store.apply_catalog_event(gb_code, CatalogEvent::Add, &item("3")));
+        assert!(store.apply_catalog_event(gb_code, CatalogEvent::Del, &item("2")));
+
+        let items = store.find_catalog(gb_code).unwrap();
+        assert_eq!(
+            items
+                .iter()
+                .map(|c| c.device_id.as_str())
+                .collect::<Vec<_>>(),
+            ["1", "3"]
+        );
+        assert_eq!(items[0].status.as_deref(), Some("OFF"));
+        assert_eq!(
+            store.find_gb_code_by_channel_id("3").as_deref(),
+            Some(gb_code)
+        );
+        assert!(store.find_gb_code_by_channel_id("2").is_none());
+        assert!(!store.apply_catalog_event("unknown", CatalogEvent::Add, &item("1")));
+    }
+}
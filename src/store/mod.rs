@@ -3,14 +3,50 @@ pub mod mysql;
 pub mod not_impl;
 pub mod redis;
 
-use crate::sip::message::{Catalog, Device};
+use crate::sip::message::{Alarm, Catalog, CatalogEvent, Device, DeviceStatus, MobilePosition};
 use crate::utils::config::Config;
+use std::fmt;
 use std::net::SocketAddr;
 use std::sync::mpsc;
 use std::sync::Arc;
 use tokio::net::tcp::OwnedWriteHalf;
 use tokio::sync::Mutex;
 
+// 目录同步状态
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum CatalogSyncStatus {
+    #[default]
+    None,
+    InProgress,
+    Complete,
+    Timeout,
+}
+
+impl fmt::Display for CatalogSyncStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CatalogSyncStatus::None => write!(f, "none"),
+            CatalogSyncStatus::InProgress => write!(f, "in_progress"),
+            CatalogSyncStatus::Complete => write!(f, "complete"),
+            CatalogSyncStatus::Timeout => write!(f, "timeout"),
+        }
+    }
+}
+
+// 设备目录同步进度, 按 (gb_code, SN) 跟踪 SumNum
+#[derive(Debug, Default, Clone)]
+pub struct CatalogSyncState {
+    pub status: CatalogSyncStatus,
+    // 当前同步的 SN
+    pub sn: u32,
+    // 已收到的去重条目数
+    pub received: u32,
+    // 设备声明的总条目数
+    pub sum_num: u32,
+    // 最近一次收到分包的时间戳
+    pub ts: u32,
+}
+
 // 定义存储在 sip_devices 中的设备信息结构体
 #[derive(Debug)]
 pub struct SipDeviceInfo {
@@ -22,8 +58,20 @@ pub struct SipDeviceInfo {
     pub tcp_stream_writer: Option<Arc<Mutex<OwnedWriteHalf>>>,
     // 时间戳
     pub ts: u32,
+    // 注册时间戳
+    pub register_ts: u32,
+    // 厂商
+    pub manufacturer: String,
+    // 型号
+    pub model: String,
+    // 固件版本
+    pub firmware: String,
+    // 设备状态查询结果
+    pub status: DeviceStatusRecord,
     // 子设备
     pub sub_devices: Option<Vec<Device>>,
+    // 目录同步状态
+    pub catalog_sync: CatalogSyncState,
 }
 
 // 定义存储在 gb_streams 中的流信息结构体
@@ -32,6 +80,8 @@ pub struct GbStreamInfo {
     // gb_code, _caller_id, _stream_server_ip, _stream_server_port, ts
     // 设备的 gb_code
     pub gb_code: String,
+    // 通道 ID
+    pub channel_id: String,
     // 调用者 ID
     pub caller_id: String,
     // 发起方tag
@@ -42,6 +92,10 @@ pub struct GbStreamInfo {
     pub stream_server_ip: String,
     // 流媒体 PORT
     pub stream_server_port: u16,
+    // 实时流按 (gb_code, channel_id) 共享, 回放流独占
+    pub is_live: bool,
+    // 共享同一路实时流的观看者数量
+    pub viewers: u32,
     // 时间戳
     pub ts: u32,
 }
@@ -53,8 +107,140 @@ pub struct DeviceInfo {
     pub tcp_stream: Option<Arc<Mutex<OwnedWriteHalf>>>,
 }
 
+// DeviceStatus 应答中需要保留的字段
+#[derive(Debug, Default, Clone)]
+pub struct DeviceStatusRecord {
+    // 设备上报的在线状态 ONLINE/OFFLINE
+    pub online: String,
+    // 是否正常工作 OK/ERROR
+    pub status: String,
+    // 是否编码 ON/OFF
+    pub encode: String,
+    // 是否录像 ON/OFF
+    pub record: String,
+    pub device_time: String,
+    // 报警设备状态数
+    pub alarm_num: String,
+    // 最近一次更新的时间戳
+    pub ts: u32,
+}
+
+impl From<&DeviceStatus> for DeviceStatusRecord {
+    fn from(data: &DeviceStatus) -> Self {
+        DeviceStatusRecord {
+            online: data.online.clone(),
+            status: data.status.clone(),
+            encode: data.encode.clone(),
+            record: data.record.clone(),
+            device_time: data.device_time.clone(),
+            alarm_num: data.alarm_status.num.clone().unwrap_or_default(),
+            ts: 0,
+        }
+    }
+}
+
+// 用于对外展示的设备注册信息
+#[derive(Debug, Default, Clone)]
+pub struct DeviceRecord {
+    pub gb_code: String,
+    // 注册地址
+    pub socket_addr: String,
+    // UDP 或 TCP
+    pub transport: String,
+    pub register_ts: u32,
+    // 最近一次心跳时间戳
+    pub keep_alive_ts: u32,
+    pub online: bool,
+    pub manufacturer: String,
+    pub model: String,
+    pub firmware: String,
+    pub status: DeviceStatusRecord,
+    pub channel_num: u32,
+}
+
+// 设备上报的报警记录
+#[derive(Debug, Default, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct AlarmRecord {
+    // 上报报警的设备
+    pub gb_code: String,
+    // 产生报警的通道或设备
+    pub device_id: String,
+    pub priority: String,
+    pub method: String,
+    pub alarm_type: String,
+    // 设备上报的报警时间
+    pub alarm_time: String,
+    pub description: String,
+    pub longitude: Option<f64>,
+    pub latitude: Option<f64>,
+    // 收到报警的时间戳
+    pub ts: u32,
+}
+
+impl From<&Alarm> for AlarmRecord {
+    fn from(data: &Alarm) -> Self {
+        AlarmRecord {
+            gb_code: String::new(),
+            device_id: data.device_id.clone(),
+            priority: data.alarm_priority.clone(),
+            method: data.alarm_method.clone(),
+            alarm_type: data
+                .info
+                .as_ref()
+                .map(|info| info.alarm_type.clone())
+                .unwrap_or_default(),
+            alarm_time: data.alarm_time.clone(),
+            description: data.alarm_description.clone(),
+            longitude: data.longitude,
+            latitude: data.latitude,
+            ts: 0,
+        }
+    }
+}
+
+// 对讲会话, 以 INVITE 的 Call-ID 关联, 设备挂断时据此释放流
+#[derive(Debug, Default, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct TalkSession {
+    pub caller_id: String,
+    pub gb_code: String,
+    pub stream_id: u32,
+}
+
+// 移动设备上报的轨迹点
+#[derive(Debug, Default, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct TrackPoint {
+    pub gb_code: String,
+    pub device_id: String,
+    // 设备上报的定位时间
+    pub time: String,
+    pub longitude: f64,
+    pub latitude: f64,
+    pub speed: Option<f64>,
+    pub direction: Option<f64>,
+    pub altitude: Option<f64>,
+    // 收到位置的时间戳, 轨迹按此排序
+    pub ts: u32,
+}
+
+impl From<&MobilePosition> for TrackPoint {
+    fn from(data: &MobilePosition) -> Self {
+        TrackPoint {
+            gb_code: String::new(),
+            device_id: data.device_id.clone(),
+            time: data.time.clone(),
+            longitude: data.longitude,
+            latitude: data.latitude,
+            speed: data.speed,
+            direction: data.direction,
+            altitude: data.altitude,
+            ts: 0,
+        }
+    }
+}
+
 // 用于表示 invite 操作的返回结果的结构体
 pub struct InviteResult {
+    // 通道已有实时流, 本次只增加了观看者
     pub success: bool,
     pub channel_id: String,
     pub stream_id: u32,
@@ -65,6 +251,7 @@ pub struct InviteResult {
 
 // 用于表示 bye 操作的返回结果的结构体
 pub struct ByeResult {
+    // 最后一个观看者离开, 需要向设备发送 BYE
     pub success: bool,
     pub caller_id: String,
     pub from_tag: String,
@@ -79,6 +266,11 @@ pub trait StoreEngine: Send + Sync {
         false
     }
 
+    // 本实例的标识, 上报给媒体节点负载均衡
+    fn service_id(&self) -> String {
+        String::new()
+    }
+
     fn set_global_sn(&self, _v: u32) {}
 
     fn add_fetch_global_sn(&self) -> u32 {
@@ -105,10 +297,31 @@ pub trait StoreEngine: Send + Sync {
         None
     }
 
+    fn save_device_info(&self, _gb_code: &str, _data: &crate::sip::message::DeviceInfo) -> bool {
+        false
+    }
+
+    fn save_device_status(&self, _gb_code: &str, _data: &DeviceStatus) -> bool {
+        false
+    }
+
+    fn list_devices(&self) -> Vec<DeviceRecord> {
+        vec![]
+    }
+
+    fn find_device_record(&self, _gb_code: &str) -> Option<DeviceRecord> {
+        None
+    }
+
     fn find_gb_code_by_caller_id(&self, _key: &str) -> Option<String> {
         None
     }
 
+    // 通道编码 -> 所属设备, 由目录同步与目录事件维护
+    fn find_gb_code_by_channel_id(&self, _channel_id: &str) -> Option<String> {
+        None
+    }
+
     fn find_gb_code(&self, _stream_id: u32) -> String {
         String::new()
     }
@@ -131,7 +344,20 @@ pub trait StoreEngine: Send + Sync {
         false
     }
 
-    fn save_catalog(&self, _gb_code: &str, _data: Catalog) -> bool {
+    fn save_catalog(&self, _gb_code: &str, _data: Catalog) -> CatalogSyncState {
+        CatalogSyncState::default()
+    }
+
+    fn find_catalog(&self, _gb_code: &str) -> Option<Vec<Device>> {
+        None
+    }
+
+    fn find_catalog_sync_state(&self, _gb_code: &str) -> Option<CatalogSyncState> {
+        None
+    }
+
+    // 增量应用目录订阅事件, 返回通道列表是否发生变化
+    fn apply_catalog_event(&self, _gb_code: &str, _event: CatalogEvent, _item: &Device) -> bool {
         false
     }
 
@@ -160,10 +386,60 @@ pub trait StoreEngine: Send + Sync {
         None
     }
 
+    // 忽略剩余观看者直接结束会话, 用于流超时
+    fn bye_all(&self, gb_code: &str, stream_id: u32) -> Option<ByeResult> {
+        self.bye(gb_code, stream_id)
+    }
+
     fn stream_keep_alive(&self, _gb_code: &str, _stream_id: u32) -> bool {
         false
     }
 
+    fn save_talk(&self, _talk: &TalkSession) -> bool {
+        false
+    }
+
+    // 设备挂断时按 Call-ID 取出并删除对讲会话
+    fn take_talk(&self, _caller_id: &str) -> Option<TalkSession> {
+        None
+    }
+
+    // 按流查找对讲会话, 用于确认 stream_id 是对讲流
+    fn find_talk(&self, _gb_code: &str, _stream_id: u32) -> Option<TalkSession> {
+        None
+    }
+
+    // 流关闭时删除对应的对讲会话
+    fn remove_talk(&self, _gb_code: &str, _stream_id: u32) -> bool {
+        false
+    }
+
+    // 报警历史按 alarm_history_size 保留最近的条目
+    fn save_alarm(&self, _alarm: &AlarmRecord) -> bool {
+        false
+    }
+
+    // 按接收时间倒序, gb_code 为空时不限设备, end_ts 为 0 时不限结束时间
+    fn list_alarms(
+        &self,
+        _gb_code: &str,
+        _start_ts: u32,
+        _end_ts: u32,
+        _limit: usize,
+    ) -> Vec<AlarmRecord> {
+        vec![]
+    }
+
+    // 每台设备按 track_history_size 保留最近的轨迹点
+    fn save_track_point(&self, _point: &TrackPoint) -> bool {
+        false
+    }
+
+    // 按时间正序, to_ts 为 0 时不限结束时间
+    fn list_track(&self, _gb_code: &str, _from_ts: u32, _to_ts: u32) -> Vec<TrackPoint> {
+        vec![]
+    }
+
     fn start_timeout_check(
         &mut self,
         _timeout_devices_sender: mpsc::Sender<Option<String>>,
@@ -174,10 +450,58 @@ pub trait StoreEngine: Send + Sync {
     fn stop_timeout_check(&mut self) {}
 }
 
+// DeviceInfo/DeviceStatus 只保存设备自身且成功的应答, 通道的应答只用于查询回调
+pub fn is_own_reply(gb_code: &str, device_id: &str, result: &str) -> bool {
+    device_id == gb_code && result != "ERROR"
+}
+
+// 同步客户端会阻塞当前线程, 在 tokio 多线程运行时中先把工作线程上的其他任务移走, redis 与 sql 存储共用
+pub fn blocking<T>(f: impl FnOnce() -> T) -> T {
+    match tokio::runtime::Handle::try_current() {
+        Ok(handle) if handle.runtime_flavor() == tokio::runtime::RuntimeFlavor::MultiThread => {
+            tokio::task::block_in_place(f)
+        }
+        _ => f(),
+    }
+}
+
+// 在内存中的通道列表上应用目录事件, memory 与 redis 存储共用
+pub fn apply_catalog_event_to(items: &mut Vec<Device>, event: CatalogEvent, item: &Device) -> bool {
+    if let Some(status) = event.status() {
+        return match items.iter_mut().find(|c| c.device_id == item.device_id) {
+            Some(channel) if channel.status.as_deref() != Some(status) => {
+                channel.status = Some(status.to_string());
+                true
+            }
+            _ => false,
+        };
+    }
+
+    match event {
+        CatalogEvent::Del => {
+            let len = items.len();
+            items.retain(|c| c.device_id != item.device_id);
+            items.len() != len
+        }
+        _ => {
+            let mut channel = item.clone();
+            channel.event = None;
+            match items.iter_mut().find(|c| c.device_id == item.device_id) {
+                Some(existing) => *existing = channel,
+                None => {
+                    items.push(channel);
+                    items.sort_by(|a, b| a.device_id.cmp(&b.device_id));
+                }
+            }
+            true
+        }
+    }
+}
+
 pub fn create_store(config: &Config) -> Box<dyn StoreEngine> {
     match config.store_engine.as_str() {
         "memory" => Box::new(memory::MemoryStore::new(config)),
-        "mysql" => Box::new(mysql::MySqlStore::new(config)),
+        "mysql" | "sqlite" => Box::new(mysql::MySqlStore::new(config)),
         "redis" => Box::new(redis::RedisStore::new(config)),
         _ => {
             tracing::error!("not impl store engine: {}", &config.store_engine);
@@ -21,7 +21,11 @@ impl NotImplStore {
     }
 }
 
-impl StoreEngine for NotImplStore {}
+impl StoreEngine for NotImplStore {
+    fn service_id(&self) -> String {
+        self.service_id.clone()
+    }
+}
 
 unsafe impl Send for NotImplStore {}
 
@@ -1,28 +1,1760 @@
-use tokio;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use ::redis::Commands;
+use dashmap::DashMap;
+use tokio::sync::Mutex;
 use uuid::Uuid;
 
 use super::StoreEngine;
+use super::{
+    blocking, AlarmRecord, ByeResult, CatalogSyncState, CatalogSyncStatus, DeviceInfo,
+    DeviceRecord, DeviceStatusRecord, InviteResult, TalkSession, TrackPoint,
+};
+use crate::sip::message::{Catalog, CatalogEvent, Device, DeviceStatus};
 
 use crate::utils::config::Config;
 
+// 所有键统一加前缀, 便于与其他业务共用同一个 redis
+static KEY_PREFIX: &str = "wvprs";
+static CONNECT_TIMEOUT_SECONDS: u64 = 3;
+
+fn counter_key(name: &str) -> String {
+    format!("{}:counter:{}", KEY_PREFIX, name)
+}
+
+fn devices_key() -> String {
+    format!("{}:devices", KEY_PREFIX)
+}
+
+fn device_key(gb_code: &str) -> String {
+    format!("{}:device:{}", KEY_PREFIX, gb_code)
+}
+
+fn catalog_key(gb_code: &str) -> String {
+    format!("{}:catalog:{}", KEY_PREFIX, gb_code)
+}
+
+fn catalog_pending_key(gb_code: &str, sn: u32) -> String {
+    format!("{}:catalog_pending:{}:{}", KEY_PREFIX, gb_code, sn)
+}
+
+// channel_id -> gb_code
+fn channel_owners_key() -> String {
+    format!("{}:channel_owners", KEY_PREFIX)
+}
+
+fn stream_key(stream_id: u32) -> String {
+    format!("{}:stream:{}", KEY_PREFIX, stream_id)
+}
+
+// stream_id -> gb_code, 流过期后用于找回所属设备
+fn stream_owners_key() -> String {
+    format!("{}:stream_owners", KEY_PREFIX)
+}
+
+// 流的对话字段 {stream_id}:{name}, 不设 TTL, 流过期被删除后仍能据此向设备发 BYE
+fn stream_dialogs_key() -> String {
+    format!("{}:stream_dialogs", KEY_PREFIX)
+}
+
+// 通道正在共享的实时流 stream_id
+fn live_stream_key(gb_code: &str, channel_id: &str) -> String {
+    format!("{}:live_stream:{}:{}", KEY_PREFIX, gb_code, channel_id)
+}
+
+// caller_id -> 对讲会话
+fn talks_key() -> String {
+    format!("{}:talks", KEY_PREFIX)
+}
+
+// 报警历史, 新的在前
+fn alarms_key() -> String {
+    format!("{}:alarms", KEY_PREFIX)
+}
+
+// 设备轨迹, 按时间正序
+fn track_key(gb_code: &str) -> String {
+    format!("{}:track:{}", KEY_PREFIX, gb_code)
+}
+
+fn caller_key(caller_id: &str) -> String {
+    format!("{}:caller:{}", KEY_PREFIX, caller_id)
+}
+
+fn from_tag_key(from_tag: &str) -> String {
+    format!("{}:from_tag:{}", KEY_PREFIX, from_tag)
+}
+
+// 建立会话: 通道已有实时流时只增加观看者, 否则分配 stream_id 并写入关联键
+// KEYS: live_stream, counter, stream_owners, caller, from_tag, stream_dialogs
+// ARGV: stream 前缀, gb_code, channel_id, caller_id, from_tag, is_live, 超时秒数, ts,
+//       设备 branch, 设备地址
+// 返回 {stream_id, 是否复用已有流}
+static INVITE_SCRIPT: &str = r"
+local is_live = ARGV[6] == '1'
+if is_live then
+    local stream_id = redis.call('GET', KEYS[1])
+    if stream_id and redis.call('EXISTS', ARGV[1] .. stream_id) == 1 then
+        redis.call('HINCRBY', ARGV[1] .. stream_id, 'viewers', 1)
+        return {tonumber(stream_id), 1}
+    end
+end
+local stream_id = redis.call('INCRBY', KEYS[2], 1)
+local key = ARGV[1] .. stream_id
+redis.call('HSET', key, 'gb_code', ARGV[2], 'channel_id', ARGV[3], 'caller_id', ARGV[4],
+    'from_tag', ARGV[5], 'to_tag', '', 'stream_server_ip', '', 'stream_server_port', '0',
+    'is_live', ARGV[6], 'viewers', '1', 'ts', ARGV[8])
+redis.call('EXPIRE', key, ARGV[7])
+redis.call('HSET', KEYS[3], stream_id, ARGV[2])
+redis.call('HSET', KEYS[6], stream_id .. ':caller_id', ARGV[4], stream_id .. ':from_tag', ARGV[5],
+    stream_id .. ':to_tag', '', stream_id .. ':is_live', ARGV[6],
+    stream_id .. ':channel_id', ARGV[3], stream_id .. ':branch', ARGV[9],
+    stream_id .. ':socket_addr', ARGV[10])
+redis.call('SETEX', KEYS[4], ARGV[7], ARGV[2])
+redis.call('SETEX', KEYS[5], ARGV[7], stream_id)
+if is_live then
+    redis.call('SET', KEYS[1], stream_id)
+end
+return {stream_id, 0}
+";
+
+// 结束会话: 减少一个观看者, 最后一个观看者离开或 force 时删除会话及关联键
+// 流已过期时从 stream_dialogs 中取对话字段, 仍按最后一个观看者处理
+// KEYS: stream, stream_owners, stream_dialogs
+// ARGV: force, stream_id, caller 前缀, from_tag 前缀, 本设备 live_stream 前缀
+// 返回 {是否需要向设备发 BYE, caller_id, from_tag, to_tag, 设备 branch, 设备地址},
+// 会话不存在时为空
+static CLOSE_STREAM_SCRIPT: &str = r"
+local id = ARGV[2]
+local d = redis.call('HMGET', KEYS[3], id .. ':caller_id', id .. ':from_tag', id .. ':to_tag',
+    id .. ':is_live', id .. ':channel_id', id .. ':branch', id .. ':socket_addr')
+local exists = redis.call('EXISTS', KEYS[1]) == 1
+if not exists and not d[1] then
+    return {}
+end
+local branch, socket_addr = d[6] or '', d[7] or ''
+if exists then
+    local f = redis.call('HMGET', KEYS[1], 'caller_id', 'from_tag', 'to_tag', 'is_live',
+        'channel_id', 'viewers')
+    if ARGV[1] ~= '1' and tonumber(f[6] or '0') > 1 then
+        redis.call('HINCRBY', KEYS[1], 'viewers', -1)
+        return {'0', f[1] or '', f[2] or '', f[3] or '', branch, socket_addr}
+    end
+    d = f
+end
+local caller_id, from_tag, to_tag = d[1] or '', d[2] or '', d[3] or ''
+redis.call('DEL', KEYS[1])
+redis.call('HDEL', KEYS[2], id)
+redis.call('HDEL', KEYS[3], id .. ':caller_id', id .. ':from_tag', id .. ':to_tag',
+    id .. ':is_live', id .. ':channel_id', id .. ':branch', id .. ':socket_addr')
+redis.call('DEL', ARGV[3] .. caller_id)
+redis.call('DEL', ARGV[4] .. from_tag)
+if d[4] == '1' then
+    local live_key = ARGV[5] .. (d[5] or '')
+    if redis.call('GET', live_key) == id then
+        redis.call('DEL', live_key)
+    end
+end
+return {'1', caller_id, from_tag, to_tag, branch, socket_addr}
+";
+
+// 仅在键存在时写入字段, 避免给已过期的设备/流重建一个没有 TTL 的哈希
+// KEYS: key
+// ARGV: field1, value1, field2, value2, ...
+// 返回是否写入
+static HSET_IF_EXISTS_SCRIPT: &str = r"
+if redis.call('EXISTS', KEYS[1]) == 0 then
+    return 0
+end
+redis.call('HSET', KEYS[1], unpack(ARGV))
+return 1
+";
+
+fn ts_now() -> u32 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .expect("Time went backwards")
+        .as_secs() as u32
+}
+
+fn field_str(fields: &HashMap<String, String>, name: &str) -> String {
+    fields.get(name).cloned().unwrap_or_default()
+}
+
+fn field_u32(fields: &HashMap<String, String>, name: &str) -> u32 {
+    fields
+        .get(name)
+        .and_then(|v| v.parse::<u32>().ok())
+        .unwrap_or(0)
+}
+
+fn catalog_sync_status_from_str(s: &str) -> CatalogSyncStatus {
+    match s {
+        "in_progress" => CatalogSyncStatus::InProgress,
+        "complete" => CatalogSyncStatus::Complete,
+        "timeout" => CatalogSyncStatus::Timeout,
+        _ => CatalogSyncStatus::None,
+    }
+}
+
+// 删除设备目录中通道的归属记录, 需在覆盖或删除目录之前调用
+fn remove_channel_owners(
+    conn: &mut ::redis::Connection,
+    gb_code: &str,
+) -> ::redis::RedisResult<()> {
+    let value: Option<String> = conn.get(catalog_key(gb_code))?;
+    let items: Vec<Device> = value
+        .and_then(|value| serde_json::from_str(&value).ok())
+        .unwrap_or_default();
+    for item in items {
+        let owner: Option<String> = conn.hget(channel_owners_key(), &item.device_id)?;
+        if owner.as_deref() == Some(gb_code) {
+            conn.hdel::<_, _, ()>(channel_owners_key(), &item.device_id)?;
+        }
+    }
+    Ok(())
+}
+
+// 处理一条过期事件, 清理关联的键并通知上层
+fn on_key_expired(
+    conn: &mut ::redis::Connection,
+    key: &str,
+    tcp_stream_writers: &DashMap<String, Arc<Mutex<tokio::net::tcp::OwnedWriteHalf>>>,
+    timeout_devices_sender: &std::sync::mpsc::Sender<Option<String>>,
+    timeout_streams_sender: &std::sync::mpsc::Sender<Option<(String, u32)>>,
+) -> ::redis::RedisResult<()> {
+    let device_prefix = device_key("");
+    let stream_prefix = stream_key(0);
+    let stream_prefix = stream_prefix.trim_end_matches('0');
+
+    if let Some(gb_code) = key.strip_prefix(&device_prefix) {
+        conn.srem::<_, _, ()>(devices_key(), gb_code)?;
+        remove_channel_owners(conn, gb_code)?;
+        conn.del::<_, ()>(catalog_key(gb_code))?;
+        tcp_stream_writers.remove(gb_code);
+        if let Err(e) = timeout_devices_sender.send(Some(gb_code.to_string())) {
+            tracing::error!("timeout_devices_sender.send error, e: {:?}", e);
+        }
+    } else if let Some(stream_id) = key
+        .strip_prefix(stream_prefix)
+        .and_then(|id| id.parse::<u32>().ok())
+    {
+        // 通道的共享记录在下次 invite 时发现流已不存在再覆盖
+        let gb_code: Option<String> = conn.hget(stream_owners_key(), stream_id)?;
+        conn.hdel::<_, _, ()>(stream_owners_key(), stream_id)?;
+        if let Some(gb_code) = gb_code {
+            if let Err(e) = timeout_streams_sender.send(Some((gb_code, stream_id))) {
+                tracing::error!("timeout_streams_sender.send error, e: {:?}", e);
+            }
+        }
+    }
+    Ok(())
+}
+
 pub struct RedisStore {
-    pub quit_flag: bool,
-    pub task_handle: Option<tokio::task::JoinHandle<()>>,
+    pub quit_flag: Arc<AtomicBool>,
+    pub task_handle: Option<std::thread::JoinHandle<()>>,
     pub service_id: String, // random generated on boot, report to load balance
+    pub stream_timeout_seconds: u32,
+    pub device_timeout_seconds: u32,
+    pub catalog_timeout_seconds: u32,
+    pub alarm_history_size: u32,
+    pub track_history_size: u32,
+    client: Option<::redis::Client>,
+    // 空闲连接, 执行命令时取出, 不在持锁期间访问网络
+    connections: std::sync::Mutex<Vec<::redis::Connection>>,
+    invite_script: ::redis::Script,
+    close_stream_script: ::redis::Script,
+    hset_if_exists_script: ::redis::Script,
+    // redis 不可用时 SN 和 CSeq 在本进程内继续递增
+    counters: DashMap<String, u32>,
+    // TCP 写端无法序列化, 只保存在本进程
+    tcp_stream_writers: Arc<DashMap<String, Arc<Mutex<tokio::net::tcp::OwnedWriteHalf>>>>,
 }
 
 impl RedisStore {
-    pub fn new(_config: &Config) -> Self {
+    pub fn new(config: &Config) -> Self {
+        let client = match ::redis::Client::open(config.store_url.as_str()) {
+            Ok(client) => Some(client),
+            Err(e) => {
+                tracing::error!(
+                    "redis::Client::open({}) error, e: {:?}",
+                    &config.store_url,
+                    e
+                );
+                None
+            }
+        };
+
         RedisStore {
-            quit_flag: true,
+            quit_flag: Arc::new(AtomicBool::new(true)),
             task_handle: None,
             service_id: Uuid::new_v4().to_string(),
+            stream_timeout_seconds: config.stream_timeout_seconds,
+            device_timeout_seconds: config.device_timeout_seconds,
+            catalog_timeout_seconds: config.catalog_timeout_seconds,
+            alarm_history_size: config.alarm_history_size,
+            track_history_size: config.track_history_size,
+            client,
+            connections: std::sync::Mutex::new(Vec::new()),
+            invite_script: ::redis::Script::new(INVITE_SCRIPT),
+            close_stream_script: ::redis::Script::new(CLOSE_STREAM_SCRIPT),
+            hset_if_exists_script: ::redis::Script::new(HSET_IF_EXISTS_SCRIPT),
+            counters: DashMap::new(),
+            tcp_stream_writers: Arc::new(DashMap::new()),
+        }
+    }
+
+    // 从空闲连接中取一条执行命令, 出错的连接直接丢弃, 下次调用时重连
+    fn exec<T>(
+        &self,
+        f: impl FnOnce(&mut ::redis::Connection) -> ::redis::RedisResult<T>,
+    ) -> Option<T> {
+        let client = self.client.as_ref()?;
+        blocking(|| {
+            let idle = self
+                .connections
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .pop();
+            let mut conn = match idle {
+                Some(conn) => conn,
+                None => match client
+                    .get_connection_with_timeout(Duration::from_secs(CONNECT_TIMEOUT_SECONDS))
+                {
+                    Ok(conn) => conn,
+                    Err(e) => {
+                        tracing::error!("redis get_connection error, e: {:?}", e);
+                        return None;
+                    }
+                },
+            };
+
+            match f(&mut conn) {
+                Ok(v) => {
+                    self.connections
+                        .lock()
+                        .unwrap_or_else(|e| e.into_inner())
+                        .push(conn);
+                    Some(v)
+                }
+                Err(e) => {
+                    tracing::error!("redis command error, e: {:?}", e);
+                    if !e.is_io_error() && !e.is_connection_dropped() {
+                        self.connections
+                            .lock()
+                            .unwrap_or_else(|e| e.into_inner())
+                            .push(conn);
+                    }
+                    None
+                }
+            }
+        })
+    }
+
+    fn incr(&self, name: &str) -> Option<u32> {
+        self.exec(|conn| conn.incr::<_, _, u32>(counter_key(name), 1))
+    }
+
+    // 优先使用 redis 中的计数, 失败时从本进程上一次的值继续, 不会回到 0
+    fn next_counter(&self, name: &str) -> u32 {
+        let v = self.incr(name);
+        let mut counter = self.counters.entry(name.to_string()).or_insert(0);
+        *counter = match v {
+            Some(v) => v,
+            None => counter.wrapping_add(1).max(1),
+        };
+        *counter
+    }
+
+    fn set_counter(&self, name: &str, v: u32) {
+        self.counters.insert(name.to_string(), v);
+        self.exec(|conn| conn.set::<_, _, ()>(counter_key(name), v));
+    }
+
+    // 仅在键存在时写入字段, 检查和写入在同一脚本中完成
+    fn hset_if_exists(&self, key: &str, items: &[(&str, String)]) -> bool {
+        self.exec(|conn| {
+            let mut invocation = self.hset_if_exists_script.key(key);
+            for (field, value) in items {
+                invocation.arg(*field).arg(value);
+            }
+            invocation.invoke::<bool>(conn)
+        })
+        .unwrap_or(false)
+    }
+
+    fn device_fields(&self, gb_code: &str) -> Option<HashMap<String, String>> {
+        self.exec(|conn| conn.hgetall::<_, HashMap<String, String>>(device_key(gb_code)))
+            .filter(|fields| !fields.is_empty())
+    }
+
+    // 减少一个观看者, 最后一个观看者离开或 force 时删除会话
+    fn close_stream(&self, gb_code: &str, stream_id: u32, force: bool) -> Option<ByeResult> {
+        let reply: Vec<String> = self.exec(|conn| {
+            self.close_stream_script
+                .key(stream_key(stream_id))
+                .key(stream_owners_key())
+                .key(stream_dialogs_key())
+                .arg(force as u32)
+                .arg(stream_id)
+                .arg(caller_key(""))
+                .arg(from_tag_key(""))
+                .arg(live_stream_key(gb_code, ""))
+                .invoke(conn)
+        })?;
+        let [bye_to_device, caller_id, from_tag, to_tag, branch, socket_addr] =
+            <[String; 6]>::try_from(reply).ok()?;
+
+        // 设备也已过期时使用建立会话时记录的地址
+        let DeviceInfo {
+            branch,
+            socket_addr,
+            tcp_stream,
+        } = match self.find_device_by_gb_code(gb_code) {
+            Some(device) => device,
+            None => DeviceInfo {
+                branch,
+                socket_addr: socket_addr.parse().ok()?,
+                tcp_stream: None,
+            },
+        };
+        Some(ByeResult {
+            success: bye_to_device == "1",
+            caller_id,
+            from_tag,
+            to_tag,
+            branch,
+            socket_addr,
+            tcp_stream,
+        })
+    }
+
+    fn device_record(&self, gb_code: &str, fields: &HashMap<String, String>) -> DeviceRecord {
+        let keep_alive_ts = field_u32(fields, "ts");
+        DeviceRecord {
+            gb_code: gb_code.to_string(),
+            socket_addr: field_str(fields, "socket_addr"),
+            transport: field_str(fields, "transport"),
+            register_ts: field_u32(fields, "register_ts"),
+            keep_alive_ts,
+            online: ts_now().saturating_sub(keep_alive_ts) <= self.device_timeout_seconds,
+            manufacturer: field_str(fields, "manufacturer"),
+            model: field_str(fields, "model"),
+            firmware: field_str(fields, "firmware"),
+            status: DeviceStatusRecord {
+                online: field_str(fields, "status_online"),
+                status: field_str(fields, "status_status"),
+                encode: field_str(fields, "status_encode"),
+                record: field_str(fields, "status_record"),
+                device_time: field_str(fields, "status_device_time"),
+                alarm_num: field_str(fields, "status_alarm_num"),
+                ts: field_u32(fields, "status_ts"),
+            },
+            channel_num: field_u32(fields, "channel_num"),
         }
     }
 }
 
-impl StoreEngine for RedisStore {}
+impl StoreEngine for RedisStore {
+    fn is_connected(&self) -> bool {
+        self.exec(|conn| ::redis::cmd("PING").query::<String>(conn))
+            .is_some_and(|pong| pong == "PONG")
+    }
+
+    fn service_id(&self) -> String {
+        self.service_id.clone()
+    }
+
+    fn set_global_sn(&self, v: u32) {
+        self.set_counter("sn", v);
+    }
+
+    fn add_fetch_global_sn(&self) -> u32 {
+        self.next_counter("sn")
+    }
+
+    fn set_register_sequence(&self, seq: u32) {
+        self.set_counter("register_sequence", seq);
+    }
+
+    fn add_fetch_register_sequence(&self) -> u32 {
+        self.next_counter("register_sequence")
+    }
+
+    fn set_global_sequence(&self, seq: u32) {
+        self.set_counter("global_sequence", seq);
+    }
+
+    fn add_fetch_global_sequence(&self) -> u32 {
+        self.next_counter("global_sequence")
+    }
+
+    fn find_device_by_gb_code(&self, key: &str) -> Option<DeviceInfo> {
+        let fields = self.device_fields(key)?;
+        let socket_addr = match field_str(&fields, "socket_addr").parse() {
+            Ok(addr) => addr,
+            Err(e) => {
+                tracing::error!("invalid socket_addr of device {}, e: {:?}", key, e);
+                return None;
+            }
+        };
+        Some(DeviceInfo {
+            branch: field_str(&fields, "branch"),
+            socket_addr,
+            tcp_stream: self
+                .tcp_stream_writers
+                .get(key)
+                .map(|writer| writer.value().clone()),
+        })
+    }
+
+    fn find_device_by_stream_id(&self, key: u32) -> Option<DeviceInfo> {
+        let gb_code = self.find_gb_code(key);
+        if !gb_code.is_empty() {
+            return self.find_device_by_gb_code(&gb_code);
+        }
+        None
+    }
+
+    fn save_device_info(&self, gb_code: &str, data: &crate::sip::message::DeviceInfo) -> bool {
+        if !super::is_own_reply(gb_code, &data.device_id, &data.result) {
+            return false;
+        }
+        self.hset_if_exists(
+            &device_key(gb_code),
+            &[
+                ("manufacturer", data.manufacturer.clone()),
+                ("model", data.model.clone()),
+                ("firmware", data.firmware.clone()),
+            ],
+        )
+    }
+
+    fn save_device_status(&self, gb_code: &str, data: &DeviceStatus) -> bool {
+        if !super::is_own_reply(gb_code, &data.device_id, &data.result) {
+            return false;
+        }
+        let status = DeviceStatusRecord::from(data);
+        self.hset_if_exists(
+            &device_key(gb_code),
+            &[
+                ("status_online", status.online),
+                ("status_status", status.status),
+                ("status_encode", status.encode),
+                ("status_record", status.record),
+                ("status_device_time", status.device_time),
+                ("status_alarm_num", status.alarm_num),
+                ("status_ts", ts_now().to_string()),
+            ],
+        )
+    }
+
+    fn list_devices(&self) -> Vec<DeviceRecord> {
+        let gb_codes = self
+            .exec(|conn| conn.smembers::<_, Vec<String>>(devices_key()))
+            .unwrap_or_default();
+
+        let mut devices = Vec::with_capacity(gb_codes.len());
+        for gb_code in gb_codes {
+            match self.device_fields(&gb_code) {
+                Some(fields) => devices.push(self.device_record(&gb_code, &fields)),
+                // 错过了过期通知, 顺带清理
+                None => {
+                    self.exec(|conn| conn.srem::<_, _, ()>(devices_key(), &gb_code));
+                }
+            }
+        }
+        devices.sort_by(|a, b| a.gb_code.cmp(&b.gb_code));
+        devices
+    }
+
+    fn find_device_record(&self, gb_code: &str) -> Option<DeviceRecord> {
+        self.device_fields(gb_code)
+            .map(|fields| self.device_record(gb_code, &fields))
+    }
+
+    fn find_gb_code_by_channel_id(&self, channel_id: &str) -> Option<String> {
+        self.exec(|conn| conn.hget::<_, _, Option<String>>(channel_owners_key(), channel_id))
+            .flatten()
+    }
+
+    fn find_gb_code_by_caller_id(&self, key: &str) -> Option<String> {
+        self.exec(|conn| conn.get::<_, Option<String>>(caller_key(key)))
+            .flatten()
+    }
+
+    fn find_gb_code(&self, stream_id: u32) -> String {
+        self.exec(|conn| conn.hget::<_, _, Option<String>>(stream_key(stream_id), "gb_code"))
+            .flatten()
+            .unwrap_or_default()
+    }
+
+    fn register(
+        &self,
+        branch: &str,
+        gb_code: &str,
+        socket_addr: std::net::SocketAddr,
+        tcp_stream: &Option<Arc<Mutex<tokio::net::tcp::OwnedWriteHalf>>>,
+    ) -> bool {
+        let key = device_key(gb_code);
+        let ts = ts_now().to_string();
+        let transport = if tcp_stream.is_some() { "TCP" } else { "UDP" };
+        let device_timeout_seconds = self.device_timeout_seconds as i64;
+
+        // HSETNX 保证同一设备只注册一次
+        let registered = self
+            .exec(|conn| {
+                if !conn.hset_nx::<_, _, _, bool>(&key, "branch", branch)? {
+                    return Ok(false);
+                }
+                conn.hset_multiple::<_, _, _, ()>(
+                    &key,
+                    &[
+                        ("socket_addr", socket_addr.to_string()),
+                        ("transport", transport.to_string()),
+                        ("ts", ts.clone()),
+                        ("register_ts", ts.clone()),
+                    ],
+                )?;
+                conn.expire::<_, ()>(&key, device_timeout_seconds)?;
+                conn.sadd::<_, _, ()>(devices_key(), gb_code)?;
+                Ok(true)
+            })
+            .unwrap_or(false);
+
+        if registered {
+            if let Some(writer) = tcp_stream {
+                self.tcp_stream_writers
+                    .insert(gb_code.to_string(), writer.clone());
+            }
+        }
+        registered
+    }
+
+    fn unregister(&self, gb_code: &str) -> bool {
+        self.tcp_stream_writers.remove(gb_code);
+        self.exec(|conn| {
+            let removed: u32 = conn.del(device_key(gb_code))?;
+            remove_channel_owners(conn, gb_code)?;
+            conn.del::<_, ()>(catalog_key(gb_code))?;
+            conn.srem::<_, _, ()>(devices_key(), gb_code)?;
+            Ok(removed > 0)
+        })
+        .unwrap_or(false)
+    }
+
+    fn register_keep_alive(&self, gb_code: &str) -> bool {
+        let key = device_key(gb_code);
+        let device_timeout_seconds = self.device_timeout_seconds as i64;
+        self.exec(|conn| {
+            if !conn.expire::<_, bool>(&key, device_timeout_seconds)? {
+                return Ok(false);
+            }
+            conn.hset::<_, _, _, ()>(&key, "ts", ts_now())?;
+            Ok(true)
+        })
+        .unwrap_or(false)
+    }
+
+    fn save_catalog(&self, gb_code: &str, data: Catalog) -> CatalogSyncState {
+        let key = device_key(gb_code);
+        let pending_key = catalog_pending_key(gb_code, data.sn);
+        let catalog_timeout_seconds = self.catalog_timeout_seconds as i64;
+
+        // 按 DeviceID 去重累积, 未收齐的分包随 TTL 自动丢弃
+        self.exec(|conn| {
+            if !conn.exists::<_, bool>(&key)? {
+                return Ok(CatalogSyncState::default());
+            }
+
+            let ts = ts_now();
+            for device in &data.device_list.items {
+                let value = serde_json::to_string(device).unwrap_or_default();
+                conn.hset::<_, _, _, ()>(&pending_key, &device.device_id, value)?;
+            }
+            conn.expire::<_, ()>(&pending_key, catalog_timeout_seconds)?;
+
+            let received: u32 = conn.hlen(&pending_key)?;
+            let complete = received >= data.sum_num;
+            if complete {
+                let values: Vec<String> = conn.hvals(&pending_key)?;
+                let mut items: Vec<Device> = values
+                    .iter()
+                    .filter_map(|v| serde_json::from_str(v).ok())
+                    .collect();
+                items.sort_by(|a, b| a.device_id.cmp(&b.device_id));
+                remove_channel_owners(conn, gb_code)?;
+                for item in &items {
+                    conn.hset::<_, _, _, ()>(channel_owners_key(), &item.device_id, gb_code)?;
+                }
+                conn.set::<_, _, ()>(
+                    catalog_key(gb_code),
+                    serde_json::to_string(&items).unwrap_or_default(),
+                )?;
+                conn.del::<_, ()>(&pending_key)?;
+                conn.hset::<_, _, _, ()>(&key, "channel_num", items.len())?;
+            }
+
+            let state = CatalogSyncState {
+                status: if complete {
+                    CatalogSyncStatus::Complete
+                } else {
+                    CatalogSyncStatus::InProgress
+                },
+                sn: data.sn,
+                received,
+                sum_num: data.sum_num,
+                ts,
+            };
+            conn.hset_multiple::<_, _, _, ()>(
+                &key,
+                &[
+                    ("catalog_status", state.status.to_string()),
+                    ("catalog_sn", state.sn.to_string()),
+                    ("catalog_received", state.received.to_string()),
+                    ("catalog_sum_num", state.sum_num.to_string()),
+                    ("catalog_ts", state.ts.to_string()),
+                ],
+            )?;
+            Ok(state)
+        })
+        .unwrap_or_default()
+    }
+
+    fn find_catalog(&self, gb_code: &str) -> Option<Vec<Device>> {
+        let value = self
+            .exec(|conn| conn.get::<_, Option<String>>(catalog_key(gb_code)))
+            .flatten()?;
+        match serde_json::from_str(&value) {
+            Ok(items) => Some(items),
+            Err(e) => {
+                tracing::error!("invalid catalog of device {}, e: {:?}", gb_code, e);
+                None
+            }
+        }
+    }
+
+    fn find_catalog_sync_state(&self, gb_code: &str) -> Option<CatalogSyncState> {
+        let fields = self.device_fields(gb_code)?;
+        let mut state = CatalogSyncState {
+            status: catalog_sync_status_from_str(&field_str(&fields, "catalog_status")),
+            sn: field_u32(&fields, "catalog_sn"),
+            received: field_u32(&fields, "catalog_received"),
+            sum_num: field_u32(&fields, "catalog_sum_num"),
+            ts: field_u32(&fields, "catalog_ts"),
+        };
+        if state.status == CatalogSyncStatus::InProgress
+            && ts_now().saturating_sub(state.ts) > self.catalog_timeout_seconds
+        {
+            state.status = CatalogSyncStatus::Timeout;
+        }
+        Some(state)
+    }
+
+    fn apply_catalog_event(&self, gb_code: &str, event: CatalogEvent, item: &Device) -> bool {
+        if self.device_fields(gb_code).is_none() {
+            return false;
+        }
+        let mut items = self.find_catalog(gb_code).unwrap_or_default();
+        if !super::apply_catalog_event_to(&mut items, event, item) {
+            return false;
+        }
+        self.exec(|conn| {
+            conn.set::<_, _, ()>(
+                catalog_key(gb_code),
+                serde_json::to_string(&items).unwrap_or_default(),
+            )?;
+            conn.hset::<_, _, _, ()>(device_key(gb_code), "channel_num", items.len())?;
+            if event == CatalogEvent::Del {
+                let owner: Option<String> = conn.hget(channel_owners_key(), &item.device_id)?;
+                if owner.as_deref() == Some(gb_code) {
+                    conn.hdel::<_, _, ()>(channel_owners_key(), &item.device_id)?;
+                }
+            } else {
+                conn.hset::<_, _, _, ()>(channel_owners_key(), &item.device_id, gb_code)?;
+            }
+            Ok(())
+        })
+        .is_some()
+    }
+
+    fn invite(
+        &self,
+        gb_code: &str,
+        channel_id: &str,
+        caller_id: &str,
+        from_tag: &str,
+        is_live: bool,
+    ) -> Option<InviteResult> {
+        let DeviceInfo {
+            branch,
+            socket_addr,
+            tcp_stream,
+        } = self.find_device_by_gb_code(gb_code)?;
+
+        let counter = counter_key(if is_live {
+            "live_stream_id"
+        } else {
+            "replay_stream_id"
+        });
+        let stream_timeout_seconds = self.stream_timeout_seconds;
+        let live_key = live_stream_key(gb_code, channel_id);
+
+        let (stream_id, is_playing): (u32, u32) = self.exec(|conn| {
+            self.invite_script
+                .key(&live_key)
+                .key(&counter)
+                .key(stream_owners_key())
+                .key(caller_key(caller_id))
+                .key(from_tag_key(from_tag))
+                .key(stream_dialogs_key())
+                .arg(stream_key(0).trim_end_matches('0'))
+                .arg(gb_code)
+                .arg(channel_id)
+                .arg(caller_id)
+                .arg(from_tag)
+                .arg(is_live as u32)
+                .arg(stream_timeout_seconds)
+                .arg(ts_now())
+                .arg(&branch)
+                .arg(socket_addr.to_string())
+                .invoke(conn)
+        })?;
+
+        Some(InviteResult {
+            success: is_playing == 1,
+            channel_id: channel_id.to_string(),
+            stream_id,
+            branch,
+            socket_addr,
+            tcp_stream,
+        })
+    }
+
+    fn update_stream_tag_info(&self, from_tag: &str, to_tag: &str) {
+        if let Some(stream_id) = self
+            .exec(|conn| conn.get::<_, Option<u32>>(from_tag_key(from_tag)))
+            .flatten()
+        {
+            if self.hset_if_exists(&stream_key(stream_id), &[("to_tag", to_tag.to_string())]) {
+                self.exec(|conn| {
+                    conn.hset::<_, _, _, ()>(
+                        stream_dialogs_key(),
+                        format!("{}:to_tag", stream_id),
+                        to_tag,
+                    )
+                });
+            }
+        }
+    }
+
+    fn update_stream_server_info(
+        &self,
+        stream_id: u32,
+        stream_server_ip: &str,
+        stream_server_port: u16,
+    ) {
+        self.hset_if_exists(
+            &stream_key(stream_id),
+            &[
+                ("stream_server_ip", stream_server_ip.to_string()),
+                ("stream_server_port", stream_server_port.to_string()),
+            ],
+        );
+    }
+
+    fn bye(&self, gb_code: &str, stream_id: u32) -> Option<ByeResult> {
+        self.close_stream(gb_code, stream_id, false)
+    }
+
+    fn bye_all(&self, gb_code: &str, stream_id: u32) -> Option<ByeResult> {
+        self.close_stream(gb_code, stream_id, true)
+    }
+
+    fn stream_keep_alive(&self, gb_code: &str, stream_id: u32) -> bool {
+        let stream_timeout_seconds = self.stream_timeout_seconds as i64;
+        self.exec(|conn| {
+            let key = stream_key(stream_id);
+            if !conn.expire::<_, bool>(&key, stream_timeout_seconds)? {
+                return Ok(false);
+            }
+            conn.hset_multiple::<_, _, _, ()>(
+                &key,
+                &[
+                    ("gb_code", gb_code.to_string()),
+                    ("ts", ts_now().to_string()),
+                ],
+            )?;
+            let (caller_id, from_tag): (Option<String>, Option<String>) = ::redis::cmd("HMGET")
+                .arg(&key)
+                .arg("caller_id")
+                .arg("from_tag")
+                .query(conn)?;
+            if let Some(caller_id) = caller_id {
+                conn.expire::<_, ()>(caller_key(&caller_id), stream_timeout_seconds)?;
+            }
+            if let Some(from_tag) = from_tag {
+                conn.expire::<_, ()>(from_tag_key(&from_tag), stream_timeout_seconds)?;
+            }
+            Ok(true)
+        })
+        .unwrap_or(false)
+    }
+
+    fn save_talk(&self, talk: &TalkSession) -> bool {
+        let Ok(value) = serde_json::to_string(talk) else {
+            return false;
+        };
+        self.exec(|conn| conn.hset::<_, _, _, ()>(talks_key(), &talk.caller_id, &value))
+            .is_some()
+    }
+
+    fn take_talk(&self, caller_id: &str) -> Option<TalkSession> {
+        self.exec(|conn| {
+            let value: Option<String> = conn.hget(talks_key(), caller_id)?;
+            // 并发挂断时只有 HDEL 成功的一方取得会话
+            let removed: u32 = conn.hdel(talks_key(), caller_id)?;
+            Ok(value.filter(|_| removed > 0))
+        })
+        .flatten()
+        .and_then(|value| serde_json::from_str(&value).ok())
+    }
+
+    fn find_talk(&self, gb_code: &str, stream_id: u32) -> Option<TalkSession> {
+        self.exec(|conn| conn.hvals::<_, Vec<String>>(talks_key()))?
+            .into_iter()
+            .filter_map(|value| serde_json::from_str::<TalkSession>(&value).ok())
+            .find(|talk| talk.gb_code == gb_code && talk.stream_id == stream_id)
+    }
+
+    fn remove_talk(&self, gb_code: &str, stream_id: u32) -> bool {
+        self.exec(|conn| {
+            let talks: HashMap<String, String> = conn.hgetall(talks_key())?;
+            let mut removed = 0;
+            for (caller_id, value) in talks {
+                if serde_json::from_str::<TalkSession>(&value)
+                    .is_ok_and(|talk| talk.gb_code == gb_code && talk.stream_id == stream_id)
+                {
+                    removed += conn.hdel::<_, _, u32>(talks_key(), caller_id)?;
+                }
+            }
+            Ok(removed > 0)
+        })
+        .unwrap_or(false)
+    }
+
+    // 设备与流的超时由 TTL 负责, 这里只订阅过期事件做清理和通知
+    fn start_timeout_check(
+        &mut self,
+        timeout_devices_sender: std::sync::mpsc::Sender<Option<String>>,
+        timeout_streams_sender: std::sync::mpsc::Sender<Option<(String, u32)>>,
+    ) {
+        let Some(client) = self.client.clone() else {
+            return;
+        };
+
+        // 托管的 redis 可能禁用了 CONFIG, 此时需要在服务端手动开启 Ex
+        if self
+            .exec(|conn| {
+                ::redis::cmd("CONFIG")
+                    .arg("SET")
+                    .arg("notify-keyspace-events")
+                    .arg("Ex")
+                    .query::<()>(conn)
+            })
+            .is_none()
+        {
+            tracing::warn!("enable redis keyspace notifications failed");
+        }
+
+        self.quit_flag.store(false, Ordering::Relaxed);
+        let quit_flag = self.quit_flag.clone();
+        let tcp_stream_writers = self.tcp_stream_writers.clone();
+        self.task_handle = Some(std::thread::spawn(move || {
+            tracing::info!("start_timeout_check begin");
+
+            while !quit_flag.load(Ordering::Relaxed) {
+                let result = (|| -> ::redis::RedisResult<()> {
+                    let mut conn = client.get_connection()?;
+                    let mut pubsub_conn = client.get_connection()?;
+                    let mut pubsub = pubsub_conn.as_pubsub();
+                    pubsub.set_read_timeout(Some(Duration::from_secs(1)))?;
+                    pubsub.psubscribe("__keyevent@*__:expired")?;
+
+                    while !quit_flag.load(Ordering::Relaxed) {
+                        let msg = match pubsub.get_message() {
+                            Ok(msg) => msg,
+                            Err(e) if e.is_timeout() => continue,
+                            Err(e) => return Err(e),
+                        };
+                        let key: String = msg.get_payload()?;
+                        on_key_expired(
+                            &mut conn,
+                            &key,
+                            &tcp_stream_writers,
+                            &timeout_devices_sender,
+                            &timeout_streams_sender,
+                        )?;
+                    }
+                    Ok(())
+                })();
+
+                if let Err(e) = result {
+                    tracing::error!("redis expired events error, e: {:?}", e);
+                    std::thread::sleep(Duration::from_secs(1));
+                }
+            }
+
+            tracing::info!("start_timeout_check end");
+        }));
+    }
+
+    fn stop_timeout_check(&mut self) {
+        self.quit_flag.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.task_handle.take() {
+            let _ = handle.join();
+        }
+    }
+
+    fn save_alarm(&self, alarm: &AlarmRecord) -> bool {
+        let Ok(value) = serde_json::to_string(alarm) else {
+            return false;
+        };
+        let size = self.alarm_history_size as isize;
+        self.exec(|conn| {
+            conn.lpush::<_, _, ()>(alarms_key(), value)?;
+            conn.ltrim::<_, ()>(alarms_key(), 0, size - 1)
+        })
+        .is_some()
+    }
+
+    fn list_alarms(
+        &self,
+        gb_code: &str,
+        start_ts: u32,
+        end_ts: u32,
+        limit: usize,
+    ) -> Vec<AlarmRecord> {
+        self.exec(|conn| conn.lrange::<_, Vec<String>>(alarms_key(), 0, -1))
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|value| serde_json::from_str::<AlarmRecord>(value).ok())
+            .filter(|alarm| gb_code.is_empty() || alarm.gb_code == gb_code)
+            .filter(|alarm| alarm.ts >= start_ts && (end_ts == 0 || alarm.ts <= end_ts))
+            .take(limit)
+            .collect()
+    }
+
+    fn save_track_point(&self, point: &TrackPoint) -> bool {
+        let Ok(value) = serde_json::to_string(point) else {
+            return false;
+        };
+        let size = self.track_history_size as isize;
+        self.exec(|conn| {
+            let key = track_key(&point.gb_code);
+            conn.rpush::<_, _, ()>(&key, value)?;
+            conn.ltrim::<_, ()>(&key, -size, -1)
+        })
+        .is_some()
+    }
+
+    fn list_track(&self, gb_code: &str, from_ts: u32, to_ts: u32) -> Vec<TrackPoint> {
+        self.exec(|conn| conn.lrange::<_, Vec<String>>(track_key(gb_code), 0, -1))
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|value| serde_json::from_str::<TrackPoint>(value).ok())
+            .filter(|point| point.ts >= from_ts && (to_ts == 0 || point.ts <= to_ts))
+            .collect()
+    }
+}
 
 unsafe impl Send for RedisStore {}
 
 unsafe impl Sync for RedisStore {}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeSet, HashMap, VecDeque};
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::sync::{Arc, Mutex};
+
+    use ::redis::Commands;
+
+    use super::{device_key, on_key_expired, stream_key, RedisStore};
+    use crate::sip::message::{Catalog, Device, DeviceInfo, DeviceList, DeviceStatus};
+    use crate::store::{
+        AlarmRecord, CatalogEvent, CatalogSyncStatus, StoreEngine, TalkSession, TrackPoint,
+    };
+    use crate::utils::config::Config;
+
+    enum Value {
+        Str(String),
+        Hash(HashMap<String, String>),
+        Set(BTreeSet<String>),
+        List(VecDeque<String>),
+    }
+
+    enum Reply {
+        Ok,
+        Int(i64),
+        Bulk(Option<String>),
+        Array(Vec<Option<String>>),
+        Multi(Vec<Reply>),
+        Error(String),
+    }
+
+    impl Reply {
+        fn write_to(&self, out: &mut impl Write) -> std::io::Result<()> {
+            match self {
+                Reply::Ok => write!(out, "+OK\r\n"),
+                Reply::Int(n) => write!(out, ":{}\r\n", n),
+                Reply::Bulk(None) => write!(out, "$-1\r\n"),
+                Reply::Bulk(Some(s)) => write!(out, "${}\r\n{}\r\n", s.len(), s),
+                Reply::Array(items) => {
+                    write!(out, "*{}\r\n", items.len())?;
+                    for item in items {
+                        Reply::Bulk(item.clone()).write_to(out)?;
+                    }
+                    Ok(())
+                }
+                Reply::Multi(items) => {
+                    write!(out, "*{}\r\n", items.len())?;
+                    for item in items {
+                        item.write_to(out)?;
+                    }
+                    Ok(())
+                }
+                Reply::Error(e) => write!(out, "-{}\r\n", e),
+            }
+        }
+
+        fn into_lua(self, lua: &mlua::Lua) -> mlua::Result<mlua::Value> {
+            Ok(match self {
+                Reply::Ok => mlua::Value::String(lua.create_string("OK")?),
+                Reply::Int(n) => mlua::Value::Integer(n),
+                Reply::Bulk(None) => mlua::Value::Boolean(false),
+                Reply::Bulk(Some(s)) => mlua::Value::String(lua.create_string(s)?),
+                Reply::Array(items) => mlua::Value::Table(
+                    lua.create_sequence_from(
+                        items
+                            .into_iter()
+                            .map(|item| Reply::Bulk(item).into_lua(lua))
+                            .collect::<mlua::Result<Vec<_>>>()?,
+                    )?,
+                ),
+                Reply::Multi(items) => mlua::Value::Table(
+                    lua.create_sequence_from(
+                        items
+                            .into_iter()
+                            .map(|item| item.into_lua(lua))
+                            .collect::<mlua::Result<Vec<_>>>()?,
+                    )?,
+                ),
+                Reply::Error(e) => return Err(mlua::Error::RuntimeError(e)),
+            })
+        }
+
+        // 按 redis 的规则把脚本返回值转换为应答, 数组遇到 nil 截断
+        fn from_lua(value: mlua::Value) -> mlua::Result<Reply> {
+            Ok(match value {
+                mlua::Value::Integer(n) => Reply::Int(n),
+                mlua::Value::Number(n) => Reply::Int(n as i64),
+                mlua::Value::Boolean(true) => Reply::Int(1),
+                mlua::Value::String(s) => Reply::Bulk(Some(s.to_str()?.to_string())),
+                mlua::Value::Table(table) => Reply::Multi(
+                    table
+                        .sequence_values::<mlua::Value>()
+                        .map(|v| Reply::from_lua(v?))
+                        .collect::<mlua::Result<Vec<_>>>()?,
+                ),
+                _ => Reply::Bulk(None),
+            })
+        }
+    }
+
+    // 用真实的 Lua 解释器执行脚本, redis.call 转发到本地命令实现
+    fn eval(db: &mut HashMap<String, Value>, script: &str, args: &[String]) -> Reply {
+        let lua = mlua::Lua::new();
+        let numkeys: usize = args[0].parse().unwrap();
+        let (keys, argv) = args[1..].split_at(numkeys);
+        let result = lua.scope(|scope| {
+            lua.globals().set("KEYS", keys.to_vec())?;
+            lua.globals().set("ARGV", argv.to_vec())?;
+            // redis 内置的是 Lua 5.1, unpack 为全局函数
+            let unpack = lua
+                .globals()
+                .get::<mlua::Table>("table")?
+                .get::<mlua::Function>("unpack")?;
+            lua.globals().set("unpack", unpack)?;
+            let call = scope.create_function_mut(|lua, args: mlua::Variadic<mlua::Value>| {
+                let args = args
+                    .iter()
+                    .map(|arg| match arg {
+                        mlua::Value::Integer(n) => Ok(n.to_string()),
+                        mlua::Value::String(s) => Ok(s.to_str()?.to_string()),
+                        _ => Err(mlua::Error::RuntimeError(format!(
+                            "unsupported argument {:?}",
+                            arg
+                        ))),
+                    })
+                    .collect::<mlua::Result<Vec<String>>>()?;
+                execute(db, &args).into_lua(lua)
+            })?;
+            let redis = lua.create_table()?;
+            redis.set("call", call)?;
+            lua.globals().set("redis", redis)?;
+            Reply::from_lua(lua.load(script).eval::<mlua::Value>()?)
+        });
+        result.unwrap_or_else(|e| Reply::Error(format!("ERR {}", e)))
+    }
+
+    // 进程内的最小 RESP 服务, 只实现本存储用到的命令, 不处理 TTL
+    fn execute(db: &mut HashMap<String, Value>, args: &[String]) -> Reply {
+        let cmd = args[0].to_uppercase();
+        let key = args.get(1).cloned().unwrap_or_default();
+        match cmd.as_str() {
+            "PING" => Reply::Bulk(Some(String::from("PONG"))),
+            "GET" => match db.get(&key) {
+                Some(Value::Str(s)) => Reply::Bulk(Some(s.clone())),
+                _ => Reply::Bulk(None),
+            },
+            "SET" => {
+                db.insert(key, Value::Str(args[2].clone()));
+                Reply::Ok
+            }
+            "SETEX" => {
+                db.insert(key, Value::Str(args[3].clone()));
+                Reply::Ok
+            }
+            "INCRBY" => {
+                let v = match db.get(&key) {
+                    Some(Value::Str(s)) => s.parse::<i64>().unwrap(),
+                    _ => 0,
+                } + args[2].parse::<i64>().unwrap();
+                db.insert(key, Value::Str(v.to_string()));
+                Reply::Int(v)
+            }
+            "HINCRBY" => {
+                let entry = db.entry(key).or_insert_with(|| Value::Hash(HashMap::new()));
+                let Value::Hash(hash) = entry else {
+                    unreachable!()
+                };
+                let v = hash
+                    .get(&args[2])
+                    .map(|s| s.parse::<i64>().unwrap())
+                    .unwrap_or(0)
+                    + args[3].parse::<i64>().unwrap();
+                hash.insert(args[2].clone(), v.to_string());
+                Reply::Int(v)
+            }
+            "DEL" => {
+                Reply::Int(args[1..].iter().filter(|k| db.remove(*k).is_some()).count() as i64)
+            }
+            "EXISTS" | "EXPIRE" => Reply::Int(db.contains_key(&key) as i64),
+            "HSET" | "HMSET" | "HSETNX" => {
+                let entry = db.entry(key).or_insert_with(|| Value::Hash(HashMap::new()));
+                let Value::Hash(hash) = entry else {
+                    unreachable!()
+                };
+                if cmd == "HSETNX" {
+                    if hash.contains_key(&args[2]) {
+                        return Reply::Int(0);
+                    }
+                    hash.insert(args[2].clone(), args[3].clone());
+                    return Reply::Int(1);
+                }
+                let mut added = 0;
+                for pair in args[2..].chunks(2) {
+                    if hash.insert(pair[0].clone(), pair[1].clone()).is_none() {
+                        added += 1;
+                    }
+                }
+                if cmd == "HMSET" {
+                    Reply::Ok
+                } else {
+                    Reply::Int(added)
+                }
+            }
+            "HGET" | "HMGET" | "HGETALL" | "HVALS" | "HLEN" | "HDEL" => {
+                let hash = match db.get_mut(&key) {
+                    Some(Value::Hash(hash)) => Some(hash),
+                    _ => None,
+                };
+                match (cmd.as_str(), hash) {
+                    ("HGET", hash) => Reply::Bulk(hash.and_then(|h| h.get(&args[2]).cloned())),
+                    ("HMGET", hash) => Reply::Array(
+                        args[2..]
+                            .iter()
+                            .map(|f| hash.as_ref().and_then(|h| h.get(f).cloned()))
+                            .collect(),
+                    ),
+                    ("HGETALL", Some(hash)) => Reply::Array(
+                        hash.iter()
+                            .flat_map(|(k, v)| [Some(k.clone()), Some(v.clone())])
+                            .collect(),
+                    ),
+                    ("HVALS", Some(hash)) => {
+                        Reply::Array(hash.values().map(|v| Some(v.clone())).collect())
+                    }
+                    ("HLEN", Some(hash)) => Reply::Int(hash.len() as i64),
+                    ("HDEL", Some(hash)) => Reply::Int(
+                        args[2..]
+                            .iter()
+                            .filter(|f| hash.remove(*f).is_some())
+                            .count() as i64,
+                    ),
+                    ("HGETALL", None) | ("HVALS", None) => Reply::Array(vec![]),
+                    _ => Reply::Int(0),
+                }
+            }
+            "SADD" => {
+                let entry = db.entry(key).or_insert_with(|| Value::Set(BTreeSet::new()));
+                let Value::Set(set) = entry else {
+                    unreachable!()
+                };
+                Reply::Int(
+                    args[2..]
+                        .iter()
+                        .filter(|m| set.insert((*m).clone()))
+                        .count() as i64,
+                )
+            }
+            "SREM" | "SCARD" | "SMEMBERS" => {
+                let set = match db.get_mut(&key) {
+                    Some(Value::Set(set)) => Some(set),
+                    _ => None,
+                };
+                match (cmd.as_str(), set) {
+                    ("SREM", Some(set)) => {
+                        Reply::Int(args[2..].iter().filter(|m| set.remove(*m)).count() as i64)
+                    }
+                    ("SCARD", Some(set)) => Reply::Int(set.len() as i64),
+                    ("SMEMBERS", Some(set)) => {
+                        Reply::Array(set.iter().map(|m| Some(m.clone())).collect())
+                    }
+                    ("SMEMBERS", None) => Reply::Array(vec![]),
+                    _ => Reply::Int(0),
+                }
+            }
+            "LPUSH" | "RPUSH" => {
+                let entry = db
+                    .entry(key)
+                    .or_insert_with(|| Value::List(VecDeque::new()));
+                let Value::List(list) = entry else {
+                    unreachable!()
+                };
+                for v in &args[2..] {
+                    if cmd == "LPUSH" {
+                        list.push_front(v.clone());
+                    } else {
+                        list.push_back(v.clone());
+                    }
+                }
+                Reply::Int(list.len() as i64)
+            }
+            "LTRIM" | "LRANGE" => {
+                let list = match db.get_mut(&key) {
+                    Some(Value::List(list)) => list,
+                    _ => return Reply::Array(vec![]),
+                };
+                let index = |s: &String| {
+                    let i = s.parse::<i64>().unwrap();
+                    if i < 0 {
+                        (list.len() as i64 + i).max(-1)
+                    } else {
+                        i.min(list.len() as i64 - 1)
+                    }
+                };
+                let (start, stop) = (index(&args[2]).max(0), index(&args[3]));
+                let items: VecDeque<String> =
+                    (start..=stop).map(|i| list[i as usize].clone()).collect();
+                if cmd == "LTRIM" {
+                    *list = items;
+                    Reply::Ok
+                } else {
+                    Reply::Array(items.into_iter().map(Some).collect())
+                }
+            }
+            // CLIENT SETINFO, SELECT, CONFIG SET 等
+            _ => Reply::Ok,
+        }
+    }
+
+    fn read_command(reader: &mut BufReader<TcpStream>) -> Option<Vec<String>> {
+        let mut line = String::new();
+        if reader.read_line(&mut line).ok()? == 0 {
+            return None;
+        }
+        let count: usize = line.trim_end().strip_prefix('*')?.parse().ok()?;
+        let mut args = Vec::with_capacity(count);
+        for _ in 0..count {
+            line.clear();
+            reader.read_line(&mut line).ok()?;
+            let len: usize = line.trim_end().strip_prefix('$')?.parse().ok()?;
+            let mut buf = vec![0u8; len + 2];
+            reader.read_exact(&mut buf).ok()?;
+            buf.truncate(len);
+            args.push(String::from_utf8(buf).ok()?);
+        }
+        Some(args)
+    }
+
+    // 脚本按 SHA1 缓存, 与服务端一样对未加载的 EVALSHA 返回 NOSCRIPT
+    fn execute_script(
+        db: &mut HashMap<String, Value>,
+        scripts: &mut HashMap<String, String>,
+        args: &[String],
+    ) -> Option<Reply> {
+        match args[0].to_uppercase().as_str() {
+            "SCRIPT" if args[1].eq_ignore_ascii_case("LOAD") => {
+                let hash = ::redis::Script::new(&args[2]).get_hash().to_string();
+                scripts.insert(hash.clone(), args[2].clone());
+                Some(Reply::Bulk(Some(hash)))
+            }
+            "EVALSHA" => Some(match scripts.get(&args[1]) {
+                Some(script) => eval(db, script, &args[2..]),
+                None => Reply::Error(String::from("NOSCRIPT No matching script.")),
+            }),
+            "EVAL" => Some(eval(db, &args[1], &args[2..])),
+            _ => None,
+        }
+    }
+
+    fn start_server() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let db = Arc::new(Mutex::new(HashMap::<String, Value>::new()));
+        let scripts = Arc::new(Mutex::new(HashMap::<String, String>::new()));
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let (db, scripts) = (db.clone(), scripts.clone());
+                std::thread::spawn(move || {
+                    stream.set_nodelay(true).unwrap();
+                    let mut writer = stream.try_clone().unwrap();
+                    let mut reader = BufReader::new(stream);
+                    while let Some(args) = read_command(&mut reader) {
+                        let mut out = Vec::new();
+                        let mut db = db.lock().unwrap();
+                        execute_script(&mut db, &mut scripts.lock().unwrap(), &args)
+                            .unwrap_or_else(|| execute(&mut db, &args))
+                            .write_to(&mut out)
+                            .unwrap();
+                        drop(db);
+                        if writer.write_all(&out).is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+        });
+        format!("redis://{}/", addr)
+    }
+
+    fn store() -> RedisStore {
+        let mut config: Config = serde_yaml::from_str("{}").unwrap();
+        config.store_engine = String::from("redis");
+        config.store_url = start_server();
+        RedisStore::new(&config)
+    }
+
+    fn catalog(sn: u32, sum_num: u32, ids: &[&str]) -> Catalog {
+        Catalog {
+            cmd_type: String::from("Catalog"),
+            sn,
+            device_id: String::from("34020000001180000000"),
+            sum_num,
+            device_list: DeviceList {
+                num: ids.len() as u32,
+                items: ids
+                    .iter()
+                    .map(|id| Device {
+                        device_id: id.to_string(),
+                        ..Default::default()
+                    })
+                    .collect(),
+            },
+        }
+    }
+
+    #[test]
+    fn test_redis_register_and_catalog() {
+        let store = store();
+        assert!(store.is_connected());
+
+        let gb_code = "34020000001180000000";
+        let addr = "192.168.1.10:5060".parse().unwrap();
+        assert!(store.register("z9hG4bK1", gb_code, addr, &None));
+        assert!(!store.register("z9hG4bK2", gb_code, addr, &None));
+        assert!(store.register_keep_alive(gb_code));
+
+        let device = store.find_device_by_gb_code(gb_code).unwrap();
+        assert_eq!(device.branch, "z9hG4bK1");
+        assert_eq!(device.socket_addr, addr);
+
+        assert_eq!(store.add_fetch_global_sn(), 1);
+        assert_eq!(store.add_fetch_global_sn(), 2);
+        store.set_global_sn(10);
+        assert_eq!(store.add_fetch_global_sn(), 11);
+
+        let state = store.save_catalog(gb_code, catalog(1, 3, &["a", "b"]));
+        assert_eq!(state.status, CatalogSyncStatus::InProgress);
+        let state = store.save_catalog(gb_code, catalog(1, 3, &["b", "c"]));
+        assert_eq!(state.status, CatalogSyncStatus::Complete);
+        assert_eq!(state.received, 3);
+        assert_eq!(store.find_catalog(gb_code).unwrap().len(), 3);
+        assert_eq!(
+            store.find_gb_code_by_channel_id("c").as_deref(),
+            Some(gb_code)
+        );
+        assert!(store.apply_catalog_event(
+            gb_code,
+            CatalogEvent::Del,
+            &Device {
+                device_id: String::from("c"),
+                ..Default::default()
+            }
+        ));
+        assert!(store.find_gb_code_by_channel_id("c").is_none());
+
+        let devices = store.list_devices();
+        assert_eq!(devices.len(), 1);
+        assert_eq!(devices[0].transport, "UDP");
+        assert_eq!(devices[0].channel_num, 2);
+
+        assert!(store.unregister(gb_code));
+        assert!(store.find_device_record(gb_code).is_none());
+        assert!(store.list_devices().is_empty());
+        assert!(store.find_gb_code_by_channel_id("a").is_none());
+    }
+
+    #[test]
+    fn test_redis_device_info_and_status() {
+        let store = store();
+        let gb_code = "34020000001180000000";
+        store.register("", gb_code, "192.168.1.10:5060".parse().unwrap(), &None);
+
+        let info = DeviceInfo {
+            device_id: gb_code.to_string(),
+            result: String::from("OK"),
+            manufacturer: String::from("Hikvision"),
+            model: String::from("DS-7808N"),
+            ..Default::default()
+        };
+        let status = DeviceStatus {
+            device_id: gb_code.to_string(),
+            result: String::from("OK"),
+            online: String::from("ONLINE"),
+            record: String::from("ON"),
+            ..Default::default()
+        };
+        // 通道的应答与失败的应答不落库
+        let channel_id = String::from("34020000001320000001");
+        assert!(!store.save_device_info(
+            gb_code,
+            &DeviceInfo {
+                device_id: channel_id.clone(),
+                ..info.clone()
+            }
+        ));
+        assert!(!store.save_device_info(
+            gb_code,
+            &DeviceInfo {
+                result: String::from("ERROR"),
+                ..info.clone()
+            }
+        ));
+        assert!(!store.save_device_status(
+            gb_code,
+            &DeviceStatus {
+                device_id: channel_id,
+                ..status.clone()
+            }
+        ));
+        assert!(!store.save_device_status(
+            gb_code,
+            &DeviceStatus {
+                result: String::from("ERROR"),
+                ..status.clone()
+            }
+        ));
+        let device = store.find_device_record(gb_code).unwrap();
+        assert!(device.manufacturer.is_empty());
+        assert!(device.status.online.is_empty());
+
+        assert!(store.save_device_info(gb_code, &info));
+        assert!(store.save_device_status(gb_code, &status));
+        let device = store.find_device_record(gb_code).unwrap();
+        assert_eq!(device.manufacturer, "Hikvision");
+        assert_eq!(device.model, "DS-7808N");
+        assert_eq!(device.status.online, "ONLINE");
+        assert_eq!(device.status.record, "ON");
+    }
+
+    #[test]
+    fn test_redis_counter_without_server() {
+        let addr = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let mut config: Config = serde_yaml::from_str("{}").unwrap();
+        config.store_url = format!("redis://{}/", addr);
+        let store = RedisStore::new(&config);
+
+        // redis 不可用时不会返回 0 或重复的 SN
+        store.set_global_sn(10);
+        assert_eq!(store.add_fetch_global_sn(), 11);
+        assert_eq!(store.add_fetch_global_sn(), 12);
+        assert_eq!(store.add_fetch_global_sequence(), 1);
+    }
+
+    #[test]
+    fn test_redis_invite_bye_and_expire() {
+        let store = store();
+        let gb_code = "34020000001180000000";
+        let addr = "192.168.1.10:5060".parse().unwrap();
+        assert!(store.register("z9hG4bK1", gb_code, addr, &None));
+
+        let first = store
+            .invite(gb_code, "34020000001320000001", "call-1", "tag-1", true)
+            .unwrap();
+        assert!(!first.success);
+        // 同一通道共享会话, 其他通道单独建立
+        let shared = store
+            .invite(gb_code, "34020000001320000001", "call-2", "tag-2", true)
+            .unwrap();
+        assert!(shared.success);
+        assert_eq!(shared.stream_id, first.stream_id);
+        let second = store
+            .invite(gb_code, "34020000001320000002", "call-3", "tag-3", true)
+            .unwrap();
+        assert!(!second.success);
+        assert_eq!(second.stream_id, first.stream_id + 1);
+
+        store.update_stream_tag_info("tag-1", "to-tag-1");
+        assert_eq!(
+            store.find_gb_code_by_caller_id("call-1").as_deref(),
+            Some(gb_code)
+        );
+        assert!(store.stream_keep_alive(gb_code, first.stream_id));
+
+        assert!(!store.bye(gb_code, first.stream_id).unwrap().success);
+        let bye = store.bye(gb_code, first.stream_id).unwrap();
+        assert!(bye.success);
+        assert_eq!(bye.to_tag, "to-tag-1");
+        assert!(store.find_gb_code_by_caller_id("call-1").is_none());
+        let again = store
+            .invite(gb_code, "34020000001320000001", "call-4", "tag-4", true)
+            .unwrap();
+        assert!(!again.success);
+
+        // 模拟 redis 推送的过期事件, 事件到达时键已被删除
+        store.update_stream_tag_info("tag-3", "to-tag-3");
+        let (devices_sender, devices_receiver) = std::sync::mpsc::channel();
+        let (streams_sender, streams_receiver) = std::sync::mpsc::channel();
+        store.exec(|conn| {
+            conn.del::<_, ()>(&[stream_key(second.stream_id), device_key(gb_code)])?;
+            on_key_expired(
+                conn,
+                &stream_key(second.stream_id),
+                &store.tcp_stream_writers,
+                &devices_sender,
+                &streams_sender,
+            )?;
+            on_key_expired(
+                conn,
+                &device_key(gb_code),
+                &store.tcp_stream_writers,
+                &devices_sender,
+                &streams_sender,
+            )
+        });
+        assert_eq!(
+            streams_receiver.try_recv().unwrap(),
+            Some((gb_code.to_string(), second.stream_id))
+        );
+        assert_eq!(
+            devices_receiver.try_recv().unwrap(),
+            Some(gb_code.to_string())
+        );
+
+        // 流和设备都已过期, 仍能按建立会话时的对话向设备发 BYE
+        let bye = store.bye_all(gb_code, second.stream_id).unwrap();
+        assert!(bye.success);
+        assert_eq!(bye.caller_id, "call-3");
+        assert_eq!(bye.from_tag, "tag-3");
+        assert_eq!(bye.to_tag, "to-tag-3");
+        assert_eq!(bye.branch, "z9hG4bK1");
+        assert_eq!(bye.socket_addr, addr);
+        assert!(store.bye_all(gb_code, second.stream_id).is_none());
+    }
+
+    #[test]
+    fn test_redis_talk_sessions() {
+        let store = store();
+        let talk = |caller_id: &str, stream_id: u32| TalkSession {
+            caller_id: caller_id.to_string(),
+            gb_code: String::from("34020000001180000000"),
+            stream_id,
+        };
+        assert!(store.save_talk(&talk("call-1", 1)));
+        assert!(store.save_talk(&talk("call-2", 2)));
+
+        // 设备挂断只取出一次, 流关闭按 (gb_code, stream_id) 删除
+        assert_eq!(
+            store.find_talk("34020000001180000000", 2),
+            Some(talk("call-2", 2))
+        );
+        assert_eq!(store.find_talk("34020000001180000000", 3), None);
+        assert_eq!(store.take_talk("call-1"), Some(talk("call-1", 1)));
+        assert_eq!(store.take_talk("call-1"), None);
+        assert!(store.remove_talk("34020000001180000000", 2));
+        assert!(!store.remove_talk("34020000001180000000", 2));
+        assert_eq!(store.take_talk("call-2"), None);
+    }
+
+    #[test]
+    fn test_redis_alarm_history() {
+        let mut store = store();
+        store.alarm_history_size = 2;
+        for (gb_code, ts) in [("1", 10), ("2", 20), ("1", 30)] {
+            assert!(store.save_alarm(&AlarmRecord {
+                gb_code: gb_code.to_string(),
+                longitude: Some(120.5),
+                ts,
+                ..Default::default()
+            }));
+        }
+
+        let alarms = store.list_alarms("", 0, 0, 10);
+        assert_eq!(alarms.iter().map(|a| a.ts).collect::<Vec<_>>(), [30, 20]);
+        assert_eq!(alarms[0].longitude, Some(120.5));
+        assert_eq!(store.list_alarms("2", 0, 0, 10).len(), 1);
+        assert_eq!(store.list_alarms("", 25, 0, 10).len(), 1);
+    }
+
+    #[test]
+    fn test_redis_track() {
+        let mut store = store();
+        store.track_history_size = 2;
+        for (gb_code, ts) in [("1", 10), ("2", 15), ("1", 20), ("1", 30)] {
+            assert!(store.save_track_point(&TrackPoint {
+                gb_code: gb_code.to_string(),
+                longitude: 120.5,
+                latitude: 30.5,
+                ts,
+                ..Default::default()
+            }));
+        }
+
+        let track = store.list_track("1", 0, 0);
+        assert_eq!(track.iter().map(|p| p.ts).collect::<Vec<_>>(), [20, 30]);
+        assert_eq!(track[0].longitude, 120.5);
+        assert_eq!(store.list_track("2", 0, 0).len(), 1);
+        assert_eq!(store.list_track("1", 0, 25).len(), 1);
+    }
+}
@@ -38,6 +38,93 @@ pub struct Config {
     pub stream_timeout_seconds: u32,
     #[serde(default = "default_device_timeout_seconds")]
     pub device_timeout_seconds: u32,
+    #[serde(default = "default_response_timeout_seconds")]
+    pub response_timeout_seconds: u32,
+    #[serde(default = "default_invite_timeout_seconds")]
+    pub invite_timeout_seconds: u32,
+    #[serde(default = "default_catalog_timeout_seconds")]
+    pub catalog_timeout_seconds: u32,
+    // 设备注册后发起目录订阅的有效期, 0 表示不订阅
+    #[serde(default = "default_catalog_subscribe_expires")]
+    pub catalog_subscribe_expires: u32,
+    #[serde(default)]
+    pub upstream_platforms: Vec<UpstreamPlatform>,
+    #[serde(default)]
+    pub media_nodes: Vec<MediaNode>,
+    // round_robin 或 least_connections
+    #[serde(default = "default_media_balance")]
+    pub media_balance: String,
+    #[serde(default = "default_media_heartbeat_seconds")]
+    pub media_heartbeat_seconds: u32,
+    #[serde(default = "default_media_node_timeout_seconds")]
+    pub media_node_timeout_seconds: u32,
+    // 报警订阅默认有效期
+    #[serde(default = "default_alarm_subscribe_expires")]
+    pub alarm_subscribe_expires: u32,
+    // 内存/redis 存储保留的最近报警条数, SQL 存储按同样条数裁剪
+    #[serde(default = "default_alarm_history_size")]
+    pub alarm_history_size: u32,
+    // 移动位置订阅默认的上报间隔与有效期
+    #[serde(default = "default_mobile_position_interval")]
+    pub mobile_position_interval: u32,
+    #[serde(default = "default_mobile_position_subscribe_expires")]
+    pub mobile_position_subscribe_expires: u32,
+    // 每台设备保留的最近轨迹点数
+    #[serde(default = "default_track_history_size")]
+    pub track_history_size: u32,
+    // 录像目录, 按 gb_code/channel_id 分目录存放
+    #[serde(default = "default_record_dir")]
+    pub record_dir: String,
+    // mp4 (fragmented) 或 flv
+    #[serde(default = "default_record_format")]
+    pub record_format: String,
+    // 单个文件时长, 到时后在下个关键帧切换文件
+    #[serde(default = "default_record_segment_seconds")]
+    pub record_segment_seconds: u32,
+    // 录像保留时长, 0 表示不清理
+    #[serde(default = "default_record_retention_seconds")]
+    pub record_retention_seconds: u32,
+}
+
+// 流媒体节点
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MediaNode {
+    pub id: String,
+    // 节点类型: zlm 或 embedded(内置收流, 只做统计)
+    #[serde(default = "default_media_kind")]
+    pub kind: String,
+    // 写入 SDP 的收流地址
+    pub ip: String,
+    // 节点 HTTP API 地址, 如 http://127.0.0.1:80, embedded 节点不需要
+    #[serde(default)]
+    pub api_url: String,
+    #[serde(default)]
+    pub secret: String,
+    // 加权轮询的权重
+    #[serde(default = "default_media_weight")]
+    pub weight: u32,
+    // 收流端口范围, 只使用其中的偶数端口
+    #[serde(default = "default_rtp_port_min")]
+    pub rtp_port_min: u16,
+    #[serde(default = "default_rtp_port_max")]
+    pub rtp_port_max: u16,
+}
+
+// 级联上级平台
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpstreamPlatform {
+    // 上级平台国标编码
+    pub id: String,
+    pub domain: String,
+    pub ip: String,
+    #[serde(default = "default_sip_port")]
+    pub port: u16,
+    // 本平台在上级注册使用的密码
+    pub password: String,
+    #[serde(default = "default_register_expires")]
+    pub register_expires: u32,
+    #[serde(default = "default_keep_alive_interval")]
+    pub keep_alive_interval: u32,
 }
 
 fn default_store_engine() -> String {
@@ -104,6 +191,94 @@ fn default_device_timeout_seconds() -> u32 {
     300
 }
 
+fn default_response_timeout_seconds() -> u32 {
+    5
+}
+
+fn default_invite_timeout_seconds() -> u32 {
+    10
+}
+
+fn default_catalog_timeout_seconds() -> u32 {
+    30
+}
+
+fn default_catalog_subscribe_expires() -> u32 {
+    3600
+}
+
+fn default_media_kind() -> String {
+    "zlm".to_string()
+}
+
+fn default_media_weight() -> u32 {
+    1
+}
+
+fn default_rtp_port_min() -> u16 {
+    30000
+}
+
+fn default_rtp_port_max() -> u16 {
+    30500
+}
+
+fn default_media_balance() -> String {
+    "round_robin".to_string()
+}
+
+fn default_media_heartbeat_seconds() -> u32 {
+    10
+}
+
+fn default_media_node_timeout_seconds() -> u32 {
+    30
+}
+
+fn default_alarm_subscribe_expires() -> u32 {
+    3600
+}
+
+fn default_alarm_history_size() -> u32 {
+    10000
+}
+
+fn default_mobile_position_interval() -> u32 {
+    5
+}
+
+fn default_mobile_position_subscribe_expires() -> u32 {
+    3600
+}
+
+fn default_track_history_size() -> u32 {
+    10000
+}
+
+fn default_record_dir() -> String {
+    String::from("./record")
+}
+
+fn default_record_format() -> String {
+    String::from("mp4")
+}
+
+fn default_record_segment_seconds() -> u32 {
+    300
+}
+
+fn default_record_retention_seconds() -> u32 {
+    7 * 24 * 3600
+}
+
+fn default_register_expires() -> u32 {
+    3600
+}
+
+fn default_keep_alive_interval() -> u32 {
+    60
+}
+
 impl Config {
     // 从YAML文件加载配置
     pub fn load_from_file(path: &PathBuf) -> Result<Self, Box<dyn std::error::Error>> {
@@ -120,6 +295,16 @@ impl Config {
             let mem = sys.total_memory() as f64 / (1024.0 * 1024.0 * 1024.0);
             config.store_url = format!("memory://main?total={}g", mem.round() as u64);
         }
+
+        for node in &config.media_nodes {
+            if node.rtp_port_min > node.rtp_port_max {
+                return Err(format!(
+                    "media node {}: rtp_port_min {} > rtp_port_max {}",
+                    node.id, node.rtp_port_min, node.rtp_port_max
+                )
+                .into());
+            }
+        }
         Ok(config)
     }
 